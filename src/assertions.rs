@@ -0,0 +1,48 @@
+//! Checking at compile time that the public types can be shared across tasks
+
+#[cfg(feature = "discord")]
+use twilight_http::Client;
+
+#[cfg(feature = "tracing")]
+use crate::ErrorLayer;
+#[cfg(feature = "log")]
+use crate::ErrorLogger;
+#[cfg(feature = "test-util")]
+use crate::{CaptureSink, SentMessage};
+use crate::{ErrorHandler, ErrorRecord, ScopedHandler, Stats, TagRoute};
+#[cfg(feature = "discord")]
+use crate::{ErrorReport, Severity};
+
+/// Fails to compile if the type isn't [`Send`] and [`Sync`]
+const fn assert_send_sync<T: Send + Sync>() {}
+
+const _: () = {
+    assert_send_sync::<ErrorHandler>();
+    assert_send_sync::<ScopedHandler>();
+    assert_send_sync::<ErrorRecord>();
+    assert_send_sync::<Stats>();
+    assert_send_sync::<TagRoute>();
+    #[cfg(feature = "log")]
+    assert_send_sync::<ErrorLogger>();
+    #[cfg(feature = "tracing")]
+    assert_send_sync::<ErrorLayer>();
+    #[cfg(feature = "test-util")]
+    assert_send_sync::<CaptureSink>();
+    #[cfg(feature = "test-util")]
+    assert_send_sync::<SentMessage>();
+};
+
+/// Fails to compile if the future isn't [`Send`]
+#[cfg(feature = "discord")]
+const fn assert_send<T: Send>(_: &T) {}
+
+/// Fails to compile if the futures of handling errors can't be spawned on a
+/// multi-threaded runtime
+#[cfg(feature = "discord")]
+#[allow(dead_code)]
+fn assert_send_futures(handler: &ErrorHandler, scoped: &ScopedHandler, http: &Client) {
+    assert_send(&handler.handle(http, ""));
+    assert_send(&handler.handle_with_severity(http, Severity::Warning, ""));
+    assert_send(&handler.handle_report(http, ErrorReport::new("")));
+    assert_send(&scoped.handle_report(http, ErrorReport::new("")));
+}
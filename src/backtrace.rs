@@ -0,0 +1,88 @@
+//! Including the backtrace of where errors are handled
+
+use std::backtrace::{Backtrace, BacktraceStatus};
+
+#[cfg(feature = "discord")]
+use twilight_model::http::attachment::Attachment;
+
+use crate::ErrorHandler;
+
+/// The name of the file the backtrace is attached to Discord messages as
+#[cfg(feature = "discord")]
+pub const BACKTRACE_FILENAME: &str = "backtrace.txt";
+
+/// The prefixes of the frames removed from the backtrace appended to the file
+#[cfg(feature = "file")]
+const TRIMMED_FRAMES: [&str; 6] = [
+    "std::",
+    "core::",
+    "alloc::",
+    "tokio::",
+    "__rust",
+    env!("CARGO_CRATE_NAME"),
+];
+
+/// Returns the backtrace of the caller if it's enabled with `RUST_BACKTRACE`
+pub(crate) fn capture() -> Option<String> {
+    let backtrace = Backtrace::capture();
+    (backtrace.status() == BacktraceStatus::Captured).then(|| backtrace.to_string())
+}
+
+/// Returns the backtrace without the frames of the standard library, Tokio
+/// and this crate
+#[cfg(feature = "file")]
+pub(crate) fn trim(backtrace: &str) -> String {
+    let mut trimmed = String::new();
+    let mut keep = true;
+
+    for line in backtrace.lines() {
+        if let Some((index, frame)) = line.trim_start().split_once(": ") {
+            if index.bytes().all(|byte| byte.is_ascii_digit()) {
+                let path = frame.trim_start_matches(['<', '&']);
+                let symbol = path.strip_prefix("dyn ").unwrap_or(path);
+                keep = !TRIMMED_FRAMES
+                    .iter()
+                    .any(|prefix| symbol.starts_with(prefix));
+            }
+        }
+
+        if keep {
+            trimmed.push_str(line);
+            trimmed.push('\n');
+        }
+    }
+
+    trimmed
+}
+
+/// Returns the attachments of the backtrace for Discord messages
+#[cfg(feature = "discord")]
+pub(crate) fn attachments(backtrace: Option<&str>) -> Vec<Attachment> {
+    backtrace
+        .map(|trace| {
+            Attachment::from_bytes(BACKTRACE_FILENAME.to_owned(), trace.as_bytes().to_vec(), 0)
+        })
+        .into_iter()
+        .collect()
+}
+
+impl ErrorHandler {
+    /// Set whether to capture the backtrace of where errors are handled
+    ///
+    /// The backtrace is only captured if it's enabled with `RUST_BACKTRACE`
+    /// or `RUST_LIB_BACKTRACE`, the full backtrace is attached to Discord
+    /// messages as [`BACKTRACE_FILENAME`] and a trimmed version is appended to
+    /// the file, defaults to `false`
+    pub fn capture_backtraces(&mut self, capture: bool) -> &mut Self {
+        self.config_mut().capture_backtraces = capture;
+        self
+    }
+
+    /// Change whether to capture the backtrace of where errors are handled at
+    /// runtime
+    ///
+    /// Same as [`Self::capture_backtraces`] but through a shared reference
+    pub fn set_capture_backtraces(&self, capture: bool) {
+        self.update_config(|config| config.capture_backtraces = capture);
+    }
+}
@@ -0,0 +1,71 @@
+//! Delivering to the Discord sinks from code that isn't async
+
+use core::fmt::Display;
+use std::{sync::Arc, thread};
+
+use tokio::runtime::{Builder, Handle};
+use twilight_http::Client;
+
+use crate::{ErrorHandler, ErrorId, ErrorReport};
+
+impl ErrorHandler {
+    /// Set the runtime and client to deliver to the Discord sinks with in
+    /// [`Self::handle_blocking`]
+    ///
+    /// If it's a current-thread runtime, another thread needs to be running
+    /// it for the requests to make progress
+    pub fn blocking_runtime(&mut self, runtime: Handle, http: Arc<Client>) -> &mut Self {
+        self.config_mut().blocking_runtime = Some((runtime, http));
+        self
+    }
+
+    /// Change the runtime and client used in [`Self::handle_blocking`] at
+    /// runtime, `None` to only append to the file there
+    ///
+    /// Same as [`Self::blocking_runtime`] but through a shared reference
+    pub fn set_blocking_runtime(&self, runtime: Option<(Handle, Arc<Client>)>) {
+        self.update_config(|config| config.blocking_runtime = runtime);
+    }
+
+    /// Handle an error, blocking the current thread until it's delivered to
+    /// every sink
+    ///
+    /// Unlike [`Self::handle_sync`], it's delivered to the Discord sinks if
+    /// [`Self::blocking_runtime`] was called, so it can be used in panic
+    /// hooks, `Drop` implementations or before exiting `main`, otherwise it's
+    /// the same as [`Self::handle_sync`]
+    ///
+    /// Inside a Tokio runtime, it's delivered from a separate thread with its
+    /// own runtime so that it doesn't block the runtime it's called in
+    #[track_caller]
+    pub fn handle_blocking(&self, error: impl Display) -> ErrorId {
+        self.handle_report_blocking(ErrorReport::new(error))
+    }
+
+    /// Handle an error along with information about it, blocking the current
+    /// thread until it's delivered to every sink
+    ///
+    /// Same as [`Self::handle_blocking`] but with the severity and information
+    /// set in the report
+    pub fn handle_report_blocking(&self, report: ErrorReport) -> ErrorId {
+        let Some((runtime, http)) = self.config().blocking_runtime.clone() else {
+            return self.handle_report_sync(report);
+        };
+
+        if Handle::try_current().is_err() {
+            return runtime.block_on(self.handle_report(&http, report));
+        }
+
+        thread::scope(|scope| {
+            scope
+                .spawn(
+                    || match Builder::new_current_thread().enable_all().build() {
+                        Ok(own_runtime) => own_runtime.block_on(self.handle_report(&http, report)),
+                        Err(_) => self.handle_report_sync(report),
+                    },
+                )
+                .join()
+                .unwrap_or_else(|_| ErrorId::new())
+        })
+    }
+}
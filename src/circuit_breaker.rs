@@ -0,0 +1,329 @@
+//! Temporarily disabling sinks that keep failing
+
+use core::{fmt, time::Duration};
+use std::sync::{Mutex, PoisonError};
+
+use tokio::time::Instant;
+
+/// A destination errors are delivered to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Sink {
+    /// The channel set with [`crate::ErrorHandler::channel`]
+    Channel,
+    /// The webhook set with [`crate::ErrorHandler::webhook`]
+    Webhook,
+    /// The file set with [`crate::ErrorHandler::file`]
+    File,
+}
+
+impl fmt::Display for Sink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Channel => "channel",
+            Self::Webhook => "webhook",
+            Self::File => "file",
+        })
+    }
+}
+
+/// The interval to retry sinks the bot is missing permissions to at
+#[cfg(feature = "discord")]
+pub(crate) const MISSING_PERMISSIONS_INTERVAL: Duration = Duration::from_hours(1);
+
+/// A change in whether a sink is used
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Transition {
+    /// The sink failed too many times in a row and won't be used until a probe
+    /// succeeds
+    Disabled {
+        /// The sink that was disabled
+        sink: Sink,
+        /// The number of consecutive failures that caused it
+        failures: u32,
+        /// The interval the sink will be probed at
+        probe_interval: Duration,
+    },
+    /// The bot is missing the access or permissions to use the sink, which
+    /// won't be used until a retry after [`MISSING_PERMISSIONS_INTERVAL`]
+    /// succeeds
+    #[cfg(feature = "discord")]
+    MissingPermissions(Sink),
+    /// A probe to the disabled sink succeeded
+    Enabled(Sink),
+}
+
+impl fmt::Display for Transition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Disabled {
+                sink,
+                failures,
+                probe_interval,
+            } => write!(
+                f,
+                "Disabled the {sink} after {failures} consecutive failures, retrying every {}s",
+                probe_interval.as_secs()
+            ),
+            #[cfg(feature = "discord")]
+            Self::MissingPermissions(sink) => write!(
+                f,
+                "Disabled the {sink} because the bot is missing access or permissions to it, \
+                 retrying every {}s",
+                MISSING_PERMISSIONS_INTERVAL.as_secs()
+            ),
+            Self::Enabled(sink) => write!(f, "Enabled the {sink} again after a successful retry"),
+        }
+    }
+}
+
+/// The failure tracking of a single sink
+#[derive(Clone, Copy, Debug)]
+struct SinkState {
+    /// The number of times the sink failed in a row
+    consecutive_failures: u32,
+    /// When the sink was disabled or last probed, `None` if it's enabled
+    disabled_since: Option<Instant>,
+    /// When the sink was disabled for missing permissions or last retried,
+    /// `None` if it isn't
+    #[cfg(feature = "discord")]
+    missing_permissions_since: Option<Instant>,
+}
+
+impl SinkState {
+    /// The state of a sink that hasn't failed
+    const ENABLED: Self = Self {
+        consecutive_failures: 0,
+        disabled_since: None,
+        #[cfg(feature = "discord")]
+        missing_permissions_since: None,
+    };
+}
+
+/// The failure tracking of every sink
+#[derive(Debug)]
+struct SinkStates {
+    /// The number of consecutive failures to disable a sink after, `None` to
+    /// never disable sinks
+    threshold: Option<u32>,
+    /// The interval to retry disabled sinks at
+    probe_interval: Duration,
+    /// State of [`Sink::Channel`]
+    channel: SinkState,
+    /// State of [`Sink::Webhook`]
+    webhook: SinkState,
+    /// State of [`Sink::File`]
+    file: SinkState,
+}
+
+impl SinkStates {
+    /// Returns the state of the given sink
+    const fn get_mut(&mut self, sink: Sink) -> &mut SinkState {
+        match sink {
+            Sink::Channel => &mut self.channel,
+            Sink::Webhook => &mut self.webhook,
+            Sink::File => &mut self.file,
+        }
+    }
+}
+
+/// Disables sinks after a number of consecutive failures, probing them
+/// periodically to enable them again
+#[derive(Debug)]
+pub(crate) struct CircuitBreaker {
+    /// The failure tracking of every sink
+    states: Mutex<SinkStates>,
+}
+
+impl CircuitBreaker {
+    /// Make a circuit breaker that never disables sinks
+    pub(crate) const fn new() -> Self {
+        Self {
+            states: Mutex::new(SinkStates {
+                threshold: None,
+                probe_interval: Duration::ZERO,
+                channel: SinkState::ENABLED,
+                webhook: SinkState::ENABLED,
+                file: SinkState::ENABLED,
+            }),
+        }
+    }
+
+    /// Set the number of consecutive failures to disable a sink after and the
+    /// interval to retry it at, `None` to never disable sinks
+    ///
+    /// Sinks that are already disabled stay disabled until they're probed
+    pub(crate) fn configure(&self, settings: Option<(u32, Duration)>) {
+        let mut states = self.states.lock().unwrap_or_else(PoisonError::into_inner);
+        states.threshold = settings.map(|(threshold, _)| threshold);
+        states.probe_interval = settings.map_or(Duration::ZERO, |(_, interval)| interval);
+    }
+
+    /// Returns whether the sink should be used
+    ///
+    /// Disabled sinks are allowed once every probe interval, callers must
+    /// [`Self::record`] the result afterwards
+    pub(crate) fn allows(&self, sink: Sink) -> bool {
+        let mut states = self.states.lock().unwrap_or_else(PoisonError::into_inner);
+        let probe_interval = states.probe_interval;
+        let state = states.get_mut(sink);
+
+        #[cfg(feature = "discord")]
+        if let Some(since) = state.missing_permissions_since {
+            if since.elapsed() < MISSING_PERMISSIONS_INTERVAL {
+                return false;
+            }
+            state.missing_permissions_since = Some(Instant::now());
+        }
+
+        match state.disabled_since {
+            None => true,
+            Some(since) if since.elapsed() >= probe_interval => {
+                state.disabled_since = Some(Instant::now());
+                true
+            }
+            Some(_) => false,
+        }
+    }
+
+    /// Records the result of using the sink, returning the transition it
+    /// caused if any
+    pub(crate) fn record(&self, sink: Sink, succeeded: bool) -> Option<Transition> {
+        let mut states = self.states.lock().unwrap_or_else(PoisonError::into_inner);
+        #[cfg(feature = "discord")]
+        if succeeded
+            && states
+                .get_mut(sink)
+                .missing_permissions_since
+                .take()
+                .is_some()
+        {
+            return Some(Transition::Enabled(sink));
+        }
+        let threshold = states.threshold?;
+        let probe_interval = states.probe_interval;
+        let state = states.get_mut(sink);
+
+        if succeeded {
+            state.consecutive_failures = 0;
+            return state
+                .disabled_since
+                .take()
+                .map(|_| Transition::Enabled(sink));
+        }
+
+        state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+        if state.disabled_since.is_some() || state.consecutive_failures < threshold {
+            return None;
+        }
+
+        state.disabled_since = Some(Instant::now());
+        Some(Transition::Disabled {
+            sink,
+            failures: state.consecutive_failures,
+            probe_interval,
+        })
+    }
+
+    /// Records that the bot is missing the access or permissions to use the
+    /// sink, disabling it regardless of the threshold and returning the
+    /// transition if it wasn't disabled for it already
+    #[cfg(feature = "discord")]
+    pub(crate) fn record_missing_permissions(&self, sink: Sink) -> Option<Transition> {
+        let mut states = self.states.lock().unwrap_or_else(PoisonError::into_inner);
+        states
+            .get_mut(sink)
+            .missing_permissions_since
+            .replace(Instant::now())
+            .is_none()
+            .then_some(Transition::MissingPermissions(sink))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn never_disables_without_threshold() {
+        let breaker = CircuitBreaker::new();
+        for _ in 0_u8..10 {
+            assert!(breaker.record(Sink::Channel, false).is_none());
+        }
+
+        assert!(breaker.allows(Sink::Channel));
+    }
+
+    #[test]
+    fn disables_after_consecutive_failures() {
+        let breaker = CircuitBreaker::new();
+        breaker.configure(Some((3, Duration::from_mins(1))));
+
+        assert!(breaker.record(Sink::Webhook, false).is_none());
+        assert!(breaker.record(Sink::Webhook, true).is_none());
+        assert!(breaker.record(Sink::Webhook, false).is_none());
+        assert!(breaker.record(Sink::Webhook, false).is_none());
+        let transition = breaker.record(Sink::Webhook, false);
+
+        assert!(matches!(
+            transition,
+            Some(Transition::Disabled {
+                sink: Sink::Webhook,
+                failures: 3,
+                ..
+            })
+        ));
+        assert!(!breaker.allows(Sink::Webhook));
+        assert!(breaker.allows(Sink::Channel));
+        assert!(breaker.record(Sink::Webhook, false).is_none());
+    }
+
+    #[test]
+    fn probe_enables_again() {
+        let breaker = CircuitBreaker::new();
+        breaker.configure(Some((1, Duration::ZERO)));
+        breaker.record(Sink::File, false);
+
+        assert!(breaker.allows(Sink::File));
+        assert!(matches!(
+            breaker.record(Sink::File, true),
+            Some(Transition::Enabled(Sink::File))
+        ));
+        assert!(breaker.record(Sink::File, true).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "discord")]
+    fn missing_permissions_disables_once() {
+        let breaker = CircuitBreaker::new();
+
+        assert!(matches!(
+            breaker.record_missing_permissions(Sink::Channel),
+            Some(Transition::MissingPermissions(Sink::Channel))
+        ));
+        assert!(breaker.record_missing_permissions(Sink::Channel).is_none());
+        assert!(!breaker.allows(Sink::Channel));
+        assert!(matches!(
+            breaker.record(Sink::Channel, true),
+            Some(Transition::Enabled(Sink::Channel))
+        ));
+    }
+
+    #[test]
+    fn transitions_are_described() {
+        let disabled = Transition::Disabled {
+            sink: Sink::Channel,
+            failures: 5,
+            probe_interval: Duration::from_secs(30),
+        };
+
+        assert_eq!(
+            disabled.to_string(),
+            "Disabled the channel after 5 consecutive failures, retrying every 30s"
+        );
+        assert_eq!(
+            Transition::Enabled(Sink::Webhook).to_string(),
+            "Enabled the webhook again after a successful retry"
+        );
+    }
+}
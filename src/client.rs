@@ -0,0 +1,56 @@
+//! Delivering through a stored client unless another one is given
+
+use core::{fmt::Display, future::Future};
+use std::sync::Arc;
+
+use twilight_http::Client;
+
+use crate::{ErrorHandler, ErrorId, ErrorReport};
+
+impl ErrorHandler {
+    /// Set the client to deliver to the Discord sinks with when
+    /// [`Self::handle_with`] isn't given one
+    pub fn default_client(&mut self, http: Arc<Client>) -> &mut Self {
+        self.config_mut().default_client = Some(http);
+        self
+    }
+
+    /// Change the client used when [`Self::handle_with`] isn't given one at
+    /// runtime, `None` to only print and append to the file then
+    ///
+    /// Same as [`Self::default_client`] but through a shared reference
+    pub fn set_default_client(&self, http: Option<Arc<Client>>) {
+        self.update_config(|config| config.default_client = http);
+    }
+
+    /// Handle an error through the given client, or the one of
+    /// [`Self::default_client`] if it's `None`, such as to report some
+    /// errors through a separate alerts-only bot account
+    ///
+    /// If it's `None` and there's no default client, it's the same as
+    /// [`Self::handle_sync`], otherwise it's the same as [`Self::handle`]
+    #[track_caller]
+    pub fn handle_with<'a>(
+        &'a self,
+        http: Option<&'a Client>,
+        error: impl Display + Send,
+    ) -> impl Future<Output = ErrorId> + Send + 'a {
+        self.handle_report_with(http, ErrorReport::new(error))
+    }
+
+    /// Handle an error along with information about it through the given
+    /// client, or the default one if it's `None`
+    ///
+    /// Same as [`Self::handle_with`] but with the severity and information set
+    /// in the report
+    pub async fn handle_report_with(&self, http: Option<&Client>, report: ErrorReport) -> ErrorId {
+        if let Some(client) = http {
+            return self.handle_report(client, report).await;
+        }
+
+        let Some(default) = self.config().default_client.clone() else {
+            return self.handle_report_sync(report);
+        };
+        self.handle_report(&default, report).await
+    }
+}
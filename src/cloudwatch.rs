@@ -0,0 +1,822 @@
+//! Pushing errors to an AWS log stream
+
+use core::{fmt, time::Duration};
+use std::{
+    collections::VecDeque,
+    env,
+    io::{self, ErrorKind},
+    sync::{Arc, Mutex, OnceLock, PoisonError},
+    time::UNIX_EPOCH,
+};
+
+use chrono::{DateTime, Utc};
+use hyper::{body, client::HttpConnector, Body, Client, Request, StatusCode};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use serde_json::{json, Value};
+use tokio::{sync::Mutex as AsyncMutex, task::JoinHandle, time};
+
+use crate::{
+    config::Config,
+    delivery,
+    signing::{hex, hmac_sha256, sha256_hex},
+    ErrorHandler, ErrorRecord,
+};
+
+/// The most events AWS accepts in a batch
+const MAX_BATCH_EVENTS: usize = 10_000;
+
+/// The most bytes AWS accepts in a batch, 1 MiB counting [`EVENT_OVERHEAD`]
+/// for each event
+const MAX_BATCH_BYTES: usize = 0x0010_0000;
+
+/// The bytes AWS counts for each event in addition to its message
+const EVENT_OVERHEAD: usize = 26;
+
+/// The most bytes of the message of an event, 256 KiB with the overhead
+const MAX_EVENT_BYTES: usize = 0x0004_0000 - EVENT_OVERHEAD;
+
+/// The longest time between the events of a batch AWS accepts, 24 hours in
+/// milliseconds
+const MAX_BATCH_SPAN_MILLIS: i64 = 86_400_000;
+
+/// The most events kept until they're pushed, the oldest ones are dropped
+/// after
+const MAX_BUFFERED: usize = 100_000;
+
+/// The most bytes of events kept until they're pushed, 16 MiB, the oldest ones
+/// are dropped after
+const MAX_BUFFERED_BYTES: usize = 0x0100_0000;
+
+/// The value of the `X-Amz-Target` header to put log events
+const PUT_LOG_EVENTS_TARGET: &str = "Logs_20140328.PutLogEvents";
+
+/// The content type of the requests to the AWS logs service
+const CONTENT_TYPE: &str = "application/x-amz-json-1.1";
+
+/// The credentials requests to AWS are signed with
+#[derive(Clone)]
+pub struct AwsCredentials {
+    /// The ID of the access key
+    access_key_id: String,
+    /// The secret of the access key
+    secret_access_key: String,
+    /// The token of temporary credentials
+    session_token: Option<String>,
+}
+
+impl AwsCredentials {
+    /// Make credentials with the access key
+    pub fn new(access_key_id: impl Into<String>, secret_access_key: impl Into<String>) -> Self {
+        Self {
+            access_key_id: access_key_id.into(),
+            secret_access_key: secret_access_key.into(),
+            session_token: None,
+        }
+    }
+
+    /// Set the session token of temporary credentials, such as the ones of an
+    /// IAM role
+    #[must_use]
+    pub fn session_token(mut self, token: impl Into<String>) -> Self {
+        self.session_token = Some(token.into());
+        self
+    }
+
+    /// Returns the credentials in the `AWS_ACCESS_KEY_ID`,
+    /// `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN` environment variables,
+    /// `None` if the first two aren't set
+    #[must_use]
+    pub fn from_env() -> Option<Self> {
+        let credentials = Self::new(
+            env::var("AWS_ACCESS_KEY_ID").ok()?,
+            env::var("AWS_SECRET_ACCESS_KEY").ok()?,
+        );
+        Some(match env::var("AWS_SESSION_TOKEN") {
+            Ok(token) => credentials.session_token(token),
+            Err(_) => credentials,
+        })
+    }
+}
+
+impl fmt::Debug for AwsCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AwsCredentials")
+            .field("access_key_id", &self.access_key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An AWS log stream to push errors to, set with
+/// [`ErrorHandler::cloudwatch`]
+#[derive(Clone, Debug)]
+pub struct CloudWatchStream {
+    /// The region of the log group, such as `eu-west-1`
+    region: String,
+    /// The name of the log group
+    log_group: String,
+    /// The name of the log stream in the group
+    log_stream: String,
+    /// The credentials the requests are signed with
+    credentials: AwsCredentials,
+}
+
+impl CloudWatchStream {
+    /// Make a stream of the log group in the region, the group and the
+    /// stream have to exist and the credentials need the
+    /// `logs:PutLogEvents` permission
+    pub fn new(
+        region: impl Into<String>,
+        log_group: impl Into<String>,
+        log_stream: impl Into<String>,
+        credentials: AwsCredentials,
+    ) -> Self {
+        Self {
+            region: region.into(),
+            log_group: log_group.into(),
+            log_stream: log_stream.into(),
+            credentials,
+        }
+    }
+
+    /// Returns the host of the AWS logs service in the region
+    fn host(&self) -> String {
+        format!("logs.{}.amazonaws.com", self.region)
+    }
+}
+
+/// A log event of an error
+#[derive(Clone, Debug)]
+struct LogEvent {
+    /// When the error was handled in milliseconds since the Unix epoch
+    timestamp: i64,
+    /// The record of the error as JSON
+    message: String,
+}
+
+impl LogEvent {
+    /// Returns the bytes AWS counts for the event
+    const fn size(&self) -> usize {
+        self.message.len().saturating_add(EVENT_OVERHEAD)
+    }
+}
+
+/// The events waiting to be pushed along with their size
+#[derive(Default)]
+struct Events {
+    /// The events, oldest first
+    queue: VecDeque<LogEvent>,
+    /// The bytes AWS counts for the events
+    bytes: usize,
+}
+
+impl Events {
+    /// Removes and returns the oldest event
+    fn pop_front(&mut self) -> Option<LogEvent> {
+        let event = self.queue.pop_front()?;
+        self.bytes = self.bytes.saturating_sub(event.size());
+        Some(event)
+    }
+
+    /// Drops the oldest events until there aren't too many of them
+    fn cap(&mut self) {
+        while self.queue.len() > MAX_BUFFERED || self.bytes > MAX_BUFFERED_BYTES {
+            self.pop_front();
+        }
+    }
+}
+
+/// The events waiting to be pushed and the state of the stream
+#[derive(Default)]
+pub(crate) struct CloudWatchBuffer {
+    /// The events waiting to be pushed
+    events: Mutex<Events>,
+    /// The sequence token of the next batch, held while pushing
+    sequence_token: AsyncMutex<Option<String>>,
+    /// The client the requests are sent with
+    client: OnceLock<Client<HttpsConnector<HttpConnector>>>,
+}
+
+impl fmt::Debug for CloudWatchBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CloudWatchBuffer")
+            .field(
+                "buffered",
+                &self
+                    .events
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .queue
+                    .len(),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl CloudWatchBuffer {
+    /// Adds the event, dropping the oldest ones if there are too many
+    fn push(&self, event: LogEvent) {
+        let mut events = self.events.lock().unwrap_or_else(PoisonError::into_inner);
+        events.bytes = events.bytes.saturating_add(event.size());
+        events.queue.push_back(event);
+        events.cap();
+    }
+
+    /// Removes and returns the oldest events that fit in a batch, sorted by
+    /// their timestamp
+    ///
+    /// The batch ends before an event that would make it span more than 24
+    /// hours
+    fn take_batch(&self) -> Vec<LogEvent> {
+        let mut events = self.events.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut batch = Vec::new();
+        let mut bytes = 0_usize;
+        let mut span: Option<(i64, i64)> = None;
+        while let Some(event) = events.queue.front() {
+            let with_event = bytes.saturating_add(event.size());
+            let span_with_event = span
+                .map_or((event.timestamp, event.timestamp), |(first, last)| {
+                    (first.min(event.timestamp), last.max(event.timestamp))
+                });
+            if batch.len() >= MAX_BATCH_EVENTS
+                || with_event > MAX_BATCH_BYTES
+                || span_with_event.1.saturating_sub(span_with_event.0) > MAX_BATCH_SPAN_MILLIS
+            {
+                break;
+            }
+            bytes = with_event;
+            span = Some(span_with_event);
+            batch.extend(events.pop_front());
+        }
+
+        batch.sort_by_key(|event| event.timestamp);
+        batch
+    }
+
+    /// Puts the batch back to be pushed again, dropping the newest events if
+    /// there are too many
+    fn requeue(&self, batch: Vec<LogEvent>) {
+        let mut events = self.events.lock().unwrap_or_else(PoisonError::into_inner);
+        for event in batch.into_iter().rev() {
+            events.bytes = events.bytes.saturating_add(event.size());
+            events.queue.push_front(event);
+        }
+        while events.queue.len() > MAX_BUFFERED || events.bytes > MAX_BUFFERED_BYTES {
+            let Some(event) = events.queue.pop_back() else {
+                break;
+            };
+            events.bytes = events.bytes.saturating_sub(event.size());
+        }
+    }
+
+    /// Returns the client, building it the first time
+    fn client(&self) -> &Client<HttpsConnector<HttpConnector>> {
+        self.client.get_or_init(|| {
+            Client::builder().build(
+                HttpsConnectorBuilder::new()
+                    .with_native_roots()
+                    .https_only()
+                    .enable_http1()
+                    .build(),
+            )
+        })
+    }
+}
+
+/// The result of putting a batch that isn't a success
+enum PutError {
+    /// The sequence token isn't the expected one
+    InvalidSequenceToken(Option<String>),
+    /// The batch was already accepted with the sequence token
+    DataAlreadyAccepted(Option<String>),
+    /// Sending the request or AWS failed, with [`ErrorKind::InvalidInput`]
+    /// if it won't succeed on a retry
+    Failed(io::Error),
+}
+
+impl Config {
+    /// Buffers the record to be pushed to AWS if it's set
+    pub(crate) fn buffer_cloudwatch(&self, buffer: &CloudWatchBuffer, record: &ErrorRecord) {
+        if self.cloudwatch.is_none() {
+            return;
+        }
+
+        let Some(message) = event_message(record) else {
+            self.print_notice("Failed to buffer an error for CloudWatch, its record is too large");
+            return;
+        };
+        let timestamp = record
+            .handled_at
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| {
+                i64::try_from(since.as_millis()).unwrap_or(i64::MAX)
+            });
+
+        buffer.push(LogEvent { timestamp, message });
+    }
+}
+
+/// Returns the record as JSON, with its error shortened if it's longer than
+/// AWS accepts, `None` if it's still too long
+fn event_message(record: &ErrorRecord) -> Option<String> {
+    let message = serde_json::to_string(record).ok()?;
+    let excess = message.len().saturating_sub(MAX_EVENT_BYTES);
+    if excess == 0 {
+        return Some(message);
+    }
+
+    // escaping only makes the removed characters longer in the JSON, removing
+    // the excess and the length of the ellipsis from the error is enough
+    let mut shortened = record.clone();
+    let keep = shortened
+        .error
+        .len()
+        .checked_sub(excess.saturating_add(3))?;
+    let end = (0..=keep)
+        .rev()
+        .find(|index| shortened.error.is_char_boundary(*index))
+        .unwrap_or(0);
+    shortened.error.truncate(end);
+    shortened.error.push_str("...");
+
+    serde_json::to_string(&shortened)
+        .ok()
+        .filter(|shortened_message| shortened_message.len() <= MAX_EVENT_BYTES)
+}
+
+/// Puts the batch to the stream with the sequence token, returning the next
+/// sequence token
+async fn put_log_events(
+    client: &Client<HttpsConnector<HttpConnector>>,
+    stream: &CloudWatchStream,
+    batch: &[LogEvent],
+    sequence_token: Option<&str>,
+) -> Result<Option<String>, PutError> {
+    let mut payload = json!({
+        "logGroupName": stream.log_group,
+        "logStreamName": stream.log_stream,
+        "logEvents": batch
+            .iter()
+            .map(|event| json!({ "timestamp": event.timestamp, "message": event.message }))
+            .collect::<Vec<_>>(),
+    });
+    if let (Some(token), Value::Object(object)) = (sequence_token, &mut payload) {
+        object.insert("sequenceToken".to_owned(), json!(token));
+    }
+    let request_body = payload.to_string();
+
+    let response = client
+        .request(signed_request(stream, request_body, Utc::now()).map_err(PutError::Failed)?)
+        .await
+        .map_err(|err| PutError::Failed(io::Error::other(err)))?;
+    let status = response.status();
+    let response_body = body::to_bytes(response.into_body())
+        .await
+        .map_err(|err| PutError::Failed(io::Error::other(err)))?;
+    let json = serde_json::from_slice::<Value>(&response_body).unwrap_or_default();
+    let token = |name: &str| json.get(name).and_then(Value::as_str).map(str::to_owned);
+
+    if status == StatusCode::OK {
+        return Ok(token("nextSequenceToken"));
+    }
+
+    let error_type = json.get("__type").and_then(Value::as_str).unwrap_or("");
+    if error_type.ends_with("InvalidSequenceTokenException") {
+        return Err(PutError::InvalidSequenceToken(token(
+            "expectedSequenceToken",
+        )));
+    }
+    if error_type.ends_with("DataAlreadyAcceptedException") {
+        return Err(PutError::DataAlreadyAccepted(token(
+            "expectedSequenceToken",
+        )));
+    }
+
+    let message = json
+        .get("message")
+        .or_else(|| json.get("Message"))
+        .and_then(Value::as_str)
+        .unwrap_or("");
+    // client errors won't succeed on a retry, except for timeouts and throttling,
+    // which AWS responds to with 400 too
+    let kind = if status.is_client_error()
+        && status != StatusCode::REQUEST_TIMEOUT
+        && status != StatusCode::TOO_MANY_REQUESTS
+        && !error_type.ends_with("ThrottlingException")
+    {
+        ErrorKind::InvalidInput
+    } else {
+        ErrorKind::Other
+    };
+    Err(PutError::Failed(io::Error::new(
+        kind,
+        format!("CloudWatch responded with {status}: {error_type} {message}"),
+    )))
+}
+
+/// Puts the batch with the sequence token, sending it again once with the
+/// expected token if it's rejected, and updates the token to the next one
+async fn put_batch(
+    client: &Client<HttpsConnector<HttpConnector>>,
+    stream: &CloudWatchStream,
+    batch: &[LogEvent],
+    sequence_token: &Mutex<Option<String>>,
+) -> io::Result<()> {
+    let token = || {
+        sequence_token
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    };
+    let set_token = |next| {
+        *sequence_token
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = next;
+    };
+
+    let mut result = put_log_events(client, stream, batch, token().as_deref()).await;
+    if let Err(PutError::InvalidSequenceToken(expected)) = result {
+        set_token(expected);
+        result = put_log_events(client, stream, batch, token().as_deref()).await;
+    }
+
+    match result {
+        Ok(next) | Err(PutError::DataAlreadyAccepted(next)) => {
+            set_token(next);
+            Ok(())
+        }
+        Err(PutError::InvalidSequenceToken(expected)) => {
+            set_token(expected);
+            Err(io::Error::other(
+                "CloudWatch rejected the sequence token twice",
+            ))
+        }
+        Err(PutError::Failed(err)) => Err(err),
+    }
+}
+
+/// Returns the key requests on the date are signed with for the service in
+/// the region
+fn signing_key(secret_access_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    [region, service, "aws4_request"].iter().fold(
+        hmac_sha256(
+            format!("AWS4{secret_access_key}").as_bytes(),
+            date.as_bytes(),
+        ),
+        |key, part| hmac_sha256(&key, part.as_bytes()),
+    )
+}
+
+/// Returns the `Authorization` header of a `POST` request to `/` with the
+/// headers and the body, signed with AWS Signature Version 4 at the time
+///
+/// The headers have to be lowercase and sorted by their name
+fn authorization(
+    credentials: &AwsCredentials,
+    region: &str,
+    service: &str,
+    headers: &[(&str, String)],
+    request_body: &[u8],
+    now: DateTime<Utc>,
+) -> String {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+
+    let canonical_headers = headers
+        .iter()
+        .fold(String::new(), |mut canonical, (name, value)| {
+            canonical.push_str(name);
+            canonical.push(':');
+            canonical.push_str(value);
+            canonical.push('\n');
+            canonical
+        });
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_request = format!(
+        "POST\n/\n\n{canonical_headers}\n{signed_headers}\n{}",
+        sha256_hex(request_body)
+    );
+
+    let scope = format!("{date}/{region}/{service}/aws4_request");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        sha256_hex(canonical_request.as_bytes())
+    );
+    let key = signing_key(&credentials.secret_access_key, &date, region, service);
+    let signature = hex(&hmac_sha256(&key, string_to_sign.as_bytes()));
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, \
+         Signature={signature}",
+        credentials.access_key_id
+    )
+}
+
+/// Returns the request to put the body, signed with AWS Signature Version 4
+/// at the time
+fn signed_request(
+    stream: &CloudWatchStream,
+    request_body: String,
+    now: DateTime<Utc>,
+) -> io::Result<Request<Body>> {
+    let host = stream.host();
+    let credentials = &stream.credentials;
+
+    let mut headers = vec![
+        ("content-type", CONTENT_TYPE.to_owned()),
+        ("host", host.clone()),
+        ("x-amz-date", now.format("%Y%m%dT%H%M%SZ").to_string()),
+    ];
+    if let Some(token) = &credentials.session_token {
+        headers.push(("x-amz-security-token", token.clone()));
+    }
+    headers.push(("x-amz-target", PUT_LOG_EVENTS_TARGET.to_owned()));
+
+    let mut request = Request::post(format!("https://{host}/")).header(
+        "authorization",
+        authorization(
+            credentials,
+            &stream.region,
+            "logs",
+            &headers,
+            request_body.as_bytes(),
+            now,
+        ),
+    );
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+
+    request
+        .body(Body::from(request_body))
+        .map_err(io::Error::other)
+}
+
+impl ErrorHandler {
+    /// Also push errors as JSON records to the AWS log stream, such as
+    /// for bots hosted on AWS
+    ///
+    /// Errors are buffered when they're handled and pushed in batches with
+    /// [`Self::push_cloudwatch`], call [`Self::spawn_cloudwatch`] to push them
+    /// periodically
+    pub fn cloudwatch(&mut self, stream: CloudWatchStream) -> &mut Self {
+        self.config_mut().cloudwatch = Some(stream);
+        self
+    }
+
+    /// Change the AWS log stream errors are pushed to at runtime,
+    /// `None` to stop buffering them
+    ///
+    /// Same as [`Self::cloudwatch`] but through a shared reference, the
+    /// buffered errors are pushed to the new stream
+    pub fn set_cloudwatch(&self, stream: Option<CloudWatchStream>) {
+        self.update_config(|config| config.cloudwatch = stream);
+    }
+
+    /// Push the buffered errors to the AWS log stream, returning the
+    /// number of them that were pushed
+    ///
+    /// The sequence token of each batch is the one AWS responded with
+    /// to the previous batch, a batch is sent again once with the expected
+    /// token if the token is rejected, failed batches are retried according to
+    /// [`Self::retry_policy`] and kept to be pushed with the next call if they
+    /// still fail, unless AWS rejected them, such as without permission
+    /// to the stream
+    ///
+    /// # Errors
+    /// Returns [`io::Error`] if sending a batch fails or AWS responds
+    /// with an error
+    pub async fn push_cloudwatch(&self) -> io::Result<usize> {
+        let config = self.config();
+        let Some(stream) = &config.cloudwatch else {
+            return Ok(0);
+        };
+
+        let mut sequence_token = self.cloudwatch.sequence_token.lock().await;
+        let token = Mutex::new(sequence_token.take());
+        let client = self.cloudwatch.client();
+        let mut pushed = 0_usize;
+        let result = loop {
+            let batch = self.cloudwatch.take_batch();
+            if batch.is_empty() {
+                break Ok(pushed);
+            }
+
+            let put = delivery::retrying(
+                config.retry_policy.as_ref(),
+                || put_batch(client, stream, &batch, &token),
+                |err| err.kind() != ErrorKind::InvalidInput,
+            )
+            .await;
+            if let Err(err) = put {
+                if err.kind() == ErrorKind::InvalidInput {
+                    break Err(io::Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("dropped {} errors that were rejected: {err}", batch.len()),
+                    ));
+                }
+                self.cloudwatch.requeue(batch);
+                break Err(err);
+            }
+            pushed = pushed.saturating_add(batch.len());
+        };
+
+        *sequence_token = token.into_inner().unwrap_or_else(PoisonError::into_inner);
+        result
+    }
+
+    /// Spawn a Tokio task that pushes the buffered errors to the AWS
+    /// Logs stream at the interval
+    ///
+    /// Failures are printed according to [`Self::console_output`] and the
+    /// errors are pushed again at the next interval
+    pub fn spawn_cloudwatch(self: Arc<Self>, every: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = time::interval(every);
+            loop {
+                interval.tick().await;
+                if let Err(err) = self.push_cloudwatch().await {
+                    self.config()
+                        .print_notice(&format!("Failed to push errors to CloudWatch: {err}"));
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone as _;
+
+    use super::*;
+    use crate::{ErrorId, ErrorReport};
+
+    #[test]
+    fn signing_key_matches_aws_example() {
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        );
+
+        assert_eq!(
+            hex(&key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    #[test]
+    fn authorization_matches_aws_test_suite() {
+        // the post-vanilla request of the AWS Signature Version 4 test suite
+        let credentials =
+            AwsCredentials::new("AKIDEXAMPLE", "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY");
+        let headers = [
+            ("host", "example.amazonaws.com".to_owned()),
+            ("x-amz-date", "20150830T123600Z".to_owned()),
+        ];
+        let now = Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap();
+
+        assert_eq!(
+            authorization(&credentials, "us-east-1", "service", &headers, b"", now),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5da7c1a2acd57cee7505fc6676e4e544621c30862966e37dddb68e92efbe5d6b"
+        );
+    }
+
+    #[test]
+    fn signed_request_has_sigv4_authorization() {
+        let stream = CloudWatchStream::new(
+            "us-east-1",
+            "bots",
+            "errors",
+            AwsCredentials::new("AKIDEXAMPLE", "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY"),
+        );
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+
+        let request = signed_request(&stream, r#"{"logEvents":[]}"#.to_owned(), now).unwrap();
+
+        assert_eq!(request.uri(), "https://logs.us-east-1.amazonaws.com/");
+        assert_eq!(request.headers()["x-amz-date"], "20240101T000000Z");
+        assert!(request.headers()["authorization"]
+            .to_str()
+            .unwrap()
+            .starts_with(
+                "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20240101/us-east-1/logs/aws4_request, \
+                 SignedHeaders=content-type;host;x-amz-date;x-amz-target, Signature="
+            ));
+    }
+
+    #[test]
+    fn session_token_is_signed() {
+        let credentials = AwsCredentials::new("AKIDEXAMPLE", "secret").session_token("token");
+        let stream = CloudWatchStream::new("eu-west-1", "bots", "errors", credentials);
+
+        let request = signed_request(&stream, String::new(), Utc::now()).unwrap();
+
+        assert_eq!(request.headers()["x-amz-security-token"], "token");
+        assert!(request.headers()["authorization"]
+            .to_str()
+            .unwrap()
+            .contains("SignedHeaders=content-type;host;x-amz-date;x-amz-security-token;"));
+    }
+
+    #[test]
+    fn buffer_drops_oldest_events_over_the_cap() {
+        let buffer = CloudWatchBuffer::default();
+        for timestamp in 0..=i64::try_from(MAX_BUFFERED).unwrap() {
+            buffer.push(LogEvent {
+                timestamp,
+                message: String::new(),
+            });
+        }
+
+        let batch = buffer.take_batch();
+
+        assert_eq!(batch.first().unwrap().timestamp, 1);
+        assert_eq!(batch.len(), MAX_BATCH_EVENTS);
+    }
+
+    #[test]
+    fn long_error_is_shortened_to_valid_json() {
+        let record = ErrorReport::new(format!("\"{}", "\u{e9}".repeat(MAX_EVENT_BYTES)))
+            .field("Command", "/play")
+            .into_record(ErrorId::new(), &Config::new(), None, None);
+
+        let message = event_message(&record).unwrap();
+
+        assert!(message.len() <= MAX_EVENT_BYTES);
+        let shortened: ErrorRecord = serde_json::from_str(&message).unwrap();
+        assert!(shortened.error.starts_with("\"\u{e9}"));
+        assert!(shortened.error.ends_with("\u{e9}..."));
+        assert_eq!(shortened.fields, record.fields);
+    }
+
+    #[test]
+    fn record_too_large_without_its_error_is_skipped() {
+        let record = ErrorReport::new("oh no")
+            .field("Log", "a".repeat(MAX_EVENT_BYTES))
+            .into_record(ErrorId::new(), &Config::new(), None, None);
+
+        assert_eq!(event_message(&record), None);
+    }
+
+    #[test]
+    fn batch_ends_before_spanning_a_day() {
+        let buffer = CloudWatchBuffer::default();
+        for timestamp in [
+            MAX_BATCH_SPAN_MILLIS,
+            0,
+            MAX_BATCH_SPAN_MILLIS.saturating_add(1),
+        ] {
+            buffer.push(LogEvent {
+                timestamp,
+                message: "error".to_owned(),
+            });
+        }
+
+        let timestamps = |batch: Vec<LogEvent>| {
+            batch
+                .into_iter()
+                .map(|event| event.timestamp)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(timestamps(buffer.take_batch()), [0, MAX_BATCH_SPAN_MILLIS]);
+        assert_eq!(
+            timestamps(buffer.take_batch()),
+            [MAX_BATCH_SPAN_MILLIS.saturating_add(1)]
+        );
+    }
+
+    #[test]
+    fn requeued_batch_is_pushed_first() {
+        let buffer = CloudWatchBuffer::default();
+        for timestamp in [3, 1, 2] {
+            buffer.push(LogEvent {
+                timestamp,
+                message: "error".to_owned(),
+            });
+        }
+
+        let batch = buffer.take_batch();
+        assert_eq!(
+            batch
+                .iter()
+                .map(|event| event.timestamp)
+                .collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        buffer.push(LogEvent {
+            timestamp: 4,
+            message: "error".to_owned(),
+        });
+        buffer.requeue(batch);
+
+        assert_eq!(buffer.take_batch().len(), 4);
+        assert!(buffer.take_batch().is_empty());
+    }
+}
@@ -0,0 +1,92 @@
+//! Merging identical errors that happen while one is being delivered
+
+use std::collections::hash_map::Entry;
+
+use crate::{sharded::ShardedMap, ErrorHandler, Fingerprint};
+
+/// The errors being delivered and how many identical ones happened meanwhile
+#[derive(Debug, Default)]
+pub(crate) struct InFlight {
+    /// The number of identical errors for each fingerprint being delivered
+    delivering: ShardedMap<Fingerprint, usize>,
+}
+
+impl InFlight {
+    /// Returns the guard of the delivery of the error, counting it instead
+    /// and returning `None` if an identical one is being delivered
+    pub(crate) fn start(&self, fingerprint: Fingerprint) -> Option<Delivering<'_>> {
+        match self.delivering.shard(&fingerprint).entry(fingerprint) {
+            Entry::Occupied(mut entry) => {
+                let count = entry.get_mut();
+                *count = count.saturating_add(1);
+                None
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(0);
+                Some(Delivering {
+                    in_flight: self,
+                    fingerprint: Some(fingerprint),
+                })
+            }
+        }
+    }
+
+    /// Removes the error, returning the number of identical errors that were
+    /// merged into it
+    fn remove(&self, fingerprint: Fingerprint) -> usize {
+        self.delivering
+            .shard(&fingerprint)
+            .remove(&fingerprint)
+            .unwrap_or_default()
+    }
+}
+
+/// An error being delivered, identical errors are merged into it until it's
+/// finished or dropped, such as if handling it is cancelled
+#[derive(Debug)]
+pub(crate) struct Delivering<'a> {
+    /// The errors being delivered
+    in_flight: &'a InFlight,
+    /// The fingerprint of the error, `None` once it's finished
+    fingerprint: Option<Fingerprint>,
+}
+
+impl Delivering<'_> {
+    /// Records that the error was delivered, returning the number of identical
+    /// errors that were merged into it
+    pub(crate) fn finish(mut self) -> usize {
+        self.fingerprint
+            .take()
+            .map_or(0, |fingerprint| self.in_flight.remove(fingerprint))
+    }
+}
+
+impl Drop for Delivering<'_> {
+    fn drop(&mut self) {
+        if let Some(fingerprint) = self.fingerprint.take() {
+            self.in_flight.remove(fingerprint);
+        }
+    }
+}
+
+impl ErrorHandler {
+    /// Set whether to merge identical errors, by their fingerprint, that
+    /// happen while one is being delivered
+    ///
+    /// The merged errors aren't formatted or delivered, instead a notice with
+    /// their count is delivered after the first one, which avoids an API call
+    /// for each error when the sinks are slow or rate limited during error
+    /// storms, they're still kept in the history and stats, defaults to
+    /// `false`
+    pub fn coalesce_identical(&mut self, coalesce: bool) -> &mut Self {
+        self.config_mut().coalesce_identical = coalesce;
+        self
+    }
+
+    /// Change whether to merge identical errors at runtime
+    ///
+    /// Same as [`Self::coalesce_identical`] but through a shared reference
+    pub fn set_coalesce_identical(&self, coalesce: bool) {
+        self.update_config(|config| config.coalesce_identical = coalesce);
+    }
+}
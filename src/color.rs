@@ -0,0 +1,79 @@
+//! Coloring the errors printed to `stderr`
+
+use core::fmt::Write as _;
+use std::{
+    borrow::Cow,
+    env,
+    io::{self, IsTerminal as _},
+    sync::LazyLock,
+};
+
+use regex::Regex;
+
+use crate::{ErrorRecord, Severity};
+
+/// The pattern of ANSI escape codes, such as the colors of `color-eyre`
+#[allow(clippy::unwrap_used)]
+static ANSI_ESCAPE_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\x1b\[[0-9;?]*[A-Za-z]").unwrap());
+
+/// Whether to color the output of [`std::io::stderr`]
+///
+/// It's colored if it's a terminal and `NO_COLOR` isn't set, or if
+/// `CLICOLOR_FORCE` is set to anything other than `0`
+static ENABLED: LazyLock<bool> = LazyLock::new(|| {
+    if env::var_os("CLICOLOR_FORCE").is_some_and(|value| !value.is_empty() && value != "0") {
+        return true;
+    }
+
+    env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()) && io::stderr().is_terminal()
+});
+
+/// The escape code to reset the color
+const RESET: &str = "\x1b[0m";
+
+/// The escape code to dim the text
+const DIM: &str = "\x1b[2m";
+
+/// Returns the escape code of the severity's color
+const fn severity_color(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Warning => "\x1b[33m",
+        Severity::Error => "\x1b[31m",
+        Severity::Critical => "\x1b[1;31m",
+    }
+}
+
+/// Returns the record rendered like [`crate::PlainFormatter`] with the ID and
+/// error colored by severity and the rest dimmed, `None` if the output of
+/// [`std::io::stderr`] shouldn't be colored
+#[allow(unused_must_use)]
+pub(crate) fn colored(record: &ErrorRecord) -> Option<String> {
+    if !*ENABLED {
+        return None;
+    }
+
+    let color = severity_color(record.severity);
+    let mut message = format!("\n\n{color}[{}]{RESET} {}", record.id, record.error);
+
+    if let Some(correlation_id) = &record.correlation_id {
+        write!(message, "\n{DIM}Correlation ID: {correlation_id}{RESET}");
+    }
+    if let Some(kind) = &record.kind {
+        write!(message, "\n{DIM}Type: {kind}{RESET}");
+    }
+    if !record.tags.is_empty() {
+        write!(message, "\n{DIM}Tags: {}{RESET}", record.tags.join(", "));
+    }
+    for (name, value) in &record.fields {
+        write!(message, "\n{DIM}{name}: {value}{RESET}");
+    }
+
+    Some(message)
+}
+
+/// Returns the text without ANSI escape codes, which show up as garbage
+/// outside of terminals
+pub(crate) fn strip(text: &str) -> Cow<'_, str> {
+    ANSI_ESCAPE_PATTERN.replace_all(text, "")
+}
@@ -0,0 +1,908 @@
+//! A slash command and message components to browse the recently handled
+//! errors with
+
+use core::{fmt::Write as _, time::Duration};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use twilight_model::{
+    application::{
+        command::{
+            Command, CommandOption, CommandOptionChoice, CommandOptionChoiceData,
+            CommandOptionType, CommandOptionValue as OptionLimit, CommandType,
+        },
+        interaction::{
+            application_command::{CommandData, CommandDataOption, CommandOptionValue},
+            message_component::MessageComponentInteractionData,
+        },
+    },
+    channel::message::{
+        component::{ActionRow, Button, ButtonStyle, SelectMenu, SelectMenuOption},
+        embed::{EmbedField, EmbedFooter},
+        Component, Embed, MessageFlags,
+    },
+    guild::Permissions,
+    http::interaction::{InteractionResponse, InteractionResponseData, InteractionResponseType},
+    id::Id,
+};
+
+use crate::{color, ErrorHandler, ErrorRecord, Fingerprint, Severity};
+
+/// The name of the command made with [`errors_command`]
+pub const ERRORS_COMMAND_NAME: &str = "errors";
+
+/// The name of the subcommand to browse the recently handled errors with
+pub const RECENT_SUBCOMMAND_NAME: &str = "recent";
+
+/// The name of the option to choose the page of recent errors with
+pub const PAGE_OPTION_NAME: &str = "page";
+
+/// The name of the subcommand to search the recently handled errors with
+pub const SEARCH_SUBCOMMAND_NAME: &str = "search";
+
+/// The name of the option to choose the text searched for with
+pub const QUERY_OPTION_NAME: &str = "query";
+
+/// The name of the option to choose the minimum severity of the searched
+/// errors with
+pub const SEVERITY_OPTION_NAME: &str = "severity";
+
+/// The name of the option to only search the errors of the last given hours
+/// with
+pub const HOURS_OPTION_NAME: &str = "hours";
+
+/// The number of errors shown in a page
+pub const ERRORS_PER_PAGE: usize = 5;
+
+/// The prefix of the custom IDs of the components in the responses to
+/// `/errors recent`, see [`ErrorHandler::error_browser_response`]
+pub const BROWSER_CUSTOM_ID_PREFIX: &str = "twilight-error:";
+
+/// The maximum number of characters of an error shown in a page
+const MAX_ERROR_LEN: usize = 900;
+
+/// The maximum number of characters in the value of an embed field, five
+/// of them with their names, the title and the footer stay under the 6000
+/// characters of an embed
+const MAX_FIELD_VALUE_LEN: usize = 1024;
+
+/// The maximum number of characters of the text searched for with
+/// `/errors search`
+const MAX_QUERY_LEN: u16 = 100;
+
+/// The maximum number of fingerprints in the select menu, along with the
+/// option to show every error
+const MAX_FINGERPRINT_OPTIONS: usize = 24;
+
+/// The maximum number of characters in the label of a select menu option
+const MAX_LABEL_LEN: usize = 100;
+
+/// The value of the select menu option to show every error
+const ALL_ERRORS_VALUE: &str = "all";
+
+/// Returns the `/errors` command, with the `/errors recent` subcommand to
+/// browse the recently handled errors and the `/errors search` subcommand to
+/// search them
+///
+/// Create it with [`twilight_http::client::InteractionClient::set_global_commands`]
+/// or its guild variant, it can only be used by administrators by default
+/// since errors may contain sensitive information
+#[must_use]
+pub fn errors_command() -> Command {
+    Command {
+        application_id: None,
+        default_member_permissions: Some(Permissions::empty()),
+        dm_permission: Some(false),
+        description: "Browse the errors the bot handled".to_owned(),
+        description_localizations: None,
+        guild_id: None,
+        id: None,
+        kind: CommandType::ChatInput,
+        name: ERRORS_COMMAND_NAME.to_owned(),
+        name_localizations: None,
+        nsfw: None,
+        options: vec![
+            CommandOption {
+                autocomplete: None,
+                channel_types: None,
+                choices: None,
+                description: "Show the most recent errors".to_owned(),
+                description_localizations: None,
+                kind: CommandOptionType::SubCommand,
+                max_length: None,
+                max_value: None,
+                min_length: None,
+                min_value: None,
+                name: RECENT_SUBCOMMAND_NAME.to_owned(),
+                name_localizations: None,
+                options: Some(vec![CommandOption {
+                    autocomplete: None,
+                    channel_types: None,
+                    choices: None,
+                    description: "The page to show, starting from the newest errors".to_owned(),
+                    description_localizations: None,
+                    kind: CommandOptionType::Integer,
+                    max_length: None,
+                    max_value: None,
+                    min_length: None,
+                    min_value: Some(OptionLimit::Integer(1)),
+                    name: PAGE_OPTION_NAME.to_owned(),
+                    name_localizations: None,
+                    options: None,
+                    required: Some(false),
+                }]),
+                required: None,
+            },
+            search_subcommand(),
+        ],
+        version: Id::new(1),
+    }
+}
+
+/// Returns the `/errors search` subcommand
+fn search_subcommand() -> CommandOption {
+    let severity_choice = |severity: Severity| {
+        CommandOptionChoice::String(CommandOptionChoiceData {
+            name: severity.to_string(),
+            name_localizations: None,
+            value: severity.to_string(),
+        })
+    };
+
+    CommandOption {
+        autocomplete: None,
+        channel_types: None,
+        choices: None,
+        description: "Search the recent errors".to_owned(),
+        description_localizations: None,
+        kind: CommandOptionType::SubCommand,
+        max_length: None,
+        max_value: None,
+        min_length: None,
+        min_value: None,
+        name: SEARCH_SUBCOMMAND_NAME.to_owned(),
+        name_localizations: None,
+        options: Some(vec![
+            CommandOption {
+                autocomplete: None,
+                channel_types: None,
+                choices: None,
+                description: "The text to search the errors for".to_owned(),
+                description_localizations: None,
+                kind: CommandOptionType::String,
+                max_length: Some(MAX_QUERY_LEN),
+                max_value: None,
+                min_length: None,
+                min_value: None,
+                name: QUERY_OPTION_NAME.to_owned(),
+                name_localizations: None,
+                options: None,
+                required: Some(true),
+            },
+            CommandOption {
+                autocomplete: None,
+                channel_types: None,
+                choices: Some(vec![
+                    severity_choice(Severity::Warning),
+                    severity_choice(Severity::Error),
+                    severity_choice(Severity::Critical),
+                ]),
+                description: "The severity the errors need to be at least".to_owned(),
+                description_localizations: None,
+                kind: CommandOptionType::String,
+                max_length: None,
+                max_value: None,
+                min_length: None,
+                min_value: None,
+                name: SEVERITY_OPTION_NAME.to_owned(),
+                name_localizations: None,
+                options: None,
+                required: Some(false),
+            },
+            CommandOption {
+                autocomplete: None,
+                channel_types: None,
+                choices: None,
+                description: "Only search the errors of the last given hours".to_owned(),
+                description_localizations: None,
+                kind: CommandOptionType::Integer,
+                max_length: None,
+                max_value: None,
+                min_length: None,
+                min_value: Some(OptionLimit::Integer(1)),
+                name: HOURS_OPTION_NAME.to_owned(),
+                name_localizations: None,
+                options: None,
+                required: Some(false),
+            },
+        ]),
+        required: None,
+    }
+}
+
+/// What's searched for with `/errors search`, returned from [`error_search`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ErrorSearch {
+    /// The text searched for
+    pub query: String,
+    /// When the errors need to be handled after
+    pub since: Option<SystemTime>,
+    /// The severity the errors need to be at least
+    pub min_severity: Option<Severity>,
+}
+
+/// Returns the options of the subcommand of `/errors`, `None` if the data
+/// isn't of that subcommand
+fn subcommand_options<'a>(data: &'a CommandData, name: &str) -> Option<&'a [CommandDataOption]> {
+    if data.name != ERRORS_COMMAND_NAME {
+        return None;
+    }
+
+    data.options.iter().find_map(|option| {
+        if let CommandOptionValue::SubCommand(options) = &option.value {
+            (option.name == name).then_some(options.as_slice())
+        } else {
+            None
+        }
+    })
+}
+
+/// Returns the page requested with `/errors recent`, `None` if the data isn't
+/// of that command
+///
+/// Pages start from 1, which is returned if the page option isn't set
+#[must_use]
+pub fn recent_errors_page(data: &CommandData) -> Option<usize> {
+    let options = subcommand_options(data, RECENT_SUBCOMMAND_NAME)?;
+
+    let page = options
+        .iter()
+        .find_map(|option| {
+            if let CommandOptionValue::Integer(page) = option.value {
+                (option.name == PAGE_OPTION_NAME).then_some(page)
+            } else {
+                None
+            }
+        })
+        .unwrap_or(1);
+
+    Some(usize::try_from(page).unwrap_or(1))
+}
+
+/// Returns what's searched for with `/errors search`, `None` if the data
+/// isn't of that command
+#[must_use]
+pub fn error_search(data: &CommandData) -> Option<ErrorSearch> {
+    let options = subcommand_options(data, SEARCH_SUBCOMMAND_NAME)?;
+
+    let mut search = ErrorSearch {
+        query: String::new(),
+        since: None,
+        min_severity: None,
+    };
+    for option in options {
+        match (option.name.as_str(), &option.value) {
+            (QUERY_OPTION_NAME, CommandOptionValue::String(query)) => {
+                search.query.clone_from(query);
+            }
+            (SEVERITY_OPTION_NAME, CommandOptionValue::String(severity)) => {
+                search.min_severity = Severity::from_name(severity);
+            }
+            (HOURS_OPTION_NAME, CommandOptionValue::Integer(hours)) => {
+                let secs = u64::try_from(*hours).unwrap_or(0).saturating_mul(3600);
+                search.since = SystemTime::now().checked_sub(Duration::from_secs(secs));
+            }
+            _ => {}
+        }
+    }
+
+    Some(search)
+}
+
+impl ErrorHandler {
+    /// Returns the ephemeral response to `/errors recent` showing the given
+    /// page of the recently handled errors
+    ///
+    /// Pages start from 1 and show [`ERRORS_PER_PAGE`] errors, newest first,
+    /// pages after the last one show the last one, see
+    /// [`recent_errors_page`] to get the page from the command
+    ///
+    /// The response has Previous and Next buttons and a select menu to only
+    /// show the errors with a fingerprint, pass their interactions to
+    /// [`Self::error_browser_response`]
+    pub fn recent_errors_response(&self, page: usize) -> InteractionResponse {
+        self.browser_response(
+            page,
+            None,
+            InteractionResponseType::ChannelMessageWithSource,
+        )
+    }
+
+    /// Returns the ephemeral response to `/errors search` showing the newest
+    /// [`ERRORS_PER_PAGE`] errors that match the search
+    ///
+    /// See [`error_search`] to get the search from the command and
+    /// [`Self::search_errors`] for how errors are matched
+    pub fn search_errors_response(&self, search: &ErrorSearch) -> InteractionResponse {
+        let records = self.search_errors(&search.query, search.since, search.min_severity);
+        let fields = records
+            .iter()
+            .take(ERRORS_PER_PAGE)
+            .map(record_field)
+            .collect::<Vec<_>>();
+
+        let embed = Embed {
+            author: None,
+            color: None,
+            description: fields
+                .is_empty()
+                .then(|| "No errors match the search".to_owned()),
+            footer: Some(EmbedFooter {
+                icon_url: None,
+                proxy_icon_url: None,
+                text: format!(
+                    "{} matching errors, showing the newest {}",
+                    records.len(),
+                    fields.len()
+                ),
+            }),
+            fields,
+            image: None,
+            kind: "rich".to_owned(),
+            provider: None,
+            thumbnail: None,
+            timestamp: None,
+            title: Some(format!(
+                "Errors matching \"{}\"",
+                escape_markdown(&search.query, MAX_QUERY_LEN.into())
+            )),
+            url: None,
+            video: None,
+        };
+
+        InteractionResponse {
+            kind: InteractionResponseType::ChannelMessageWithSource,
+            data: Some(InteractionResponseData {
+                allowed_mentions: None,
+                attachments: None,
+                choices: None,
+                components: None,
+                content: None,
+                custom_id: None,
+                embeds: Some(vec![embed]),
+                flags: Some(MessageFlags::EPHEMERAL),
+                title: None,
+                tts: None,
+            }),
+        }
+    }
+
+    /// Returns the response to an interaction with the buttons or the select
+    /// menu of [`Self::recent_errors_response`], updating its message
+    ///
+    /// Returns `None` if the custom ID of the component doesn't start with
+    /// [`BROWSER_CUSTOM_ID_PREFIX`], so that the other components of the bot
+    /// can be handled then
+    ///
+    /// ```ignore
+    /// if let Some(response) = handler.error_browser_response(&data) {
+    ///     interaction_client
+    ///         .create_response(interaction.id, &interaction.token, &response)
+    ///         .await?;
+    /// }
+    /// ```
+    pub fn error_browser_response(
+        &self,
+        data: &MessageComponentInteractionData,
+    ) -> Option<InteractionResponse> {
+        let (page, fingerprint) = match data
+            .custom_id
+            .strip_prefix(BROWSER_CUSTOM_ID_PREFIX)?
+            .split(':')
+            .collect::<Vec<_>>()
+            .as_slice()
+        {
+            ["fingerprint"] => (
+                1,
+                data.values
+                    .first()
+                    .and_then(|value| Fingerprint::parse(value)),
+            ),
+            ["page", page, fingerprint, _] => (page.parse().ok()?, Fingerprint::parse(fingerprint)),
+            _ => return None,
+        };
+
+        Some(self.browser_response(page, fingerprint, InteractionResponseType::UpdateMessage))
+    }
+
+    /// Returns the response showing the given page of the recently handled
+    /// errors with the fingerprint, or every error if it's `None`
+    fn browser_response(
+        &self,
+        page: usize,
+        fingerprint: Option<Fingerprint>,
+        kind: InteractionResponseType,
+    ) -> InteractionResponse {
+        let all_records = self.recent_errors();
+        let records = all_records
+            .iter()
+            .filter(|record| fingerprint.is_none_or(|shown| record.fingerprint() == shown))
+            .collect::<Vec<_>>();
+        let page_count = records.len().div_ceil(ERRORS_PER_PAGE).max(1);
+        let page_number = page.clamp(1, page_count);
+
+        let fields = records
+            .iter()
+            .skip(
+                page_number
+                    .saturating_sub(1)
+                    .saturating_mul(ERRORS_PER_PAGE),
+            )
+            .take(ERRORS_PER_PAGE)
+            .map(|record| record_field(record))
+            .collect::<Vec<_>>();
+
+        let embed = Embed {
+            author: None,
+            color: None,
+            description: fields
+                .is_empty()
+                .then(|| "No errors were handled yet".to_owned()),
+            fields,
+            footer: Some(EmbedFooter {
+                icon_url: None,
+                proxy_icon_url: None,
+                text: format!(
+                    "Page {page_number} of {page_count}, {} errors",
+                    records.len()
+                ),
+            }),
+            image: None,
+            kind: "rich".to_owned(),
+            provider: None,
+            thumbnail: None,
+            timestamp: None,
+            title: Some(fingerprint.map_or_else(
+                || "Recent errors".to_owned(),
+                |shown| format!("Recent errors with the fingerprint {shown}"),
+            )),
+            url: None,
+            video: None,
+        };
+
+        let mut components = Vec::new();
+        if !all_records.is_empty() {
+            components.push(Component::ActionRow(ActionRow {
+                components: vec![fingerprint_menu(&all_records, fingerprint)],
+            }));
+        }
+        components.push(Component::ActionRow(ActionRow {
+            components: vec![
+                page_button(
+                    "Previous",
+                    page_number.saturating_sub(1),
+                    fingerprint,
+                    page_number == 1,
+                ),
+                page_button(
+                    "Next",
+                    page_number.saturating_add(1),
+                    fingerprint,
+                    page_number == page_count,
+                ),
+            ],
+        }));
+
+        InteractionResponse {
+            kind,
+            data: Some(InteractionResponseData {
+                allowed_mentions: None,
+                attachments: None,
+                choices: None,
+                components: Some(components),
+                content: None,
+                custom_id: None,
+                embeds: Some(vec![embed]),
+                flags: Some(MessageFlags::EPHEMERAL),
+                title: None,
+                tts: None,
+            }),
+        }
+    }
+}
+
+/// Returns the button to show the page of the errors with the fingerprint
+fn page_button(
+    label: &str,
+    page: usize,
+    fingerprint: Option<Fingerprint>,
+    disabled: bool,
+) -> Component {
+    let shown = fingerprint.map_or_else(|| ALL_ERRORS_VALUE.to_owned(), |shown| shown.to_string());
+
+    Component::Button(Button {
+        custom_id: Some(format!(
+            "{BROWSER_CUSTOM_ID_PREFIX}page:{page}:{shown}:{}",
+            label.to_ascii_lowercase()
+        )),
+        disabled,
+        emoji: None,
+        label: Some(label.to_owned()),
+        style: ButtonStyle::Secondary,
+        url: None,
+    })
+}
+
+/// Returns the select menu of the fingerprints of the errors, newest first,
+/// with the option to show every error
+fn fingerprint_menu(records: &[ErrorRecord], selected: Option<Fingerprint>) -> Component {
+    let mut fingerprints: Vec<(Fingerprint, &ErrorRecord, usize)> = Vec::new();
+    for record in records {
+        let fingerprint = record.fingerprint();
+        if let Some((_, _, count)) = fingerprints
+            .iter_mut()
+            .find(|(known, _, _)| *known == fingerprint)
+        {
+            *count = count.saturating_add(1);
+        } else {
+            fingerprints.push((fingerprint, record, 1));
+        }
+    }
+
+    let mut options = vec![SelectMenuOption {
+        default: selected.is_none(),
+        description: None,
+        emoji: None,
+        label: "All errors".to_owned(),
+        value: ALL_ERRORS_VALUE.to_owned(),
+    }];
+    options.extend(fingerprints.into_iter().take(MAX_FINGERPRINT_OPTIONS).map(
+        |(fingerprint, record, count)| {
+            let label = color::strip(&record.error)
+                .chars()
+                .take(MAX_LABEL_LEN)
+                .collect::<String>();
+            SelectMenuOption {
+                default: selected == Some(fingerprint),
+                description: Some(format!("{count} times, fingerprint {fingerprint}")),
+                emoji: None,
+                label: if label.trim().is_empty() {
+                    fingerprint.to_string()
+                } else {
+                    label
+                },
+                value: fingerprint.to_string(),
+            }
+        },
+    ));
+
+    Component::SelectMenu(SelectMenu {
+        custom_id: format!("{BROWSER_CUSTOM_ID_PREFIX}fingerprint"),
+        disabled: false,
+        max_values: Some(1),
+        min_values: Some(1),
+        options,
+        placeholder: Some("Show the errors with a fingerprint".to_owned()),
+    })
+}
+
+/// Returns the first `max_len` characters of the text with the markdown
+/// characters escaped, so that they're shown as they are
+fn escape_markdown(text: &str, max_len: usize) -> String {
+    let mut escaped = String::new();
+    for character in text.chars().take(max_len) {
+        if matches!(
+            character,
+            '\\' | '`' | '*' | '_' | '~' | '|' | '>' | '#' | '[' | ']'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(character);
+    }
+    escaped
+}
+
+/// Returns the embed field showing the error
+///
+/// The details of the error are cut before the error itself so that the
+/// value fits in [`MAX_FIELD_VALUE_LEN`]
+#[allow(unused_must_use)]
+fn record_field(record: &ErrorRecord) -> EmbedField {
+    let mut details = String::new();
+
+    if let Ok(handled_at) = record.handled_at.duration_since(UNIX_EPOCH) {
+        writeln!(details, "<t:{}:R>", handled_at.as_secs());
+    }
+    if let Some(correlation_id) = &record.correlation_id {
+        writeln!(details, "Correlation ID: {correlation_id}");
+    }
+    if let Some(kind) = &record.kind {
+        writeln!(details, "Type: `{kind}`");
+    }
+    if !record.tags.is_empty() {
+        writeln!(details, "Tags: {}", record.tags.join(", "));
+    }
+    for (name, field_value) in &record.fields {
+        writeln!(details, "{name}: {field_value}");
+    }
+
+    let stripped = color::strip(&record.error);
+    let mut error = stripped.chars().take(MAX_ERROR_LEN).collect::<String>();
+    if stripped.chars().nth(MAX_ERROR_LEN).is_some() {
+        error.push_str("...");
+    }
+
+    let details_len = MAX_FIELD_VALUE_LEN.saturating_sub(error.chars().count());
+    let mut value = if details.chars().nth(details_len).is_some() {
+        let mut cut = details
+            .chars()
+            .take(details_len.saturating_sub(4))
+            .collect::<String>();
+        cut.push_str("...\n");
+        cut
+    } else {
+        details
+    };
+    value.push_str(&error);
+
+    EmbedField {
+        inline: false,
+        name: format!("{} {}", record.id, record.severity),
+        value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use twilight_model::channel::message::component::ComponentType;
+
+    use super::*;
+    use crate::{config::Config, ErrorId, ErrorReport};
+
+    /// Returns a record of the error
+    fn record(error: &str) -> ErrorRecord {
+        ErrorReport::new(error).into_record(ErrorId::new(), &Config::new(), None, None)
+    }
+
+    /// Returns the data of an interaction with the component
+    fn component_data(custom_id: String, values: Vec<String>) -> MessageComponentInteractionData {
+        MessageComponentInteractionData {
+            custom_id,
+            component_type: ComponentType::Button,
+            values,
+        }
+    }
+
+    /// Returns the components of the response's message
+    fn response_components(response: &InteractionResponse) -> Vec<Component> {
+        response
+            .data
+            .as_ref()
+            .and_then(|data| data.components.clone())
+            .unwrap()
+            .into_iter()
+            .flat_map(|component| {
+                if let Component::ActionRow(row) = component {
+                    row.components
+                } else {
+                    vec![component]
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the buttons of the response's message
+    fn response_buttons(response: &InteractionResponse) -> Vec<Button> {
+        response_components(response)
+            .into_iter()
+            .filter_map(|component| {
+                if let Component::Button(button) = component {
+                    Some(button)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the footer of the response's embed
+    fn response_footer(response: &InteractionResponse) -> String {
+        response.data.as_ref().unwrap().embeds.as_ref().unwrap()[0]
+            .footer
+            .as_ref()
+            .unwrap()
+            .text
+            .clone()
+    }
+
+    /// Returns a record with a long error and many long fields
+    fn long_record() -> ErrorRecord {
+        let mut report = ErrorReport::new("e".repeat(2_000)).kind("k".repeat(300));
+        for index in 0_u8..10 {
+            report = report.field(format!("Field {index}"), "v".repeat(200));
+        }
+        report.into_record(ErrorId::new(), &Config::new(), None, None)
+    }
+
+    /// Returns the number of characters in the embed that count towards its
+    /// limit
+    fn embed_len(embed: &Embed) -> usize {
+        let footer = embed.footer.as_ref().map(|footer| footer.text.as_str());
+        [embed.title.as_deref(), embed.description.as_deref(), footer]
+            .into_iter()
+            .flatten()
+            .chain(
+                embed
+                    .fields
+                    .iter()
+                    .flat_map(|field| [field.name.as_str(), field.value.as_str()]),
+            )
+            .map(|text| text.chars().count())
+            .sum()
+    }
+
+    #[test]
+    fn field_cuts_the_details_before_the_error() {
+        let field = record_field(&long_record());
+
+        assert_eq!(field.value.chars().count(), MAX_FIELD_VALUE_LEN);
+        assert!(field
+            .value
+            .ends_with(&format!("{}...", "e".repeat(MAX_ERROR_LEN))));
+        assert!(field.value.contains("...\n"));
+        assert!(!field.value.contains("Field 9"));
+    }
+
+    #[test]
+    fn short_field_is_kept() {
+        let record = ErrorReport::new("oh no")
+            .field("Command", "/play")
+            .into_record(ErrorId::new(), &Config::new(), None, None);
+
+        let field = record_field(&record);
+
+        assert!(field.value.contains("Command: /play\n"));
+        assert!(field.value.ends_with("\noh no"));
+    }
+
+    #[test]
+    fn query_option_has_a_max_length() {
+        let search = search_subcommand();
+
+        let query = &search.options.unwrap()[0];
+        assert_eq!(query.name, QUERY_OPTION_NAME);
+        assert_eq!(query.max_length, Some(MAX_QUERY_LEN));
+    }
+
+    #[test]
+    fn search_title_is_shortened_and_escaped() {
+        let handler = ErrorHandler::new();
+        let search = ErrorSearch {
+            query: format!("`{}`", "*".repeat(300)),
+            since: None,
+            min_severity: None,
+        };
+
+        let response = handler.search_errors_response(&search);
+
+        let title = response.data.unwrap().embeds.unwrap()[0]
+            .title
+            .clone()
+            .unwrap();
+        assert!(title.chars().count() <= 256);
+        assert!(title.starts_with("Errors matching \"\\`\\*\\*"));
+        assert!(!title.contains("*`"));
+    }
+
+    #[test]
+    fn recent_errors_page_fits_the_embed_limit() {
+        let handler = ErrorHandler::new();
+        for _ in 0..ERRORS_PER_PAGE {
+            handler.history.push(long_record());
+        }
+
+        let response = handler.recent_errors_response(1);
+
+        let embed = &response.data.unwrap().embeds.unwrap()[0];
+        assert_eq!(embed.fields.len(), ERRORS_PER_PAGE);
+        assert!(embed_len(embed) <= 6_000);
+    }
+
+    #[test]
+    fn page_buttons_show_the_neighbouring_pages() {
+        let handler = ErrorHandler::new();
+        for index in 0..7_u8 {
+            handler.history.push(record(&format!("error {index}")));
+        }
+
+        let response = handler.recent_errors_response(1);
+        assert_eq!(response_footer(&response), "Page 1 of 2, 7 errors");
+
+        let buttons = response_buttons(&response);
+        assert_eq!(buttons.len(), 2);
+        let (previous, next) = (&buttons[0], &buttons[1]);
+        assert!(previous.disabled);
+        assert!(!next.disabled);
+        assert_eq!(
+            next.custom_id.as_deref(),
+            Some("twilight-error:page:2:all:next")
+        );
+
+        let next_page = handler
+            .error_browser_response(&component_data(next.custom_id.clone().unwrap(), vec![]))
+            .unwrap();
+        assert_eq!(next_page.kind, InteractionResponseType::UpdateMessage);
+        assert_eq!(response_footer(&next_page), "Page 2 of 2, 7 errors");
+    }
+
+    #[test]
+    fn fingerprint_menu_counts_the_errors() {
+        let records = [
+            record("timed out after 5s"),
+            record("disk full"),
+            record("timed out after 9s"),
+        ];
+        let timed_out = records[0].fingerprint();
+
+        let menu = match fingerprint_menu(&records, Some(timed_out)) {
+            Component::SelectMenu(menu) => Some(menu),
+            Component::ActionRow(_)
+            | Component::Button(_)
+            | Component::TextInput(_)
+            | Component::Unknown(_) => None,
+        }
+        .unwrap();
+
+        assert_eq!(menu.custom_id, "twilight-error:fingerprint");
+        assert_eq!(menu.options.len(), 3);
+        assert_eq!(menu.options[0].value, ALL_ERRORS_VALUE);
+        assert!(!menu.options[0].default);
+        assert_eq!(menu.options[1].label, "timed out after 5s");
+        assert_eq!(menu.options[1].value, timed_out.to_string());
+        assert!(menu.options[1].default);
+        assert_eq!(
+            menu.options[1].description,
+            Some(format!("2 times, fingerprint {timed_out}"))
+        );
+        assert_eq!(menu.options[2].label, "disk full");
+        assert!(!menu.options[2].default);
+    }
+
+    #[test]
+    fn selected_fingerprint_filters_the_errors() {
+        let handler = ErrorHandler::new();
+        handler.history.push(record("timed out after 5s"));
+        handler.history.push(record("disk full"));
+        handler.history.push(record("timed out after 9s"));
+        let timed_out = record("timed out after 1s").fingerprint();
+
+        let response = handler
+            .error_browser_response(&component_data(
+                format!("{BROWSER_CUSTOM_ID_PREFIX}fingerprint"),
+                vec![timed_out.to_string()],
+            ))
+            .unwrap();
+
+        assert_eq!(response_footer(&response), "Page 1 of 1, 2 errors");
+        let buttons = response_buttons(&response);
+        assert_eq!(
+            buttons[1].custom_id,
+            Some(format!("twilight-error:page:2:{timed_out}:next"))
+        );
+    }
+
+    #[test]
+    fn other_components_are_ignored() {
+        let handler = ErrorHandler::new();
+
+        for custom_id in [
+            "music:page:2",
+            "twilight-error:unknown",
+            "twilight-error:page:x:all:next",
+        ] {
+            assert!(handler
+                .error_browser_response(&component_data(custom_id.to_owned(), vec![]))
+                .is_none());
+        }
+    }
+}
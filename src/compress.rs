@@ -0,0 +1,117 @@
+//! Compressing large attachments with gzip
+
+use miniz_oxide::deflate;
+use twilight_model::http::attachment::Attachment;
+
+use crate::ErrorHandler;
+
+/// The header of gzip files compressed with deflate, without a name or a
+/// modification time
+const GZIP_HEADER: [u8; 10] = [0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 0xff];
+
+/// The polynomial of the CRC-32 checksum in gzip files, reversed
+const CRC32_POLYNOMIAL: u32 = 0xedb8_8320;
+
+/// Replaces the attachments larger than the threshold with their gzip
+/// compressed version, adding `.gz` to their filename
+pub(crate) fn compress_large(attachments: &mut [Attachment], compress_over: Option<usize>) {
+    let Some(threshold) = compress_over else {
+        return;
+    };
+
+    for attachment in attachments
+        .iter_mut()
+        .filter(|attachment| attachment.file.len() > threshold)
+    {
+        attachment.file = gzip(&attachment.file);
+        attachment.filename.push_str(".gz");
+    }
+}
+
+/// Returns the data compressed into a gzip file
+#[allow(clippy::little_endian_bytes)] // gzip is little endian
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut file = GZIP_HEADER.to_vec();
+    file.extend(deflate::compress_to_vec(data, 9));
+    file.extend(crc32(data).to_le_bytes());
+    // the size modulo 2^32, which are the low bytes on every platform
+    file.extend(data.len().to_le_bytes().into_iter().take(4));
+    file
+}
+
+/// Returns the CRC-32 checksum of the data
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = u32::MAX;
+
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0_u8..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1_u8) ^ (CRC32_POLYNOMIAL & mask);
+        }
+    }
+
+    !crc
+}
+
+impl ErrorHandler {
+    /// Set the handler to compress the files attached to Discord messages
+    /// that are larger than the given number of bytes with gzip
+    ///
+    /// This keeps huge errors and backtraces within Discord's upload limit,
+    /// `.gz` is added to the name of the compressed files
+    pub fn compress_attachments(&mut self, threshold: usize) -> &mut Self {
+        self.config_mut().compress_attachments_over = Some(threshold);
+        self
+    }
+
+    /// Change the size above which attachments are compressed at runtime,
+    /// `None` to stop compressing them
+    ///
+    /// Same as [`Self::compress_attachments`] but through a shared reference
+    pub fn set_compress_attachments(&self, threshold: Option<usize>) {
+        self.update_config(|config| config.compress_attachments_over = threshold);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use miniz_oxide::inflate;
+
+    use super::*;
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    #[allow(clippy::little_endian_bytes)]
+    fn gzip_round_trips() {
+        let data = "error\n".repeat(1_000).into_bytes();
+
+        let file = gzip(&data);
+        let (header, rest) = file.split_at(GZIP_HEADER.len());
+        let (deflated, trailer) = rest.split_at(rest.len() - 8);
+
+        assert_eq!(header, GZIP_HEADER);
+        assert_eq!(inflate::decompress_to_vec(deflated).unwrap(), data);
+        assert_eq!(trailer[..4], crc32(&data).to_le_bytes());
+        assert_eq!(trailer[4..], 6_000_u32.to_le_bytes());
+    }
+
+    #[test]
+    fn only_large_attachments_are_compressed() {
+        let mut attachments = [
+            Attachment::from_bytes("small.txt".to_owned(), vec![0; 10], 0),
+            Attachment::from_bytes("large.txt".to_owned(), vec![0; 1_000], 1),
+        ];
+
+        compress_large(&mut attachments, Some(100));
+
+        assert_eq!(attachments[0].filename, "small.txt");
+        assert_eq!(attachments[1].filename, "large.txt.gz");
+        assert!(attachments[1].file.len() < 1_000);
+    }
+}
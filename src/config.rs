@@ -0,0 +1,439 @@
+//! The configuration of the handler, which can be changed at runtime
+
+#[cfg(feature = "discord")]
+use core::time::Duration;
+use core::{error::Error, fmt};
+use std::io;
+use std::path::PathBuf;
+#[cfg(feature = "discord")]
+use std::sync::Arc;
+
+use regex::Regex;
+#[cfg(feature = "discord")]
+use tokio::runtime::Handle;
+#[cfg(feature = "toml")]
+use toml::de::Error as TomlError;
+#[cfg(feature = "discord")]
+use twilight_http::Client;
+#[cfg(feature = "discord")]
+use twilight_model::id::{
+    marker::{ChannelMarker, RoleMarker, UserMarker, WebhookMarker},
+    Id,
+};
+
+#[cfg(feature = "cloudwatch")]
+use crate::cloudwatch::CloudWatchStream;
+#[cfg(feature = "file")]
+use crate::files::AdditionalFile;
+#[cfg(feature = "nats")]
+use crate::nats::{NatsAuth, NatsSink};
+#[cfg(any(
+    feature = "discord",
+    feature = "nats",
+    feature = "cloudwatch",
+    feature = "http-sink"
+))]
+use crate::RetryPolicy;
+#[cfg(feature = "test-util")]
+use crate::Transport;
+use crate::{
+    console::ConsoleOutput, context::ContextProviders, diagnostics::DiagnosticsHook,
+    formatter::Formatters, metrics::Metrics, DeliveryMode, Severity, TagRoute, TimestampFormat,
+    Timezone,
+};
+#[cfg(feature = "discord")]
+use crate::{
+    crash_loop::CrashLoopPolicy, escalation::RepeatedPolicy, locale::UserMessages,
+    style::SeverityStyles,
+};
+#[cfg(feature = "discord")]
+use crate::{DeliverySemantics, Sink, Truncation, TwilightErrorKind};
+
+/// The configuration of [`crate::ErrorHandler`]
+#[derive(Clone, Debug)]
+#[allow(clippy::struct_excessive_bools)]
+pub(crate) struct Config {
+    /// Channel to create message in on error
+    #[cfg(feature = "discord")]
+    pub(crate) channel: Option<Id<ChannelMarker>>,
+    /// User to DM on error if the channel isn't set
+    #[cfg(feature = "discord")]
+    pub(crate) owner: Option<Id<UserMarker>>,
+    /// Webhook to execute on error
+    #[cfg(feature = "discord")]
+    pub(crate) webhook: Option<(Id<WebhookMarker>, String)>,
+    /// NATS server and subject to publish to on error
+    #[cfg(feature = "nats")]
+    pub(crate) nats: Option<NatsSink>,
+    /// The credentials to connect to the NATS server with
+    #[cfg(feature = "nats")]
+    pub(crate) nats_auth: Option<NatsAuth>,
+    /// AWS log stream to push errors to
+    #[cfg(feature = "cloudwatch")]
+    pub(crate) cloudwatch: Option<CloudWatchStream>,
+    /// URL to post errors to
+    #[cfg(feature = "http-sink")]
+    pub(crate) http_endpoint: Option<String>,
+    /// Secret to sign the bodies posted to the URL with
+    #[cfg(feature = "http-sink")]
+    pub(crate) http_signing_secret: Option<String>,
+    /// File to append to on error
+    #[cfg(feature = "file")]
+    pub(crate) file: Option<PathBuf>,
+    /// Files to also append to on error
+    #[cfg(feature = "file")]
+    pub(crate) additional_files: Vec<AdditionalFile>,
+    /// How the sinks are used
+    pub(crate) delivery_mode: DeliveryMode,
+    /// Whether to print the error and append it to the file before the
+    /// Discord sinks
+    pub(crate) print_before_send: bool,
+    /// The severity errors need to be at least to be delivered to the sinks
+    pub(crate) min_severity: Severity,
+    /// Whether to only print what would be sent to Discord
+    pub(crate) dry_run: bool,
+    /// Whether to redact tokens from error messages
+    pub(crate) redact_secrets: bool,
+    /// The patterns to replace in error messages and their replacements
+    pub(crate) redaction_rules: Vec<(Regex, String)>,
+    /// The formatters set for the sinks
+    pub(crate) formatters: Formatters,
+    /// How error messages that are too long for Discord are shortened
+    #[cfg(feature = "discord")]
+    pub(crate) truncation: Truncation,
+    /// How the timestamps of errors are formatted
+    pub(crate) timestamp_format: TimestampFormat,
+    /// The timezone of the timestamps of errors
+    pub(crate) timezone: Timezone,
+    /// Whether to capture the backtrace of the handled errors
+    pub(crate) capture_backtraces: bool,
+    /// Whether to attach the host and process to every report
+    pub(crate) include_metadata: bool,
+    /// The name and version of the application
+    pub(crate) app: Option<String>,
+    /// The commit the application was built from
+    pub(crate) commit: Option<String>,
+    /// Whether to pin the messages of critical errors in the channel
+    #[cfg(feature = "discord")]
+    pub(crate) pin_critical: bool,
+    /// How long the messages of non-critical errors are kept in the channel
+    #[cfg(feature = "discord")]
+    pub(crate) delete_non_critical_after: Option<Duration>,
+    /// How long incidents stay open without their error happening, `None`
+    /// if incidents aren't opened
+    #[cfg(feature = "discord")]
+    pub(crate) incident_inactivity: Option<Duration>,
+    /// The errors per minute above which they're escalated and the role to
+    /// mention then
+    #[cfg(feature = "discord")]
+    pub(crate) escalation: Option<(u64, Option<Id<RoleMarker>>)>,
+    /// When errors are detected as a crash loop
+    #[cfg(feature = "discord")]
+    pub(crate) crash_loop: Option<CrashLoopPolicy>,
+    /// What's done with the errors that have each tag
+    pub(crate) tag_routes: Vec<(String, TagRoute)>,
+    /// Where errors that keep happening are additionally delivered
+    #[cfg(feature = "discord")]
+    pub(crate) repeated_policy: Option<RepeatedPolicy>,
+    /// Whether to merge identical errors that happen while one is being
+    /// delivered
+    #[cfg(feature = "discord")]
+    pub(crate) coalesce_identical: bool,
+    /// The size in bytes above which attachments are compressed
+    #[cfg(feature = "compression")]
+    pub(crate) compress_attachments_over: Option<usize>,
+    /// The severities of the Twilight error kinds that don't have their
+    /// default severity
+    #[cfg(feature = "discord")]
+    pub(crate) twilight_severities: Vec<(TwilightErrorKind, Severity)>,
+    /// The log file attached to critical errors and how many bytes from its
+    /// end are attached
+    #[cfg(feature = "discord")]
+    pub(crate) log_tail: Option<(PathBuf, u64)>,
+    /// Whether to attach where errors were reported from
+    pub(crate) include_location: bool,
+    /// The function called with the operational events of the handler
+    pub(crate) diagnostics_hook: Option<DiagnosticsHook>,
+    /// The hook called with the activity of the handler
+    pub(crate) metrics_hook: Option<Metrics>,
+    /// Where errors and notices are printed
+    pub(crate) console_output: ConsoleOutput,
+    /// The emoji and embed color of the Discord messages of each severity
+    #[cfg(feature = "discord")]
+    pub(crate) severity_styles: SeverityStyles,
+    /// The messages shown to users when their interaction failed by locale
+    #[cfg(feature = "discord")]
+    pub(crate) user_messages: UserMessages,
+    /// How each Discord sink retries failed deliveries
+    #[cfg(feature = "discord")]
+    pub(crate) delivery_semantics: Vec<(Sink, DeliverySemantics)>,
+    /// How the network sinks retry failed deliveries
+    #[cfg(any(
+        feature = "discord",
+        feature = "nats",
+        feature = "cloudwatch",
+        feature = "http-sink"
+    ))]
+    pub(crate) retry_policy: Option<RetryPolicy>,
+    /// The file errors are kept in until they're delivered to Discord
+    #[cfg(all(feature = "discord", feature = "json"))]
+    pub(crate) outbox: Option<PathBuf>,
+    /// Whether to keep errors in the outbox while the Discord sinks are rate
+    /// limited
+    #[cfg(all(feature = "discord", feature = "json"))]
+    pub(crate) defer_rate_limited: bool,
+    /// The file critical errors are written to for container orchestrators
+    pub(crate) termination_log: Option<PathBuf>,
+    /// Whether to attach how long the handler has been running
+    pub(crate) include_uptime: bool,
+    /// The providers of the context attached to every report
+    pub(crate) context_providers: ContextProviders,
+    /// Whether to attach the state of the process to critical errors
+    pub(crate) snapshot_critical: bool,
+    /// The code the process exits with after a fatal error
+    #[cfg(feature = "discord")]
+    pub(crate) fatal_exit_code: i32,
+    /// How long to wait for a fatal error to be delivered before exiting
+    #[cfg(feature = "discord")]
+    pub(crate) fatal_deadline: Duration,
+    /// The runtime and client to deliver to the Discord sinks with from code
+    /// that isn't async
+    #[cfg(feature = "discord")]
+    pub(crate) blocking_runtime: Option<(Handle, Arc<Client>)>,
+    /// The client to deliver to the Discord sinks with when none is given
+    #[cfg(feature = "discord")]
+    pub(crate) default_client: Option<Arc<Client>>,
+    /// What the messages of the Discord sinks are sent through instead of
+    /// HTTP
+    #[cfg(feature = "test-util")]
+    pub(crate) transport: Option<Arc<dyn Transport>>,
+    /// Whether to include the span trace of the handled errors
+    #[cfg(feature = "tracing-error")]
+    pub(crate) capture_span_traces: bool,
+}
+
+impl Config {
+    /// Make a configuration that only prints errors to [`std::io::stderr`]
+    pub(crate) const fn new() -> Self {
+        Self {
+            #[cfg(feature = "discord")]
+            channel: None,
+            #[cfg(feature = "discord")]
+            owner: None,
+            #[cfg(feature = "discord")]
+            webhook: None,
+            #[cfg(feature = "nats")]
+            nats: None,
+            #[cfg(feature = "nats")]
+            nats_auth: None,
+            #[cfg(feature = "cloudwatch")]
+            cloudwatch: None,
+            #[cfg(feature = "http-sink")]
+            http_endpoint: None,
+            #[cfg(feature = "http-sink")]
+            http_signing_secret: None,
+            #[cfg(feature = "file")]
+            file: None,
+            #[cfg(feature = "file")]
+            additional_files: Vec::new(),
+            delivery_mode: DeliveryMode::Concurrent,
+            print_before_send: false,
+            min_severity: Severity::Warning,
+            dry_run: false,
+            redact_secrets: true,
+            redaction_rules: Vec::new(),
+            formatters: Formatters::NONE,
+            #[cfg(feature = "discord")]
+            truncation: Truncation::HeadAndTail,
+            timestamp_format: TimestampFormat::Rfc3339,
+            timezone: Timezone::Utc,
+            capture_backtraces: false,
+            include_metadata: true,
+            app: None,
+            commit: None,
+            #[cfg(feature = "discord")]
+            pin_critical: false,
+            #[cfg(feature = "discord")]
+            delete_non_critical_after: None,
+            #[cfg(feature = "discord")]
+            incident_inactivity: None,
+            #[cfg(feature = "discord")]
+            escalation: None,
+            #[cfg(feature = "discord")]
+            crash_loop: None,
+            tag_routes: Vec::new(),
+            #[cfg(feature = "discord")]
+            repeated_policy: None,
+            #[cfg(feature = "discord")]
+            coalesce_identical: false,
+            #[cfg(feature = "compression")]
+            compress_attachments_over: None,
+            #[cfg(feature = "discord")]
+            twilight_severities: Vec::new(),
+            #[cfg(feature = "discord")]
+            log_tail: None,
+            include_location: true,
+            diagnostics_hook: None,
+            metrics_hook: None,
+            console_output: ConsoleOutput::Stderr,
+            #[cfg(feature = "discord")]
+            severity_styles: SeverityStyles::DEFAULT,
+            #[cfg(feature = "discord")]
+            user_messages: UserMessages::new(),
+            #[cfg(feature = "discord")]
+            delivery_semantics: Vec::new(),
+            #[cfg(any(
+                feature = "discord",
+                feature = "nats",
+                feature = "cloudwatch",
+                feature = "http-sink"
+            ))]
+            retry_policy: None,
+            #[cfg(all(feature = "discord", feature = "json"))]
+            outbox: None,
+            #[cfg(all(feature = "discord", feature = "json"))]
+            defer_rate_limited: false,
+            termination_log: None,
+            include_uptime: false,
+            context_providers: ContextProviders::NONE,
+            snapshot_critical: false,
+            #[cfg(feature = "discord")]
+            fatal_exit_code: 1,
+            #[cfg(feature = "discord")]
+            fatal_deadline: Duration::from_secs(10),
+            #[cfg(feature = "discord")]
+            blocking_runtime: None,
+            #[cfg(feature = "discord")]
+            default_client: None,
+            #[cfg(feature = "test-util")]
+            transport: None,
+            #[cfg(feature = "tracing-error")]
+            capture_span_traces: false,
+        }
+    }
+}
+
+/// The error returned when making a handler from configuration fails
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ConfigError {
+    /// Reading the configuration file failed
+    Io(io::Error),
+    /// The configuration file isn't valid TOML or doesn't match
+    /// [`crate::ErrorHandlerConfig`]
+    #[cfg(feature = "toml")]
+    Toml(TomlError),
+    /// The configuration file isn't valid JSON or doesn't match
+    /// [`crate::ErrorHandlerConfig`]
+    #[cfg(feature = "json")]
+    Json(serde_json::Error),
+    /// The webhook URL isn't in the form of
+    /// `https://discord.com/api/webhooks/{id}/{token}`
+    InvalidWebhookUrl(String),
+    /// The webhook isn't in the form of `{id}/{token}` or a webhook URL
+    #[cfg(feature = "discord")]
+    InvalidWebhookSpec(String),
+    /// The ID is 0, which isn't a valid Discord ID
+    #[cfg(feature = "discord")]
+    ZeroId,
+    /// The environment variable isn't valid for the option it sets
+    InvalidEnvVar {
+        /// The name of the environment variable
+        name: &'static str,
+        /// The value of the environment variable
+        value: String,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read the configuration file: {err}"),
+            #[cfg(feature = "toml")]
+            Self::Toml(err) => write!(f, "invalid TOML configuration: {err}"),
+            #[cfg(feature = "json")]
+            Self::Json(err) => write!(f, "invalid JSON configuration: {err}"),
+            Self::InvalidWebhookUrl(url) => write!(f, "invalid webhook URL: {url}"),
+            #[cfg(feature = "discord")]
+            Self::InvalidWebhookSpec(spec) => write!(f, "invalid webhook: {spec}"),
+            #[cfg(feature = "discord")]
+            Self::ZeroId => f.write_str("the ID is 0"),
+            Self::InvalidEnvVar { name, value } => {
+                write!(f, "invalid value for environment variable {name}: {value}")
+            }
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            #[cfg(feature = "toml")]
+            Self::Toml(err) => Some(err),
+            #[cfg(feature = "json")]
+            Self::Json(err) => Some(err),
+            Self::InvalidWebhookUrl(_) | Self::InvalidEnvVar { .. } => None,
+            #[cfg(feature = "discord")]
+            Self::InvalidWebhookSpec(_) | Self::ZeroId => None,
+        }
+    }
+}
+
+/// Returns the ID and token of the webhook in the URL, `None` if it's not a
+/// webhook URL
+#[cfg(feature = "discord")]
+pub(crate) fn parse_webhook_url(url: &str) -> Option<(Id<WebhookMarker>, String)> {
+    let (_, path_and_query) = url.split_once("/webhooks/")?;
+    let path = path_and_query.split(['?', '#']).next()?;
+    let (id, token_and_slash) = path.split_once('/')?;
+    let token = token_and_slash.trim_end_matches('/');
+
+    if token.is_empty() || token.contains('/') {
+        return None;
+    }
+
+    Some((Id::new_checked(id.parse().ok()?)?, token.to_owned()))
+}
+
+#[cfg(test)]
+#[cfg(feature = "discord")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn webhook_url_is_parsed() {
+        let parsed = parse_webhook_url("https://discord.com/api/webhooks/123/token-abc");
+
+        assert_eq!(parsed, Some((Id::new(123), "token-abc".to_owned())));
+    }
+
+    #[test]
+    fn webhook_url_with_version_query_and_slash_is_parsed() {
+        for url in [
+            "https://discord.com/api/v10/webhooks/123/token/",
+            "https://canary.discord.com/api/webhooks/123/token?wait=true",
+            "https://discord.com/api/webhooks/123/token#fragment",
+        ] {
+            assert_eq!(
+                parse_webhook_url(url),
+                Some((Id::new(123), "token".to_owned())),
+                "{url}"
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_webhook_urls_are_rejected() {
+        for url in [
+            "https://discord.com/api/channels/123",
+            "https://discord.com/api/webhooks/123",
+            "https://discord.com/api/webhooks/123/",
+            "https://discord.com/api/webhooks/0/token",
+            "https://discord.com/api/webhooks/abc/token",
+            "https://discord.com/api/webhooks/123/token/extra",
+        ] {
+            assert_eq!(parse_webhook_url(url), None, "{url}");
+        }
+    }
+}
@@ -0,0 +1,330 @@
+//! Making a handler from a configuration file or a section of one
+
+use core::time::Duration;
+#[cfg(feature = "file")]
+use std::path::PathBuf;
+#[cfg(any(feature = "toml", feature = "json"))]
+use std::{fs, path::Path};
+
+use serde::Deserialize;
+#[cfg(feature = "discord")]
+use twilight_model::id::{
+    marker::{ChannelMarker, UserMarker},
+    Id,
+};
+
+#[cfg(feature = "discord")]
+use crate::config::parse_webhook_url;
+#[cfg(feature = "discord")]
+use crate::Truncation;
+use crate::{
+    config::Config, ConfigError, DeliveryMode, ErrorHandler, Severity, TimestampFormat, Timezone,
+};
+
+/// The configuration of a handler, to deserialize from the bot's own
+/// configuration
+///
+/// Every field is optional, such as with the TOML below
+///
+/// ```toml
+/// channel_id = "123"
+/// webhook_url = "https://discord.com/api/webhooks/456/token"
+/// file = "errors.log"
+/// min_severity = "error"
+/// delivery_mode = "fallback"
+/// dry_run = false
+/// redact_secrets = true
+/// truncation = "head_and_tail"
+/// timestamp_format = "%Y-%m-%d %H:%M:%S"
+/// timezone = "local"
+///
+/// [circuit_breaker]
+/// threshold = 3
+/// probe_interval_secs = 300
+/// ```
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
+pub struct ErrorHandlerConfig {
+    /// Channel to create message in on error, see [`ErrorHandler::channel`]
+    #[cfg(feature = "discord")]
+    pub channel_id: Option<Id<ChannelMarker>>,
+    /// User to DM on error, see [`ErrorHandler::owner`]
+    #[cfg(feature = "discord")]
+    pub owner_id: Option<Id<UserMarker>>,
+    /// URL of the webhook to execute on error, see [`ErrorHandler::webhook`]
+    #[cfg(feature = "discord")]
+    pub webhook_url: Option<String>,
+    /// File to append to on error, see [`ErrorHandler::file`]
+    #[cfg(feature = "file")]
+    pub file: Option<PathBuf>,
+    /// See [`ErrorHandler::min_severity`]
+    pub min_severity: Option<Severity>,
+    /// See [`ErrorHandler::delivery_mode`]
+    pub delivery_mode: Option<DeliveryMode>,
+    /// See [`ErrorHandler::circuit_breaker`]
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    /// See [`ErrorHandler::dry_run`]
+    pub dry_run: Option<bool>,
+    /// See [`ErrorHandler::redact_secrets`]
+    pub redact_secrets: Option<bool>,
+    /// See [`ErrorHandler::truncation`]
+    #[cfg(feature = "discord")]
+    pub truncation: Option<Truncation>,
+    /// A `strftime` pattern, see [`TimestampFormat::Custom`] and
+    /// [`ErrorHandler::timestamp_format`], RFC 3339 by default
+    pub timestamp_format: Option<String>,
+    /// See [`ErrorHandler::timestamp_format`]
+    pub timezone: Option<Timezone>,
+}
+
+/// The circuit breaker section of [`ErrorHandlerConfig`]
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
+pub struct CircuitBreakerConfig {
+    /// The number of consecutive failures to disable a sink after
+    pub threshold: u32,
+    /// The interval to retry disabled sinks at in seconds
+    pub probe_interval_secs: u64,
+}
+
+/// The format of a configuration file
+#[cfg(any(feature = "toml", feature = "json"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConfigFormat {
+    /// See [`ErrorHandler::from_toml`]
+    #[cfg(feature = "toml")]
+    Toml,
+    /// See [`ErrorHandler::from_json`]
+    #[cfg(feature = "json")]
+    Json,
+}
+
+#[cfg(any(feature = "toml", feature = "json"))]
+impl ConfigFormat {
+    /// Reads and deserializes the configuration file at the given path
+    pub(crate) fn read(self, path: &Path) -> Result<ErrorHandlerConfig, ConfigError> {
+        let content = fs::read_to_string(path).map_err(ConfigError::Io)?;
+
+        match self {
+            #[cfg(feature = "toml")]
+            Self::Toml => toml::from_str(&content).map_err(ConfigError::Toml),
+            #[cfg(feature = "json")]
+            Self::Json => serde_json::from_str(&content).map_err(ConfigError::Json),
+        }
+    }
+}
+
+impl ErrorHandler {
+    /// Make a handler from the given configuration
+    ///
+    /// # Errors
+    /// Returns [`ConfigError::InvalidWebhookUrl`] if the webhook URL is
+    /// invalid
+    pub fn from_config(config: ErrorHandlerConfig) -> Result<Self, ConfigError> {
+        let handler = Self::new();
+        handler.apply_config(config)?;
+        Ok(handler)
+    }
+
+    /// Make a handler from the TOML file at the given path
+    ///
+    /// The file should match [`ErrorHandlerConfig`]
+    ///
+    /// # Errors
+    /// Returns [`ConfigError`] if reading or deserializing the file fails or
+    /// the configuration is invalid
+    #[cfg(feature = "toml")]
+    pub fn from_toml(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        Self::from_config(ConfigFormat::Toml.read(path.as_ref())?)
+    }
+
+    /// Make a handler from the JSON file at the given path
+    ///
+    /// The file should match [`ErrorHandlerConfig`]
+    ///
+    /// # Errors
+    /// Returns [`ConfigError`] if reading or deserializing the file fails or
+    /// the configuration is invalid
+    #[cfg(feature = "json")]
+    pub fn from_json(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        Self::from_config(ConfigFormat::Json.read(path.as_ref())?)
+    }
+
+    /// Replace the configuration of the handler with the given one at runtime
+    ///
+    /// Options that aren't set in the configuration are reset to their
+    /// defaults, options that can't be set in it, such as
+    /// [`Self::redact_with`], are kept, errors that are already being handled keep
+    /// using the previous configuration
+    ///
+    /// # Errors
+    /// Returns [`ConfigError::InvalidWebhookUrl`] if the webhook URL is
+    /// invalid, in which case the configuration isn't changed
+    #[cfg_attr(
+        not(any(feature = "discord", feature = "file")),
+        allow(clippy::needless_pass_by_value)
+    )]
+    pub fn apply_config(&self, config: ErrorHandlerConfig) -> Result<(), ConfigError> {
+        #[cfg(feature = "discord")]
+        let webhook = config
+            .webhook_url
+            .map(|url| parse_webhook_url(&url).ok_or(ConfigError::InvalidWebhookUrl(url)))
+            .transpose()?;
+
+        let defaults = Config::new();
+        self.update_config(|current| {
+            #[cfg(feature = "discord")]
+            {
+                current.channel = config.channel_id;
+                current.owner = config.owner_id;
+                current.webhook = webhook;
+            };
+            #[cfg(feature = "file")]
+            {
+                current.file = config.file;
+            };
+            current.min_severity = config.min_severity.unwrap_or(defaults.min_severity);
+            current.delivery_mode = config.delivery_mode.unwrap_or(defaults.delivery_mode);
+            current.dry_run = config.dry_run.unwrap_or(defaults.dry_run);
+            current.redact_secrets = config.redact_secrets.unwrap_or(defaults.redact_secrets);
+            #[cfg(feature = "discord")]
+            {
+                current.truncation = config.truncation.unwrap_or(defaults.truncation);
+            };
+            current.timestamp_format = config
+                .timestamp_format
+                .map_or(defaults.timestamp_format, TimestampFormat::Custom);
+            current.timezone = config.timezone.unwrap_or(defaults.timezone);
+        });
+        self.set_circuit_breaker(config.circuit_breaker.map(|circuit_breaker| {
+            (
+                circuit_breaker.threshold,
+                Duration::from_secs(circuit_breaker.probe_interval_secs),
+            )
+        }));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "toml", feature = "discord"))]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    /// Writes the content to a file in the temporary directory, returning its
+    /// path
+    fn write_file(name: &str, content: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("twilight-error-{}-{name}", process::id()));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn toml_file_is_applied() {
+        let path = write_file(
+            "config.toml",
+            r#"
+            channel_id = "123"
+            webhook_url = "https://discord.com/api/webhooks/456/token"
+            min_severity = "critical"
+            delivery_mode = "fallback"
+            dry_run = true
+            redact_secrets = false
+            truncation = "tail"
+            timestamp_format = "%Y-%m-%d"
+            timezone = "local"
+
+            [circuit_breaker]
+            threshold = 3
+            probe_interval_secs = 60
+            "#,
+        );
+
+        let handler = ErrorHandler::from_toml(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let config = handler.config();
+        assert_eq!(config.channel, Some(Id::new(123)));
+        assert_eq!(config.webhook, Some((Id::new(456), "token".to_owned())));
+        assert_eq!(config.min_severity, Severity::Critical);
+        assert_eq!(config.delivery_mode, DeliveryMode::Fallback);
+        assert!(config.dry_run);
+        assert!(!config.redact_secrets);
+        assert_eq!(config.truncation, Truncation::Tail);
+        assert_eq!(
+            config.timestamp_format,
+            TimestampFormat::Custom("%Y-%m-%d".to_owned())
+        );
+        assert_eq!(config.timezone, Timezone::Local);
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn json_file_is_applied() {
+        let path = write_file(
+            "config.json",
+            r#"{ "channel_id": "123", "min_severity": "warning", "truncation": "fallback" }"#,
+        );
+
+        let handler = ErrorHandler::from_json(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let config = handler.config();
+        assert_eq!(config.channel, Some(Id::new(123)));
+        assert_eq!(config.min_severity, Severity::Warning);
+        assert_eq!(config.truncation, Truncation::Fallback);
+    }
+
+    #[test]
+    fn unset_options_are_reset() {
+        let mut handler = ErrorHandler::new();
+        handler
+            .channel(Id::new(123))
+            .dry_run(true)
+            .truncation(Truncation::Head)
+            .timestamp_format(TimestampFormat::Custom("%H".to_owned()), Timezone::Local);
+
+        handler.apply_config(ErrorHandlerConfig::default()).unwrap();
+
+        let config = handler.config();
+        let defaults = Config::new();
+        assert_eq!(config.channel, None);
+        assert!(!config.dry_run);
+        assert_eq!(config.truncation, defaults.truncation);
+        assert_eq!(config.timestamp_format, defaults.timestamp_format);
+        assert_eq!(config.timezone, defaults.timezone);
+    }
+
+    #[test]
+    fn invalid_webhook_url_keeps_the_configuration() {
+        let mut handler = ErrorHandler::new();
+        handler.channel(Id::new(123));
+        let config = ErrorHandlerConfig {
+            webhook_url: Some("https://example.com".to_owned()),
+            ..ErrorHandlerConfig::default()
+        };
+
+        let result = handler.apply_config(config);
+
+        assert!(
+            matches!(result, Err(ConfigError::InvalidWebhookUrl(url)) if url == "https://example.com")
+        );
+        assert_eq!(handler.config().channel, Some(Id::new(123)));
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let path = write_file("unknown.toml", "chanel_id = 123");
+
+        let result = ErrorHandler::from_toml(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(result, Err(ConfigError::Toml(_))));
+    }
+}
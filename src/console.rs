@@ -0,0 +1,157 @@
+//! Printing errors and notices to the console
+
+#[cfg(feature = "json")]
+use serde_json::{json, Map, Value};
+#[cfg(feature = "tracing")]
+use tracing::field;
+
+#[cfg(any(feature = "tracing", feature = "log"))]
+use crate::Severity;
+use crate::{config::Config, formatter::Message, ErrorHandler};
+
+/// The target of the `tracing` events and `log` records of
+/// [`ConsoleOutput::Tracing`] and [`ConsoleOutput::Log`]
+#[cfg(any(feature = "tracing", feature = "log"))]
+pub const CONSOLE_TARGET: &str = concat!(env!("CARGO_CRATE_NAME"), "::console");
+
+/// Where errors and the handler's notices are printed, set with
+/// [`ErrorHandler::console_output`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConsoleOutput {
+    /// Print them to [`std::io::stderr`], colored by severity if it's a
+    /// terminal
+    #[default]
+    Stderr,
+    /// Emit them as `tracing` events with the [`CONSOLE_TARGET`] target and
+    /// the ID, severity and fingerprint of errors as fields, at the `ERROR`
+    /// level for errors and critical errors and `WARN` otherwise
+    #[cfg(feature = "tracing")]
+    Tracing,
+    /// Log them with the [`CONSOLE_TARGET`] target, at the `Error` level for
+    /// errors and critical errors and `Warn` otherwise
+    #[cfg(feature = "log")]
+    Log,
+    /// Print them to [`std::io::stderr`] as one JSON object per line with the
+    /// printed text as `message`, and the `error_id`, `severity`,
+    /// `fingerprint`, `kind`, `tags`, `correlation_id` and fields as `context`
+    /// of errors, so that container log collectors ingest them as structured
+    /// logs
+    #[cfg(feature = "json")]
+    Json,
+}
+
+impl Config {
+    /// Prints the content of the message according to
+    /// [`ErrorHandler::console_output`]
+    #[allow(clippy::print_stderr)]
+    #[cfg_attr(
+        not(any(feature = "tracing", feature = "log")),
+        allow(unused_variables)
+    )]
+    pub(crate) fn print(&self, message: Message<'_>, content: &str) {
+        match self.console_output {
+            ConsoleOutput::Stderr => eprintln!("{content}"),
+            #[cfg(feature = "tracing")]
+            ConsoleOutput::Tracing => {
+                let record = match message {
+                    Message::Record(record) => Some(record),
+                    Message::Notice(_) => None,
+                };
+                let error_id = record.map(|error| field::display(error.id));
+                let severity = record.map(|error| field::display(error.severity));
+                let fingerprint = record.map(|error| field::display(error.fingerprint()));
+                let trimmed = content.trim_start();
+                if is_error(message) {
+                    tracing::error!(
+                        target: CONSOLE_TARGET,
+                        error_id,
+                        severity,
+                        fingerprint,
+                        "{trimmed}"
+                    );
+                } else {
+                    tracing::warn!(
+                        target: CONSOLE_TARGET,
+                        error_id,
+                        severity,
+                        fingerprint,
+                        "{trimmed}"
+                    );
+                }
+            }
+            #[cfg(feature = "log")]
+            ConsoleOutput::Log => {
+                let trimmed = content.trim_start();
+                if is_error(message) {
+                    log::error!(target: CONSOLE_TARGET, "{trimmed}");
+                } else {
+                    log::warn!(target: CONSOLE_TARGET, "{trimmed}");
+                }
+            }
+            #[cfg(feature = "json")]
+            ConsoleOutput::Json => eprintln!("{}", json_line(message, content)),
+        }
+    }
+
+    /// Prints the notice according to [`ErrorHandler::console_output`]
+    #[cfg_attr(not(feature = "discord"), allow(dead_code))]
+    pub(crate) fn print_notice(&self, notice: &str) {
+        self.print(Message::Notice(notice), notice);
+    }
+}
+
+/// Returns the JSON object printed for the message with
+/// [`ConsoleOutput::Json`]
+#[cfg(feature = "json")]
+fn json_line(message: Message<'_>, content: &str) -> Value {
+    let mut line = json!({ "message": content.trim() });
+    if let (Message::Record(record), Value::Object(object)) = (message, &mut line) {
+        let fields = record
+            .fields
+            .iter()
+            .map(|(name, value)| (name.clone(), Value::String(value.clone())))
+            .collect::<Map<_, _>>();
+        object.extend([
+            ("error_id".to_owned(), json!(record.id.to_string())),
+            ("severity".to_owned(), json!(record.severity.to_string())),
+            (
+                "fingerprint".to_owned(),
+                json!(record.fingerprint().to_string()),
+            ),
+            ("kind".to_owned(), json!(record.kind)),
+            ("tags".to_owned(), json!(record.tags)),
+            ("correlation_id".to_owned(), json!(record.correlation_id)),
+            ("context".to_owned(), Value::Object(fields)),
+        ]);
+    }
+    line
+}
+
+/// Returns whether the message is of an error or a critical error
+#[cfg(any(feature = "tracing", feature = "log"))]
+const fn is_error(message: Message<'_>) -> bool {
+    matches!(
+        message.severity(),
+        Some(Severity::Error | Severity::Critical)
+    )
+}
+
+impl ErrorHandler {
+    /// Set where errors and the handler's notices are printed, such as to
+    /// emit them as `tracing` events so that they're formatted and filtered
+    /// like the rest of the application's output
+    ///
+    /// Defaults to [`ConsoleOutput::Stderr`]
+    pub fn console_output(&mut self, output: ConsoleOutput) -> &mut Self {
+        self.config_mut().console_output = output;
+        self
+    }
+
+    /// Change where errors and the handler's notices are printed at runtime
+    ///
+    /// Same as [`Self::console_output`] but through a shared reference
+    pub fn set_console_output(&self, output: ConsoleOutput) {
+        self.update_config(|config| config.console_output = output);
+    }
+}
@@ -0,0 +1,137 @@
+//! Attaching context that changes over time to every report when it's handled
+
+use core::fmt;
+#[cfg(feature = "discord")]
+use core::{future::Future, pin::Pin};
+use std::sync::Arc;
+
+use crate::ErrorHandler;
+
+/// The future returned from [`AsyncContextProvider::context`]
+#[cfg(feature = "discord")]
+pub type ContextFuture<'a> = Pin<Box<dyn Future<Output = Vec<(String, String)>> + Send + 'a>>;
+
+/// Provides fields attached to every report when it's handled, such as cache
+/// sizes or queue depths, set with [`ErrorHandler::context_provider`]
+///
+/// It's implemented for closures that return the fields
+pub trait ContextProvider: Send + Sync {
+    /// Returns the names and values of the fields to attach
+    fn context(&self) -> Vec<(String, String)>;
+}
+
+impl<F: Fn() -> Vec<(String, String)> + Send + Sync> ContextProvider for F {
+    fn context(&self) -> Vec<(String, String)> {
+        self()
+    }
+}
+
+/// Provides fields attached to every report when it's handled, such as shard
+/// latencies, by awaiting a future, set with
+/// [`ErrorHandler::async_context_provider`]
+///
+/// It's implemented for closures that return a future of the fields
+#[cfg(feature = "discord")]
+pub trait AsyncContextProvider: Send + Sync {
+    /// Returns the future of the names and values of the fields to attach
+    fn context(&self) -> ContextFuture<'_>;
+}
+
+#[cfg(feature = "discord")]
+impl<F, Fut> AsyncContextProvider for F
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = Vec<(String, String)>> + Send + 'static,
+{
+    fn context(&self) -> ContextFuture<'_> {
+        Box::pin(self())
+    }
+}
+
+/// The providers of the context attached to every report
+#[derive(Clone)]
+pub(crate) struct ContextProviders {
+    /// The providers called in every handle method
+    providers: Vec<Arc<dyn ContextProvider>>,
+    /// The providers awaited in the async handle methods
+    #[cfg(feature = "discord")]
+    async_providers: Vec<Arc<dyn AsyncContextProvider>>,
+}
+
+impl ContextProviders {
+    /// No providers, so that no context is attached
+    pub(crate) const NONE: Self = Self {
+        providers: Vec::new(),
+        #[cfg(feature = "discord")]
+        async_providers: Vec::new(),
+    };
+
+    /// Returns the fields of the providers that aren't async
+    pub(crate) fn fields(&self) -> Vec<(String, String)> {
+        self.providers
+            .iter()
+            .flat_map(|provider| provider.context())
+            .collect()
+    }
+
+    /// Returns the fields of the async providers, awaiting them one after
+    /// another
+    #[cfg(feature = "discord")]
+    pub(crate) async fn async_fields(&self) -> Vec<(String, String)> {
+        let mut fields = Vec::new();
+        for provider in &self.async_providers {
+            fields.extend(provider.context().await);
+        }
+        fields
+    }
+}
+
+impl fmt::Debug for ContextProviders {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("ContextProviders");
+        debug.field("providers", &self.providers.len());
+        #[cfg(feature = "discord")]
+        debug.field("async_providers", &self.async_providers.len());
+        debug.finish()
+    }
+}
+
+impl ErrorHandler {
+    /// Add a provider of fields attached to every report when it's handled,
+    /// so that the places errors are handled at don't need to know about
+    /// them
+    ///
+    /// The fields are attached after the report's own fields and before
+    /// [`Self::include_metadata`]
+    pub fn context_provider(&mut self, provider: impl ContextProvider + 'static) -> &mut Self {
+        self.config_mut()
+            .context_providers
+            .providers
+            .push(Arc::new(provider));
+        self
+    }
+
+    /// Add a provider of fields attached to every report when it's handled
+    /// by awaiting a future
+    ///
+    /// Same as [`Self::context_provider`] but it's only awaited in the async
+    /// handle methods, such as [`Self::handle`], and not in
+    /// [`Self::handle_sync`]
+    #[cfg(feature = "discord")]
+    pub fn async_context_provider(
+        &mut self,
+        provider: impl AsyncContextProvider + 'static,
+    ) -> &mut Self {
+        self.config_mut()
+            .context_providers
+            .async_providers
+            .push(Arc::new(provider));
+        self
+    }
+
+    /// Stop attaching the fields of the providers set with
+    /// [`Self::context_provider`] and the async providers
+    pub fn remove_context_providers(&self) {
+        self.update_config(|config| config.context_providers = ContextProviders::NONE);
+    }
+}
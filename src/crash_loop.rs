@@ -0,0 +1,189 @@
+//! Detecting errors that keep happening right after startup
+
+use core::time::Duration;
+use std::{
+    collections::HashMap,
+    sync::{Mutex, PoisonError},
+};
+
+use tokio::time::Instant;
+use twilight_http::Client;
+use twilight_model::id::{marker::RoleMarker, Id};
+
+use crate::{
+    config::Config, formatter::Message, DiagnosticEvent, ErrorHandler, ErrorId, ErrorRecord,
+    ErrorReport, Fingerprint, Severity,
+};
+
+/// When errors are detected as a crash loop, see
+/// [`ErrorHandler::crash_loop_detection`]
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct CrashLoopPolicy {
+    /// The number of times an error needs to happen to be a crash loop
+    pub(crate) occurrences: usize,
+    /// The duration since startup the occurrences are counted in, also how
+    /// long the errors of a crash loop stay muted after it's detected
+    pub(crate) window: Duration,
+    /// The role to mention, such as the on-call role
+    pub(crate) role_id: Option<Id<RoleMarker>>,
+}
+
+/// The state of the crash loops
+#[derive(Debug, Default)]
+struct State {
+    /// The number of times the errors with each fingerprint happened since
+    /// startup
+    counts: HashMap<Fingerprint, usize>,
+    /// When the errors with each fingerprint were detected as crash loops
+    detected: HashMap<Fingerprint, Instant>,
+}
+
+/// The errors that happened since startup and the crash loops among them
+#[derive(Debug, Default)]
+pub(crate) struct CrashLoops {
+    /// The state of the crash loops
+    state: Mutex<State>,
+}
+
+/// What an error is in regards to crash loops
+enum Detection {
+    /// The error isn't in a crash loop
+    None,
+    /// The error was just detected as a crash loop
+    Detected,
+    /// The error is in a crash loop that was already alerted
+    Looping,
+}
+
+impl CrashLoops {
+    /// Records that the error with the fingerprint happened, counting it only
+    /// if it's still in the window, a crash loop stays muted for the window
+    /// after it's detected
+    fn record(
+        &self,
+        fingerprint: Fingerprint,
+        policy: CrashLoopPolicy,
+        in_window: bool,
+    ) -> Detection {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(detected_at) = state.detected.get(&fingerprint) {
+            if detected_at.elapsed() < policy.window {
+                return Detection::Looping;
+            }
+            state.detected.remove(&fingerprint);
+        }
+        if !in_window {
+            state.counts.clear();
+            return Detection::None;
+        }
+
+        let count = state.counts.entry(fingerprint).or_default();
+        *count = count.saturating_add(1);
+        if *count < policy.occurrences {
+            return Detection::None;
+        }
+
+        state.counts.remove(&fingerprint);
+        state.detected.insert(fingerprint, Instant::now());
+        Detection::Detected
+    }
+}
+
+impl ErrorHandler {
+    /// Returns whether the error is in a crash loop, delivering the alert of
+    /// it when it's detected
+    pub(crate) async fn crash_looping(
+        &self,
+        http: &Client,
+        config: &Config,
+        record: &ErrorRecord,
+    ) -> bool {
+        let Some(policy) = config.crash_loop else {
+            return false;
+        };
+        let in_window = self.uptime() < policy.window;
+
+        match self
+            .crash_loops
+            .record(record.fingerprint(), policy, in_window)
+        {
+            Detection::None => false,
+            Detection::Looping => true,
+            Detection::Detected => {
+                self.diagnose(&DiagnosticEvent::CrashLoop(record.fingerprint()));
+                let report = ErrorReport::new(format_args!(
+                    "Crash loop: the error happened {} times in the first {}s since startup, \
+                     it's only appended to the file for the next {}s\n\n{}",
+                    policy.occurrences,
+                    policy.window.as_secs(),
+                    policy.window.as_secs(),
+                    record.error
+                ))
+                .severity(Severity::Critical)
+                .kind("Crash loop")
+                .field("Fingerprint", record.fingerprint())
+                .without_location();
+
+                let mut alert = report.into_record(
+                    ErrorId::new(),
+                    config,
+                    http.token(),
+                    Some(self.uptime_field()),
+                );
+                alert
+                    .mentions
+                    .extend(policy.role_id.map(|id| format!("<@&{id}>")));
+                for field in &record.fields {
+                    if !alert.fields.iter().any(|(name, _)| *name == field.0) {
+                        alert.fields.push(field.clone());
+                    }
+                }
+                self.history.push(alert.clone());
+                self.report(http, config, Message::Record(&alert), &[])
+                    .await;
+                true
+            }
+        }
+    }
+
+    /// Deliver a single critical alert mentioning the role instead of the
+    /// error once the same error, by its fingerprint, happens `occurrences`
+    /// times in the first `window` since the handler was made
+    ///
+    /// The errors of the crash loop are only printed to [`std::io::stderr`]
+    /// and appended to the file for the next `window`, so that they aren't
+    /// rate limited into silence, combine it with [`Self::restart_counter`] to
+    /// also see loops of the whole application
+    ///
+    /// The window follows Tokio's clock, so tests can pause and advance it
+    pub fn crash_loop_detection(
+        &mut self,
+        occurrences: usize,
+        window: Duration,
+        role_id: Option<Id<RoleMarker>>,
+    ) -> &mut Self {
+        self.config_mut().crash_loop = Some(CrashLoopPolicy {
+            occurrences,
+            window,
+            role_id,
+        });
+        self
+    }
+
+    /// Change the crash loop detection settings at runtime, `None` to never
+    /// detect crash loops
+    ///
+    /// Same as [`Self::crash_loop_detection`] but through a shared reference
+    pub fn set_crash_loop_detection(
+        &self,
+        settings: Option<(usize, Duration, Option<Id<RoleMarker>>)>,
+    ) {
+        self.update_config(|config| {
+            config.crash_loop = settings.map(|(occurrences, window, role_id)| CrashLoopPolicy {
+                occurrences,
+                window,
+                role_id,
+            });
+        });
+    }
+}
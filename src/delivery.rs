@@ -0,0 +1,364 @@
+//! How error messages are delivered to the sinks
+
+use core::fmt::Display;
+#[cfg(any(
+    feature = "discord",
+    feature = "nats",
+    feature = "cloudwatch",
+    feature = "http-sink",
+    all(feature = "tracing", feature = "file")
+))]
+use core::time::Duration;
+#[cfg(any(
+    feature = "discord",
+    feature = "nats",
+    feature = "cloudwatch",
+    feature = "http-sink"
+))]
+use core::{error::Error, future::Future};
+#[cfg(feature = "discord")]
+use std::borrow::Cow;
+
+#[cfg(any(
+    feature = "discord",
+    feature = "nats",
+    feature = "cloudwatch",
+    feature = "http-sink"
+))]
+use tokio::time;
+#[cfg(any(
+    feature = "discord",
+    feature = "nats",
+    feature = "cloudwatch",
+    feature = "http-sink",
+    all(feature = "tracing", feature = "file")
+))]
+use tokio::time::Instant;
+#[cfg(all(
+    feature = "tracing",
+    any(feature = "discord", feature = "nats", feature = "http-sink")
+))]
+use tracing::Instrument as _;
+#[cfg(all(
+    feature = "tracing",
+    any(
+        feature = "discord",
+        feature = "file",
+        feature = "nats",
+        feature = "http-sink"
+    )
+))]
+use tracing::{field, Span};
+
+#[cfg(any(
+    feature = "discord",
+    feature = "nats",
+    feature = "cloudwatch",
+    feature = "http-sink"
+))]
+use crate::RetryPolicy;
+use crate::{circuit_breaker::Transition, config::Config};
+#[cfg(feature = "discord")]
+use crate::{ErrorHandler, Sink};
+
+/// How the sinks are used when handling an error
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+#[non_exhaustive]
+pub enum DeliveryMode {
+    /// Deliver to every sink at the same time
+    ///
+    /// The failures of the sinks are only appended to the output of
+    /// [`std::io::stderr`]
+    #[default]
+    Concurrent,
+    /// Deliver to the sinks one after another, in the order of channel,
+    /// webhook and file
+    ///
+    /// The failures of the previous sinks are appended to the error message
+    /// the next sinks receive
+    Sequential,
+    /// Deliver to the first sink that works, in the order of webhook, channel
+    /// and file
+    ///
+    /// Sinks that aren't set or are disabled by the circuit breaker are
+    /// skipped, the failures of the previous sinks are appended to the error
+    /// message the next sinks receive
+    Fallback,
+}
+
+/// How a Discord sink retries failed deliveries, set with
+/// [`ErrorHandler::delivery_semantics`]
+///
+/// The error's ID is the idempotency key of every delivery, it's in the
+/// content, the nonce of channel messages and [`crate::SentMessage`], so that
+/// duplicates from retries can be recognized
+#[cfg(feature = "discord")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DeliverySemantics {
+    /// Deliver once, giving up if it fails
+    #[default]
+    BestEffort,
+    /// Retry failed deliveries until they succeed or the duration passes,
+    /// waiting a second before the first retry and doubling the wait after
+    /// each one
+    ///
+    /// Errors that won't succeed on a retry, such as missing permissions or
+    /// invalid requests, aren't retried
+    RetryUntil(Duration),
+    /// Keep the error in [`ErrorHandler::outbox`] until it's delivered to
+    /// the sink, so that it's delivered by [`ErrorHandler::deliver_outbox`]
+    /// after a restart
+    ///
+    /// Without the outbox, it's the same as [`Self::BestEffort`], with
+    /// [`DeliveryMode::Fallback`], delivering to any sink is enough
+    #[cfg(feature = "json")]
+    AtLeastOnce,
+}
+
+/// The result of trying to deliver an error message to a sink
+#[derive(Debug, Default)]
+pub(crate) struct Delivery {
+    /// Whether the error message was delivered
+    #[cfg_attr(not(feature = "discord"), allow(dead_code))]
+    pub(crate) delivered: bool,
+    /// The text to append to the error message, such as why the delivery
+    /// failed
+    pub(crate) note: Option<String>,
+    /// The circuit breaker transition the delivery caused
+    pub(crate) transition: Option<Transition>,
+}
+
+impl Delivery {
+    /// Makes a delivery from the result of using the sink, describing the
+    /// error with the given action
+    pub(crate) fn new<T, E: Display>(
+        result: &Result<T, E>,
+        action: &str,
+        transition: Option<Transition>,
+    ) -> Self {
+        Self {
+            delivered: result.is_ok(),
+            note: result
+                .as_ref()
+                .err()
+                .map(|err| format!("\n\nFailed to {action}: {err}")),
+            transition,
+        }
+    }
+
+    /// Redacts the secrets in the note, see [`Config::redact`]
+    pub(crate) fn redacted(mut self, config: &Config, bot_token: Option<&str>) -> Self {
+        self.note = self.note.map(|note| config.redact(&note, bot_token));
+        self
+    }
+
+    /// Combines the delivery with another one to the same kind of sink
+    ///
+    /// It's delivered if either of them is, the notes are joined
+    #[cfg(feature = "file")]
+    pub(crate) fn and(self, other: Self) -> Self {
+        let note = match (self.note, other.note) {
+            (Some(mut note), Some(other_note)) => {
+                note.push_str(&other_note);
+                Some(note)
+            }
+            (note, other_note) => note.or(other_note),
+        };
+
+        Self {
+            delivered: self.delivered || other.delivered,
+            note,
+            transition: self.transition.or(other.transition),
+        }
+    }
+
+    /// Makes a delivery that only describes the given action instead of doing
+    /// it
+    ///
+    /// It isn't delivered, so that the next sinks of
+    /// [`crate::DeliveryMode::Fallback`] and the file are still used
+    #[cfg(feature = "discord")]
+    pub(crate) fn dry_run(action: &str) -> Self {
+        Self {
+            delivered: false,
+            note: Some(format!("\n\nDry run, would {action}")),
+            transition: None,
+        }
+    }
+}
+
+/// Sends with the function, retrying the errors it returns `true` for
+/// according to the policy, once if it's `None`
+#[cfg(any(
+    feature = "discord",
+    feature = "nats",
+    feature = "cloudwatch",
+    feature = "http-sink"
+))]
+pub(crate) async fn retrying<T, E: Error + 'static, Fut: Future<Output = Result<T, E>>>(
+    policy: Option<&RetryPolicy>,
+    mut send: impl FnMut() -> Fut,
+    retryable: impl Fn(&E) -> bool,
+) -> Result<T, E> {
+    let Some(retry_policy) = policy else {
+        return send().await;
+    };
+    let started_at = Instant::now();
+    let mut delay = retry_policy.first_delay();
+    let mut attempts = 1_u32;
+
+    loop {
+        let result = send().await;
+        let remaining = retry_policy.deadline().map_or(Duration::MAX, |deadline| {
+            deadline.saturating_sub(started_at.elapsed())
+        });
+        let retry = result
+            .as_ref()
+            .is_err_and(|err| retryable(err) && retry_policy.retries(err));
+        if !retry || remaining.is_zero() || !retry_policy.allows(attempts) {
+            return result;
+        }
+
+        time::sleep(retry_policy.jittered(delay).min(remaining)).await;
+        delay = retry_policy.next_delay(delay);
+        attempts = attempts.saturating_add(1);
+    }
+}
+
+/// Awaits sending to the sink, in a `deliver` span with the `tracing` feature
+/// that records whether it succeeded and how long it took
+#[cfg(any(feature = "discord", feature = "nats", feature = "http-sink"))]
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) async fn traced<T, E>(
+    sink: impl Display,
+    send: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    #[cfg(feature = "tracing")]
+    {
+        let span = deliver_span(&sink);
+        let started_at = Instant::now();
+        let result = send.instrument(span.clone()).await;
+        finish_span(&span, started_at.elapsed(), result.is_ok());
+        result
+    }
+    #[cfg(not(feature = "tracing"))]
+    send.await
+}
+
+/// Sends to the sink, in a `deliver` span with the `tracing` feature that
+/// records whether it succeeded and how long it took
+#[cfg(feature = "file")]
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn traced_sync<T, E>(
+    sink: impl Display,
+    send: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    #[cfg(feature = "tracing")]
+    {
+        let span = deliver_span(&sink);
+        let started_at = Instant::now();
+        let result = span.in_scope(send);
+        finish_span(&span, started_at.elapsed(), result.is_ok());
+        result
+    }
+    #[cfg(not(feature = "tracing"))]
+    send()
+}
+
+/// Returns the span of delivering to the sink
+#[cfg(all(
+    feature = "tracing",
+    any(
+        feature = "discord",
+        feature = "file",
+        feature = "nats",
+        feature = "http-sink"
+    )
+))]
+fn deliver_span(sink: &impl Display) -> Span {
+    tracing::info_span!(
+        target: env!("CARGO_CRATE_NAME"),
+        "deliver",
+        sink = %sink,
+        ok = field::Empty,
+        elapsed_ms = field::Empty,
+    )
+}
+
+/// Records the result of the delivery in its span and emits it as an event
+#[cfg(all(
+    feature = "tracing",
+    any(
+        feature = "discord",
+        feature = "file",
+        feature = "nats",
+        feature = "http-sink"
+    )
+))]
+fn finish_span(span: &Span, elapsed: Duration, ok: bool) {
+    let elapsed_ms = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX);
+    span.record("ok", ok);
+    span.record("elapsed_ms", elapsed_ms);
+    tracing::debug!(
+        target: env!("CARGO_CRATE_NAME"),
+        parent: span,
+        "delivered in {elapsed:?}"
+    );
+}
+
+#[cfg(feature = "discord")]
+impl Config {
+    /// Returns how the sink retries failed deliveries
+    pub(crate) fn delivery_semantics(&self, sink: Sink) -> DeliverySemantics {
+        self.delivery_semantics
+            .iter()
+            .find_map(|(set_sink, semantics)| (*set_sink == sink).then_some(*semantics))
+            .unwrap_or_default()
+    }
+
+    /// Returns how the Discord sink retries failed deliveries, the policy of
+    /// [`DeliverySemantics::RetryUntil`] if it's set for the sink
+    pub(crate) fn sink_retry_policy(&self, sink: Sink) -> Option<Cow<'_, RetryPolicy>> {
+        if let DeliverySemantics::RetryUntil(deadline) = self.delivery_semantics(sink) {
+            return Some(Cow::Owned(RetryPolicy::until(deadline)));
+        }
+        self.retry_policy.as_ref().map(Cow::Borrowed)
+    }
+}
+
+#[cfg(feature = "discord")]
+impl ErrorHandler {
+    /// Set how the Discord sink retries failed deliveries, defaults to
+    /// [`DeliverySemantics::BestEffort`]
+    ///
+    /// [`Sink::File`] is always delivered to once
+    pub fn delivery_semantics(&mut self, sink: Sink, semantics: DeliverySemantics) -> &mut Self {
+        let config = self.config_mut();
+        config
+            .delivery_semantics
+            .retain(|(set_sink, _)| *set_sink != sink);
+        config.delivery_semantics.push((sink, semantics));
+        self
+    }
+
+    /// Change how the Discord sink retries failed deliveries at runtime,
+    /// `None` for [`DeliverySemantics::BestEffort`]
+    ///
+    /// Same as [`Self::delivery_semantics`] but through a shared reference
+    pub fn set_delivery_semantics(&self, sink: Sink, semantics: Option<DeliverySemantics>) {
+        self.update_config(|config| {
+            config
+                .delivery_semantics
+                .retain(|(set_sink, _)| *set_sink != sink);
+            config
+                .delivery_semantics
+                .extend(semantics.map(|set_semantics| (sink, set_semantics)));
+        });
+    }
+}
@@ -0,0 +1,230 @@
+//! Reporting the handler's own operational events
+
+use core::{fmt, time::Duration};
+use std::sync::Arc;
+
+#[cfg(all(feature = "discord", feature = "json"))]
+use crate::ErrorId;
+#[cfg(feature = "discord")]
+use crate::Fingerprint;
+use crate::{circuit_breaker::Transition, ErrorHandler, Sink};
+
+/// The `tracing` target the diagnostic events are emitted with
+pub const DIAGNOSTICS_TARGET: &str = concat!(env!("CARGO_CRATE_NAME"), "::diagnostics");
+
+/// An operational event of the handler itself, set a hook for them with
+/// [`ErrorHandler::diagnostics_hook`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DiagnosticEvent {
+    /// The circuit breaker disabled the sink after it failed too many times
+    /// in a row
+    SinkDisabled {
+        /// The sink that was disabled
+        sink: Sink,
+        /// The number of consecutive failures that caused it
+        failures: u32,
+        /// The interval the sink will be retried at
+        probe_interval: Duration,
+    },
+    /// The sink was disabled because the bot is missing the access or
+    /// permissions to use it
+    #[cfg(feature = "discord")]
+    SinkMissingPermissions(Sink),
+    /// A retry of the disabled sink succeeded
+    SinkEnabled(Sink),
+    /// Discord kept rate limiting the sink when it was retried, or the rate
+    /// limit was too long to wait for, so the error wasn't delivered to it
+    #[cfg(feature = "discord")]
+    RetryExhausted {
+        /// The sink that was rate limited
+        sink: Sink,
+        /// How long the sink is rate limited for
+        retry_after: Duration,
+    },
+    /// A report wasn't queued because the queue of [`crate::Reporter`] is
+    /// full
+    #[cfg(feature = "discord")]
+    QueueFull,
+    /// The error with the ID was kept in the outbox because the Discord
+    /// sinks are rate limited, see [`ErrorHandler::defer_rate_limited`]
+    #[cfg(all(feature = "discord", feature = "json"))]
+    Deferred(ErrorId),
+    /// Delivering to Discord kept failing so it's degraded, see
+    /// [`ErrorHandler::watchdog`]
+    #[cfg(feature = "discord")]
+    Degraded {
+        /// The number of consecutive failures that caused it
+        failures: u32,
+        /// How long errors aren't delivered to Discord
+        cooldown: Duration,
+    },
+    /// Delivering to Discord worked again after it was degraded
+    #[cfg(feature = "discord")]
+    Recovered,
+    /// The error rate went above the threshold of [`ErrorHandler::escalation`]
+    #[cfg(feature = "discord")]
+    Escalated,
+    /// The error rate dropped below the threshold of
+    /// [`ErrorHandler::escalation`]
+    #[cfg(feature = "discord")]
+    EscalationEnded,
+    /// The error with the fingerprint was detected as a crash loop, see
+    /// [`ErrorHandler::crash_loop_detection`]
+    #[cfg(feature = "discord")]
+    CrashLoop(Fingerprint),
+    /// The configuration was reloaded from the file, see
+    /// [`ErrorHandler::watch_config`]
+    #[cfg(all(feature = "discord", any(feature = "toml", feature = "json")))]
+    ConfigReloaded,
+    /// Reloading the configuration from the file failed
+    #[cfg(all(feature = "discord", any(feature = "toml", feature = "json")))]
+    ConfigReloadFailed(String),
+}
+
+impl DiagnosticEvent {
+    /// Returns whether the event is about something not working, emitted at
+    /// the warning level instead of the info level
+    #[must_use]
+    pub const fn is_problem(&self) -> bool {
+        match self {
+            Self::SinkDisabled { .. } => true,
+            #[cfg(feature = "discord")]
+            Self::SinkMissingPermissions(_)
+            | Self::RetryExhausted { .. }
+            | Self::QueueFull
+            | Self::Degraded { .. }
+            | Self::Escalated
+            | Self::CrashLoop(_) => true,
+            #[cfg(all(feature = "discord", any(feature = "toml", feature = "json")))]
+            Self::ConfigReloadFailed(_) => true,
+            Self::SinkEnabled(_) => false,
+            #[cfg(all(feature = "discord", feature = "json"))]
+            Self::Deferred(_) => false,
+            #[cfg(feature = "discord")]
+            Self::Recovered | Self::EscalationEnded => false,
+            #[cfg(all(feature = "discord", any(feature = "toml", feature = "json")))]
+            Self::ConfigReloaded => false,
+        }
+    }
+}
+
+impl fmt::Display for DiagnosticEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SinkDisabled {
+                sink,
+                failures,
+                probe_interval,
+            } => write!(
+                f,
+                "disabled the {sink} after {failures} consecutive failures, retrying every {}s",
+                probe_interval.as_secs()
+            ),
+            #[cfg(feature = "discord")]
+            Self::SinkMissingPermissions(sink) => write!(
+                f,
+                "disabled the {sink} because the bot is missing access or permissions to it"
+            ),
+            Self::SinkEnabled(sink) => write!(f, "enabled the {sink} again"),
+            #[cfg(feature = "discord")]
+            Self::RetryExhausted { sink, retry_after } => write!(
+                f,
+                "gave up delivering to the {sink} after a rate limit of {}s",
+                retry_after.as_secs()
+            ),
+            #[cfg(feature = "discord")]
+            Self::QueueFull => f.write_str("dropped a report because the error queue is full"),
+            #[cfg(all(feature = "discord", feature = "json"))]
+            Self::Deferred(id) => write!(
+                f,
+                "kept the error {id} in the outbox because Discord is rate limiting"
+            ),
+            #[cfg(feature = "discord")]
+            Self::Degraded { failures, cooldown } => write!(
+                f,
+                "degraded delivering to Discord for {}s after {failures} consecutive failures",
+                cooldown.as_secs()
+            ),
+            #[cfg(feature = "discord")]
+            Self::Recovered => f.write_str("delivering to Discord recovered"),
+            #[cfg(feature = "discord")]
+            Self::Escalated => f.write_str("the error rate went above the escalation threshold"),
+            #[cfg(feature = "discord")]
+            Self::EscalationEnded => {
+                f.write_str("the error rate dropped below the escalation threshold")
+            }
+            #[cfg(feature = "discord")]
+            Self::CrashLoop(fingerprint) => write!(f, "detected a crash loop of {fingerprint}"),
+            #[cfg(all(feature = "discord", any(feature = "toml", feature = "json")))]
+            Self::ConfigReloaded => f.write_str("reloaded the configuration"),
+            #[cfg(all(feature = "discord", any(feature = "toml", feature = "json")))]
+            Self::ConfigReloadFailed(err) => write!(f, "failed to reload the configuration: {err}"),
+        }
+    }
+}
+
+impl From<Transition> for DiagnosticEvent {
+    fn from(transition: Transition) -> Self {
+        match transition {
+            Transition::Disabled {
+                sink,
+                failures,
+                probe_interval,
+            } => Self::SinkDisabled {
+                sink,
+                failures,
+                probe_interval,
+            },
+            #[cfg(feature = "discord")]
+            Transition::MissingPermissions(sink) => Self::SinkMissingPermissions(sink),
+            Transition::Enabled(sink) => Self::SinkEnabled(sink),
+        }
+    }
+}
+
+/// The function called with the diagnostic events
+#[derive(Clone)]
+pub(crate) struct DiagnosticsHook(pub(crate) Arc<dyn Fn(&DiagnosticEvent) + Send + Sync>);
+
+impl fmt::Debug for DiagnosticsHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DiagnosticsHook")
+    }
+}
+
+impl ErrorHandler {
+    /// Reports the operational event to the diagnostics hook, and as a
+    /// `tracing` event with the `tracing` feature
+    pub(crate) fn diagnose(&self, event: &DiagnosticEvent) {
+        #[cfg(feature = "tracing")]
+        if event.is_problem() {
+            tracing::warn!(target: DIAGNOSTICS_TARGET, "{event}");
+        } else {
+            tracing::info!(target: DIAGNOSTICS_TARGET, "{event}");
+        }
+
+        if let Some(hook) = &self.config().diagnostics_hook {
+            (hook.0)(event);
+        }
+    }
+
+    /// Set the function called with the operational events of the handler
+    /// itself, such as sinks being disabled, so that they can be monitored
+    ///
+    /// With the `tracing` feature, they're also emitted as `tracing` events
+    /// with the [`DIAGNOSTICS_TARGET`] target, which [`crate::ErrorLayer`]
+    /// ignores
+    pub fn diagnostics_hook(
+        &mut self,
+        hook: impl Fn(&DiagnosticEvent) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.config_mut().diagnostics_hook = Some(DiagnosticsHook(Arc::new(hook)));
+        self
+    }
+
+    /// Stop calling the diagnostics hook
+    pub fn remove_diagnostics_hook(&self) {
+        self.update_config(|config| config.diagnostics_hook = None);
+    }
+}
@@ -0,0 +1,218 @@
+//! Making a handler from environment variables
+
+use core::time::Duration;
+use std::env::{self, VarError};
+
+#[cfg(feature = "discord")]
+use crate::config::parse_webhook_url;
+use crate::{ConfigError, DeliveryMode, ErrorHandler, Severity};
+
+/// The environment variable to read the webhook URL from
+#[cfg(feature = "discord")]
+pub const WEBHOOK_URL_VAR: &str = "TWILIGHT_ERROR_WEBHOOK_URL";
+/// The environment variable to read the channel ID from
+#[cfg(feature = "discord")]
+pub const CHANNEL_ID_VAR: &str = "TWILIGHT_ERROR_CHANNEL_ID";
+/// The environment variable to read the owner's user ID from
+#[cfg(feature = "discord")]
+pub const OWNER_ID_VAR: &str = "TWILIGHT_ERROR_OWNER_ID";
+/// The environment variable to read the file path from
+#[cfg(feature = "file")]
+pub const FILE_VAR: &str = "TWILIGHT_ERROR_FILE";
+/// The environment variable to read the minimum severity from
+pub const MIN_SEVERITY_VAR: &str = "TWILIGHT_ERROR_MIN_SEVERITY";
+/// The environment variable to read the delivery mode from
+pub const DELIVERY_MODE_VAR: &str = "TWILIGHT_ERROR_DELIVERY_MODE";
+/// The environment variable to read whether to do a dry run from
+pub const DRY_RUN_VAR: &str = "TWILIGHT_ERROR_DRY_RUN";
+/// The environment variable to read whether to redact secrets from
+pub const REDACT_SECRETS_VAR: &str = "TWILIGHT_ERROR_REDACT_SECRETS";
+/// The environment variable to read the circuit breaker threshold from
+pub const CIRCUIT_BREAKER_THRESHOLD_VAR: &str = "TWILIGHT_ERROR_CIRCUIT_BREAKER_THRESHOLD";
+/// The environment variable to read the circuit breaker probe interval in
+/// seconds from
+pub const CIRCUIT_BREAKER_PROBE_INTERVAL_VAR: &str =
+    "TWILIGHT_ERROR_CIRCUIT_BREAKER_PROBE_INTERVAL_SECS";
+
+impl ErrorHandler {
+    /// Make a handler from environment variables
+    ///
+    /// Unset or empty variables are ignored, so this makes a handler that only
+    /// prints to [`std::io::stderr`] if none of them are set
+    ///
+    /// - [`WEBHOOK_URL_VAR`]: see [`Self::webhook`], in the form of
+    ///   `https://discord.com/api/webhooks/{id}/{token}`, only with the
+    ///   `discord` feature
+    /// - [`CHANNEL_ID_VAR`]: see [`Self::channel`], only with the `discord`
+    ///   feature
+    /// - [`OWNER_ID_VAR`]: see [`Self::owner`], only with the `discord`
+    ///   feature
+    /// - [`FILE_VAR`]: see [`Self::file`], only with the `file` feature
+    /// - [`MIN_SEVERITY_VAR`]: see [`Self::min_severity`], one of `warning`,
+    ///   `error` or `critical`
+    /// - [`DELIVERY_MODE_VAR`]: see [`Self::delivery_mode`], one of
+    ///   `concurrent`, `sequential` or `fallback`
+    /// - [`DRY_RUN_VAR`]: see [`Self::dry_run`], `true` or `false`
+    /// - [`REDACT_SECRETS_VAR`]: see [`Self::redact_secrets`], `true` or
+    ///   `false`
+    /// - [`CIRCUIT_BREAKER_THRESHOLD_VAR`] and
+    ///   [`CIRCUIT_BREAKER_PROBE_INTERVAL_VAR`]: see [`Self::circuit_breaker`],
+    ///   the probe interval defaults to 5 minutes
+    ///
+    /// # Errors
+    /// Returns [`ConfigError::InvalidEnvVar`] if a variable isn't unicode or
+    /// isn't valid for its option
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let mut handler = Self::new();
+
+        #[cfg(feature = "discord")]
+        if let Some(url) = var(WEBHOOK_URL_VAR)? {
+            let (webhook_id, token) =
+                parse_webhook_url(&url).ok_or(ConfigError::InvalidWebhookUrl(url))?;
+            handler.webhook(webhook_id, token);
+        }
+        #[cfg(feature = "discord")]
+        if let Some(channel_id) = parse_var(CHANNEL_ID_VAR, |value| value.parse().ok())? {
+            handler.channel(channel_id);
+        }
+        #[cfg(feature = "discord")]
+        if let Some(owner_id) = parse_var(OWNER_ID_VAR, |value| value.parse().ok())? {
+            handler.owner(owner_id);
+        }
+        #[cfg(feature = "file")]
+        if let Some(path) = var(FILE_VAR)? {
+            handler.file(path);
+        }
+        if let Some(severity) = parse_var(MIN_SEVERITY_VAR, Severity::from_name)? {
+            handler.min_severity(severity);
+        }
+        if let Some(mode) = parse_var(DELIVERY_MODE_VAR, parse_delivery_mode)? {
+            handler.delivery_mode(mode);
+        }
+        if let Some(dry_run) = parse_var(DRY_RUN_VAR, |value| value.parse().ok())? {
+            handler.dry_run(dry_run);
+        }
+        if let Some(redact) = parse_var(REDACT_SECRETS_VAR, |value| value.parse().ok())? {
+            handler.redact_secrets(redact);
+        }
+        if let Some(threshold) =
+            parse_var(CIRCUIT_BREAKER_THRESHOLD_VAR, |value| value.parse().ok())?
+        {
+            let probe_interval_secs = parse_var(CIRCUIT_BREAKER_PROBE_INTERVAL_VAR, |value| {
+                value.parse().ok()
+            })?
+            .unwrap_or(300);
+            handler.circuit_breaker(threshold, Duration::from_secs(probe_interval_secs));
+        }
+
+        Ok(handler)
+    }
+}
+
+/// Returns the value of the environment variable, `None` if it's unset or empty
+fn var(name: &'static str) -> Result<Option<String>, ConfigError> {
+    match env::var(name) {
+        Ok(value) if value.is_empty() => Ok(None),
+        Ok(value) => Ok(Some(value)),
+        Err(VarError::NotPresent) => Ok(None),
+        Err(VarError::NotUnicode(value)) => Err(ConfigError::InvalidEnvVar {
+            name,
+            value: value.to_string_lossy().into_owned(),
+        }),
+    }
+}
+
+/// Returns the value of the environment variable parsed with the given
+/// function, `None` if it's unset or empty
+fn parse_var<T>(
+    name: &'static str,
+    parse: impl FnOnce(&str) -> Option<T>,
+) -> Result<Option<T>, ConfigError> {
+    var(name)?
+        .map(|value| parse(&value).ok_or(ConfigError::InvalidEnvVar { name, value }))
+        .transpose()
+}
+
+/// Returns the delivery mode with the given name
+fn parse_delivery_mode(name: &str) -> Option<DeliveryMode> {
+    match name.to_ascii_lowercase().as_str() {
+        "concurrent" => Some(DeliveryMode::Concurrent),
+        "sequential" => Some(DeliveryMode::Sequential),
+        "fallback" => Some(DeliveryMode::Fallback),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "discord")]
+mod tests {
+    use twilight_model::id::Id;
+
+    use super::*;
+
+    /// The variables read by [`ErrorHandler::from_env`], which are only set
+    /// in [`variables_are_applied`] since the tests share them
+    const VARS: [&str; 6] = [
+        WEBHOOK_URL_VAR,
+        CHANNEL_ID_VAR,
+        MIN_SEVERITY_VAR,
+        DELIVERY_MODE_VAR,
+        DRY_RUN_VAR,
+        CIRCUIT_BREAKER_THRESHOLD_VAR,
+    ];
+
+    #[test]
+    fn variables_are_applied() {
+        env::set_var(
+            WEBHOOK_URL_VAR,
+            "https://discord.com/api/webhooks/456/token",
+        );
+        env::set_var(CHANNEL_ID_VAR, "123");
+        env::set_var(MIN_SEVERITY_VAR, "critical");
+        env::set_var(DELIVERY_MODE_VAR, "Sequential");
+        env::set_var(DRY_RUN_VAR, "true");
+        env::set_var(CIRCUIT_BREAKER_THRESHOLD_VAR, "");
+
+        let config = ErrorHandler::from_env().unwrap().config();
+        assert_eq!(config.webhook, Some((Id::new(456), "token".to_owned())));
+        assert_eq!(config.channel, Some(Id::new(123)));
+        assert_eq!(config.min_severity, Severity::Critical);
+        assert_eq!(config.delivery_mode, DeliveryMode::Sequential);
+        assert!(config.dry_run);
+
+        env::set_var(DRY_RUN_VAR, "yes");
+        let invalid_dry_run = ErrorHandler::from_env();
+        assert!(matches!(
+            invalid_dry_run,
+            Err(ConfigError::InvalidEnvVar { name: DRY_RUN_VAR, value }) if value == "yes"
+        ));
+
+        env::set_var(DRY_RUN_VAR, "false");
+        env::set_var(WEBHOOK_URL_VAR, "https://example.com");
+        let invalid_webhook = ErrorHandler::from_env();
+        assert!(matches!(
+            invalid_webhook,
+            Err(ConfigError::InvalidWebhookUrl(_))
+        ));
+
+        for name in VARS {
+            env::remove_var(name);
+        }
+        let unset = ErrorHandler::from_env().unwrap().config();
+        assert_eq!(unset.channel, None);
+        assert!(!unset.dry_run);
+    }
+
+    #[test]
+    fn delivery_modes_are_parsed_case_insensitively() {
+        assert_eq!(
+            parse_delivery_mode("FALLBACK"),
+            Some(DeliveryMode::Fallback)
+        );
+        assert_eq!(
+            parse_delivery_mode("concurrent"),
+            Some(DeliveryMode::Concurrent)
+        );
+        assert_eq!(parse_delivery_mode("parallel"), None);
+    }
+}
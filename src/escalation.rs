@@ -0,0 +1,212 @@
+//! Escalating errors that happen too often at once
+
+use core::{sync::atomic::Ordering, time::Duration};
+use std::collections::VecDeque;
+
+use tokio::time::Instant;
+use twilight_http::Client;
+use twilight_model::id::{
+    marker::{RoleMarker, WebhookMarker},
+    Id,
+};
+
+use crate::{
+    config::Config, formatter::Message, sharded::ShardedMap, webhook::execute_additional,
+    DiagnosticEvent, ErrorHandler, ErrorId, ErrorRecord, ErrorReport, Fingerprint, Severity, Sink,
+};
+
+/// Where errors that keep happening are additionally delivered, see
+/// [`ErrorHandler::escalate_repeated`]
+#[derive(Clone, Debug)]
+pub(crate) struct RepeatedPolicy {
+    /// The number of times an error needs to happen to be escalated
+    pub(crate) occurrences: usize,
+    /// The duration the occurrences are counted in
+    pub(crate) window: Duration,
+    /// The webhook to escalate to and its token
+    pub(crate) webhook: (Id<WebhookMarker>, String),
+    /// The role to mention, such as the on-call role
+    pub(crate) role_id: Option<Id<RoleMarker>>,
+}
+
+/// The occurrences, window, escalation webhook and its token and the role of
+/// [`ErrorHandler::set_escalate_repeated`]
+type RepeatedSettings = (
+    usize,
+    Duration,
+    Id<WebhookMarker>,
+    String,
+    Option<Id<RoleMarker>>,
+);
+
+/// When the errors with each fingerprint recently happened
+#[derive(Debug, Default)]
+pub(crate) struct Occurrences {
+    /// When the errors with each fingerprint happened, oldest first
+    recent: ShardedMap<Fingerprint, VecDeque<Instant>>,
+}
+
+impl Occurrences {
+    /// Records that the error with the fingerprint happened, returning the
+    /// number of times it happened in the window
+    fn record(&self, fingerprint: Fingerprint, window: Duration) -> usize {
+        let now = Instant::now();
+        let mut recent = self.recent.shard(&fingerprint);
+
+        recent.retain(|_, times| {
+            while times
+                .front()
+                .is_some_and(|time| now.duration_since(*time) >= window)
+            {
+                times.pop_front();
+            }
+            !times.is_empty()
+        });
+
+        let times = recent.entry(fingerprint).or_default();
+        times.push_back(now);
+        times.len()
+    }
+}
+
+impl ErrorHandler {
+    /// Returns whether the error rate is above the escalation threshold,
+    /// delivering the escalation when it's crossed and the recovery notice
+    /// when it drops below it again
+    pub(crate) async fn escalated(&self, http: &Client, config: &Config) -> bool {
+        let above_threshold = config
+            .escalation
+            .is_some_and(|(threshold, _)| self.stats.get().last_minute > threshold);
+        let was_escalated = self.escalated.swap(above_threshold, Ordering::Relaxed);
+
+        match (was_escalated, above_threshold) {
+            (false, true) => {
+                self.diagnose(&DiagnosticEvent::Escalated);
+                let (threshold, role_id) = config.escalation.unwrap_or_default();
+                let mention = role_id.map(|id| format!("<@&{id}> ")).unwrap_or_default();
+                let report = ErrorReport::new(format_args!(
+                    "{mention}More than {threshold} errors were handled in the last minute, \
+                     they're only appended to the file until the rate drops"
+                ))
+                .severity(Severity::Critical)
+                .kind("Error rate escalation")
+                .without_location();
+                let record = report.into_record(
+                    ErrorId::new(),
+                    config,
+                    http.token(),
+                    Some(self.uptime_field()),
+                );
+                self.history.push(record.clone());
+                self.report(http, config, Message::Record(&record), &[])
+                    .await;
+            }
+            (true, false) => {
+                self.diagnose(&DiagnosticEvent::EscalationEnded);
+                let notice = "\n\nThe error rate dropped, errors are delivered to every sink \
+                              again";
+                self.report(http, config, Message::Notice(notice), &[])
+                    .await;
+            }
+            _ => {}
+        }
+
+        above_threshold
+    }
+
+    /// Additionally delivers the error to the escalation webhook if its
+    /// fingerprint happened more than the policy's number of times, returning
+    /// the note of why that failed
+    pub(crate) async fn escalate_if_repeated(
+        &self,
+        http: &Client,
+        config: &Config,
+        record: &ErrorRecord,
+    ) -> Option<String> {
+        let policy = config.repeated_policy.as_ref()?;
+        let occurrences = self.occurrences.record(record.fingerprint(), policy.window);
+        if occurrences <= policy.occurrences {
+            return None;
+        }
+        let message = Message::Record(record);
+        let output = message.output(config, Some(Sink::Webhook));
+        let mention = policy
+            .role_id
+            .map(|id| format!("<@&{id}> "))
+            .unwrap_or_default();
+        let full_content = format!(
+            "{mention}Happened {occurrences} times in the last {}s{}",
+            policy.window.as_secs(),
+            output.content
+        );
+
+        execute_additional(
+            http,
+            config,
+            &policy.webhook,
+            "escalation webhook",
+            message,
+            &output,
+            &full_content,
+        )
+        .await
+    }
+
+    /// Also deliver errors to the escalation webhook once the same error, by
+    /// its fingerprint, happens more than `occurrences` times in `window`,
+    /// mentioning the role
+    ///
+    /// Unlike [`Self::escalation`], the errors are still delivered to the
+    /// other sinks
+    pub fn escalate_repeated(
+        &mut self,
+        occurrences: usize,
+        window: Duration,
+        webhook_id: Id<WebhookMarker>,
+        token: impl Into<String>,
+        role_id: Option<Id<RoleMarker>>,
+    ) -> &mut Self {
+        self.config_mut().repeated_policy = Some(RepeatedPolicy {
+            occurrences,
+            window,
+            webhook: (webhook_id, token.into()),
+            role_id,
+        });
+        self
+    }
+
+    /// Change the settings of escalating errors that keep happening at
+    /// runtime, `None` to stop delivering them to the escalation webhook
+    ///
+    /// Same as [`Self::escalate_repeated`] but through a shared reference
+    pub fn set_escalate_repeated(&self, settings: Option<RepeatedSettings>) {
+        self.update_config(|config| {
+            config.repeated_policy = settings.map(
+                |(occurrences, window, webhook_id, token, role_id)| RepeatedPolicy {
+                    occurrences,
+                    window,
+                    webhook: (webhook_id, token),
+                    role_id,
+                },
+            );
+        });
+    }
+
+    /// Deliver a single critical escalation instead of the errors while more
+    /// than `threshold` of them are handled in a minute, mentioning the role
+    ///
+    /// The errors are still appended to the file and printed to
+    /// [`std::io::stderr`] meanwhile, a notice is delivered with the first
+    /// error after the rate drops
+    pub fn escalation(&mut self, threshold: u64, role_id: Option<Id<RoleMarker>>) -> &mut Self {
+        self.config_mut().escalation = Some((threshold, role_id));
+        self
+    }
+
+    /// Change the escalation settings at runtime, `None` to never escalate
+    ///
+    /// Same as [`Self::escalation`] but through a shared reference
+    pub fn set_escalation(&self, settings: Option<(u64, Option<Id<RoleMarker>>)>) {
+        self.update_config(|config| config.escalation = settings);
+    }
+}
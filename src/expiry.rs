@@ -0,0 +1,102 @@
+//! Deleting the messages of non-critical errors after a while
+
+use core::time::Duration;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use tokio::{
+    task::JoinHandle,
+    time::{self, Instant},
+};
+use twilight_http::Client;
+use twilight_model::id::{
+    marker::{ChannelMarker, MessageMarker},
+    Id,
+};
+
+use crate::ErrorHandler;
+
+/// How often the expired messages are deleted
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A message to delete once it expires
+type ExpiringMessage = (Instant, Id<ChannelMarker>, Id<MessageMarker>);
+
+/// The messages to delete once they expire
+#[derive(Debug, Default)]
+pub(crate) struct Expiry {
+    /// When each message expires, its channel and its ID
+    messages: Mutex<Vec<ExpiringMessage>>,
+}
+
+impl Expiry {
+    /// Schedules the message to be deleted after the duration
+    pub(crate) fn push(
+        &self,
+        after: Duration,
+        channel_id: Id<ChannelMarker>,
+        message_id: Id<MessageMarker>,
+    ) {
+        let Some(expires_at) = Instant::now().checked_add(after) else {
+            return;
+        };
+        self.messages
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push((expires_at, channel_id, message_id));
+    }
+
+    /// Removes and returns the messages that expired
+    fn take_expired(&self) -> Vec<ExpiringMessage> {
+        let now = Instant::now();
+        let mut messages = self.messages.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let (expired, pending) = messages
+            .drain(..)
+            .partition(|(expires_at, _, _)| *expires_at <= now);
+        *messages = pending;
+        expired
+    }
+}
+
+impl ErrorHandler {
+    /// Set the handler to delete the messages of errors that aren't
+    /// critical from the channel after the given duration
+    ///
+    /// The messages are deleted by the task spawned with
+    /// [`Self::spawn_expiry`], the error is still kept in the file and the
+    /// other sinks
+    pub fn delete_non_critical_after(&mut self, after: Duration) -> &mut Self {
+        self.config_mut().delete_non_critical_after = Some(after);
+        self
+    }
+
+    /// Change how long the messages of non-critical errors are kept at
+    /// runtime, `None` to keep them
+    ///
+    /// Same as [`Self::delete_non_critical_after`] but through a shared
+    /// reference, messages that were already created are still deleted
+    pub fn set_delete_non_critical_after(&self, after: Option<Duration>) {
+        self.update_config(|config| config.delete_non_critical_after = after);
+    }
+
+    /// Spawn a Tokio task that deletes the messages set to expire with
+    /// [`Self::delete_non_critical_after`]
+    ///
+    /// The expired messages are deleted every 30 seconds, failures are printed
+    /// according to [`Self::console_output`]
+    pub fn spawn_expiry(self: Arc<Self>, http: Arc<Client>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = time::interval(CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                for (_, channel_id, message_id) in self.expiry.take_expired() {
+                    if let Err(err) = http.delete_message(channel_id, message_id).await {
+                        self.config().print_notice(&format!(
+                            "Failed to delete the expired message {message_id}: {err}"
+                        ));
+                    }
+                }
+            }
+        })
+    }
+}
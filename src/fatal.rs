@@ -0,0 +1,80 @@
+//! Exiting after reporting an error the bot can't recover from
+
+use core::{fmt::Display, time::Duration};
+use std::process;
+
+use tokio::time;
+use twilight_http::Client;
+
+use crate::{ErrorHandler, ErrorReport, Severity};
+
+impl ErrorHandler {
+    /// Handle an error the bot can't recover from, such as at startup, then
+    /// exit the process with [`Self::fatal_exit_code`]
+    ///
+    /// The error is handled with [`Severity::Critical`], then the handler is
+    /// [flushed](Self::flush), waiting at most [`Self::fatal_deadline`] in
+    /// total for them, use [`Self::handle_report_fatal`] to include where it
+    /// happened
+    pub async fn handle_fatal(&self, http: &Client, error: impl Display + Send) -> ! {
+        self.handle_report_fatal(http, ErrorReport::new(error).without_location())
+            .await
+    }
+
+    /// Handle an error along with information about it, then exit the
+    /// process
+    ///
+    /// Same as [`Self::handle_fatal`] but with the information set in the
+    /// report, its severity is always [`Severity::Critical`]
+    #[allow(clippy::exit)]
+    pub async fn handle_report_fatal(&self, http: &Client, report: ErrorReport) -> ! {
+        let config = self.config();
+        let flushed = time::timeout(config.fatal_deadline, async {
+            self.handle_report(http, report.severity(Severity::Critical))
+                .await;
+            self.flush(config.fatal_deadline).await
+        })
+        .await
+        .unwrap_or(false);
+        if !flushed {
+            config.print_notice(&format!(
+                "Exiting before the fatal error and the ones being handled were delivered to \
+                 every sink after {}s",
+                config.fatal_deadline.as_secs()
+            ));
+        }
+
+        process::exit(config.fatal_exit_code)
+    }
+
+    /// Set the code the process exits with in [`Self::handle_fatal`],
+    /// defaults to 1
+    pub fn fatal_exit_code(&mut self, code: i32) -> &mut Self {
+        self.config_mut().fatal_exit_code = code;
+        self
+    }
+
+    /// Change the code the process exits with after a fatal error at runtime
+    ///
+    /// Same as [`Self::fatal_exit_code`] but through a shared reference
+    pub fn set_fatal_exit_code(&self, code: i32) {
+        self.update_config(|config| config.fatal_exit_code = code);
+    }
+
+    /// Set how long [`Self::handle_fatal`] waits for the error to be
+    /// delivered before exiting anyway, defaults to 10 seconds
+    ///
+    /// The hook of [`Self::install_panic_hook`] waits as long for the panic
+    /// to be delivered
+    pub fn fatal_deadline(&mut self, deadline: Duration) -> &mut Self {
+        self.config_mut().fatal_deadline = deadline;
+        self
+    }
+
+    /// Change how long to wait for fatal errors to be delivered at runtime
+    ///
+    /// Same as [`Self::fatal_deadline`] but through a shared reference
+    pub fn set_fatal_deadline(&self, deadline: Duration) {
+        self.update_config(|config| config.fatal_deadline = deadline);
+    }
+}
@@ -0,0 +1,58 @@
+//! Appending errors to additional files, each with its own severity and format
+
+use core::fmt;
+use std::{path::PathBuf, sync::Arc};
+
+use crate::{ErrorHandler, Formatter, Severity};
+
+/// A file errors are appended to along with [`ErrorHandler::file`]
+#[derive(Clone)]
+pub(crate) struct AdditionalFile {
+    /// The path of the file
+    pub(crate) path: PathBuf,
+    /// The severity errors need to be at least to be appended to the file
+    pub(crate) min_severity: Severity,
+    /// The formatter to render errors with, the file's formatter if it's
+    /// `None`
+    pub(crate) formatter: Option<Arc<dyn Formatter>>,
+}
+
+impl fmt::Debug for AdditionalFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AdditionalFile")
+            .field("path", &self.path)
+            .field("min_severity", &self.min_severity)
+            .field("formatter", &self.formatter.is_some())
+            .finish()
+    }
+}
+
+impl ErrorHandler {
+    /// Also append errors that are at least the given severity to the file,
+    /// rendered with the formatter or like [`Self::file`] if it's `None`
+    ///
+    /// The severity is checked after [`Self::min_severity`], so that
+    /// `critical.log` can only receive critical errors for example, the file
+    /// will be created if it doesn't exist
+    ///
+    /// The failures of additional files are appended to the error message but
+    /// don't count towards the circuit breaker of [`crate::Sink::File`]
+    pub fn additional_file(
+        &mut self,
+        path: impl Into<PathBuf>,
+        min_severity: Severity,
+        formatter: Option<Arc<dyn Formatter>>,
+    ) -> &mut Self {
+        self.config_mut().additional_files.push(AdditionalFile {
+            path: path.into(),
+            min_severity,
+            formatter,
+        });
+        self
+    }
+
+    /// Stop appending to the files set with [`Self::additional_file`]
+    pub fn remove_additional_files(&self) {
+        self.update_config(|config| config.additional_files.clear());
+    }
+}
@@ -0,0 +1,52 @@
+//! Recognizing errors that happened before
+
+use core::{
+    fmt,
+    hash::{Hash as _, Hasher as _},
+};
+use std::hash::DefaultHasher;
+
+use crate::ErrorRecord;
+
+/// Identifies the errors that are the same apart from the numbers in them,
+/// such as IDs and counts, returned from [`ErrorRecord::fingerprint`]
+///
+/// It's displayed as 16 hexadecimal characters, such as `3f2a9c0d1e4b5a67`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fingerprint(u64);
+
+impl Fingerprint {
+    /// Returns the fingerprint as a number
+    #[must_use]
+    pub const fn get(self) -> u64 {
+        self.0
+    }
+
+    /// Returns the fingerprint displayed as the given hexadecimal characters,
+    /// `None` if they aren't valid
+    #[cfg(feature = "discord")]
+    pub(crate) fn parse(hex: &str) -> Option<Self> {
+        u64::from_str_radix(hex, 16).ok().map(Self)
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl ErrorRecord {
+    /// Returns the fingerprint of the error, made from its kind and message
+    /// without the numbers in it
+    #[must_use]
+    pub fn fingerprint(&self) -> Fingerprint {
+        let mut hasher = DefaultHasher::new();
+        self.kind.hash(&mut hasher);
+        for part in self.error.split(|char: char| char.is_ascii_digit()) {
+            part.hash(&mut hasher);
+        }
+
+        Fingerprint(hasher.finish())
+    }
+}
@@ -0,0 +1,124 @@
+//! Waiting for the errors being handled before the process exits
+
+use core::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+#[cfg(all(feature = "unix", unix))]
+use std::{io, process, sync::Arc};
+
+#[cfg(all(feature = "unix", unix))]
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    task::JoinHandle,
+};
+use tokio::{sync::Notify, time};
+
+use crate::ErrorHandler;
+
+/// The number of errors being delivered or queued in a [`crate::Reporter`]
+#[derive(Debug, Default)]
+pub(crate) struct Pending {
+    /// The number of errors
+    count: AtomicUsize,
+    /// Notified when the number of errors drops to 0
+    idle: Notify,
+}
+
+impl Pending {
+    /// Counts an error until the guard is dropped
+    pub(crate) fn start(&self) -> PendingGuard<'_> {
+        self.add();
+        PendingGuard(self)
+    }
+
+    /// Counts an error until [`Self::done`] is called
+    pub(crate) fn add(&self) {
+        self.count.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Stops counting an error
+    pub(crate) fn done(&self) {
+        if self.count.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.idle.notify_waiters();
+        }
+    }
+
+    /// Waits until there are no errors
+    async fn idle(&self) {
+        loop {
+            let notified = self.idle.notified();
+            if self.count.load(Ordering::Acquire) == 0 {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Stops counting the error when it's dropped
+pub(crate) struct PendingGuard<'a>(&'a Pending);
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.done();
+    }
+}
+
+impl ErrorHandler {
+    /// Wait until the errors being delivered and the ones queued in every
+    /// [`crate::Reporter`] are handled, then push the errors buffered for the
+    /// AWS log stream, at most for the deadline, returning whether they were
+    /// all delivered
+    ///
+    /// The file is written to without buffering, so this is only needed for
+    /// the network sinks, such as before the process exits during a deploy
+    pub async fn flush(&self, deadline: Duration) -> bool {
+        time::timeout(deadline, async {
+            self.pending.idle().await;
+            #[cfg(feature = "cloudwatch")]
+            if let Err(err) = self.push_cloudwatch().await {
+                self.config()
+                    .print_notice(&format!("Failed to push errors to CloudWatch: {err}"));
+                return false;
+            }
+
+            true
+        })
+        .await
+        .unwrap_or(false)
+    }
+
+    /// Spawn a Tokio task that waits for `SIGTERM` or `SIGINT`, then
+    /// [flushes](Self::flush) the handler for at most the deadline and exits
+    /// the process with 143 or 130, the usual codes of these signals
+    ///
+    /// The signals' handlers are registered before this returns, so it has to
+    /// be called inside a Tokio runtime with its IO driver enabled
+    ///
+    /// # Errors
+    /// Returns [`io::Error`] if registering the handlers fails
+    #[cfg(all(feature = "unix", unix))]
+    pub fn install_signal_flush(self: Arc<Self>, deadline: Duration) -> io::Result<JoinHandle<()>> {
+        let mut terminate = signal(SignalKind::terminate())?;
+        let mut interrupt = signal(SignalKind::interrupt())?;
+
+        Ok(tokio::spawn(async move {
+            // the macro picks the branch to poll first with `%`
+            #[allow(clippy::integer_division_remainder_used)]
+            let code = tokio::select! {
+                _ = terminate.recv() => 143_i32,
+                _ = interrupt.recv() => 130_i32,
+            };
+            if !self.flush(deadline).await {
+                self.config().print_notice(&format!(
+                    "Exiting before the errors being handled were delivered after {}s",
+                    deadline.as_secs()
+                ));
+            }
+
+            #[allow(clippy::exit)]
+            process::exit(code);
+        }))
+    }
+}
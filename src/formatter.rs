@@ -0,0 +1,501 @@
+//! Rendering errors differently for each sink
+
+use core::fmt::{self, Write as _};
+use std::{borrow::Cow, sync::Arc};
+
+#[cfg(feature = "discord")]
+use twilight_model::channel::message::Embed;
+
+#[cfg(feature = "file")]
+use crate::backtrace;
+#[cfg(feature = "discord")]
+use crate::ErrorId;
+use crate::{
+    color, config::Config, ConsoleOutput, ErrorHandler, ErrorRecord, Severity, Sink,
+    TimestampFormat, Timezone,
+};
+
+/// Renders handled errors, set for a sink with [`ErrorHandler::formatter`]
+///
+/// Closures taking an [`ErrorRecord`] and returning a [`FormattedOutput`] are
+/// also formatters
+///
+/// ```ignore
+/// handler.formatter(Sink::File, |record: &ErrorRecord| {
+///     FormattedOutput::new(format!("{} {}\n", record.id, record.error))
+/// });
+/// ```
+///
+/// [`PlainFormatter`], [`MarkdownFormatter`] and [`JsonFormatter`] are
+/// built in
+///
+/// The failures of other sinks and the circuit breaker notices are appended
+/// to the content of the output, the backtrace is still attached to Discord
+/// messages
+pub trait Formatter: Send + Sync {
+    /// Returns the output of the handled error
+    fn format(&self, record: &ErrorRecord) -> FormattedOutput;
+
+    /// Returns the output of the handled error, with the format and timezone
+    /// of [`crate::ErrorHandler::timestamp_format`] to format when it was
+    /// handled with
+    ///
+    /// This is what the handler calls, defaults to [`Self::format`]
+    fn format_with_timestamp(
+        &self,
+        record: &ErrorRecord,
+        _timestamp_format: &TimestampFormat,
+        _timezone: Timezone,
+    ) -> FormattedOutput {
+        self.format(record)
+    }
+}
+
+impl<F: Fn(&ErrorRecord) -> FormattedOutput + Send + Sync> Formatter for F {
+    fn format(&self, record: &ErrorRecord) -> FormattedOutput {
+        self(record)
+    }
+}
+
+/// The output of a [`Formatter`]
+#[derive(Clone, Debug, Default)]
+#[must_use]
+#[non_exhaustive]
+pub struct FormattedOutput {
+    /// The text of the output, the content of Discord messages
+    pub content: String,
+    /// The embeds of Discord messages, ignored by the file and
+    /// [`std::io::stderr`]
+    #[cfg(feature = "discord")]
+    pub embeds: Vec<Embed>,
+}
+
+impl FormattedOutput {
+    /// Make an output with the given text
+    pub fn new(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            #[cfg(feature = "discord")]
+            embeds: Vec::new(),
+        }
+    }
+
+    /// Add an embed to Discord messages
+    #[cfg(feature = "discord")]
+    pub fn embed(mut self, embed: Embed) -> Self {
+        self.embeds.push(embed);
+        self
+    }
+}
+
+/// Renders errors as plain text, the default format
+///
+/// The output starts with a blank line to separate it from the previous
+/// error, followed by the ID, the error, the correlation ID and the fields
+#[derive(Clone, Copy, Debug, Default)]
+#[allow(clippy::exhaustive_structs)]
+pub struct PlainFormatter;
+
+impl Formatter for PlainFormatter {
+    fn format(&self, record: &ErrorRecord) -> FormattedOutput {
+        FormattedOutput::new(record.message())
+    }
+}
+
+/// Renders errors as Discord markdown, with the error in a code block and the
+/// ID, severity and fields in bold
+#[derive(Clone, Copy, Debug, Default)]
+#[allow(clippy::exhaustive_structs)]
+pub struct MarkdownFormatter;
+
+impl Formatter for MarkdownFormatter {
+    #[allow(unused_must_use)]
+    fn format(&self, record: &ErrorRecord) -> FormattedOutput {
+        let mut content = format!(
+            "**`{}`** {}\n```\n{}\n```",
+            record.id,
+            record.severity,
+            record.error.replace("```", "``\u{200b}`")
+        );
+
+        if let Some(correlation_id) = &record.correlation_id {
+            write!(content, "\n**Correlation ID**: `{correlation_id}`");
+        }
+        if let Some(kind) = &record.kind {
+            write!(content, "\n**Type**: `{kind}`");
+        }
+        if !record.tags.is_empty() {
+            write!(content, "\n**Tags**: {}", record.tags.join(", "));
+        }
+        for (name, value) in &record.fields {
+            write!(content, "\n**{name}**: {value}");
+        }
+
+        FormattedOutput::new(content)
+    }
+}
+
+/// Renders errors as compact JSON on one line, such as for files read by log
+/// collectors
+///
+/// Falls back to [`PlainFormatter`] if the record can't be serialized
+#[cfg(feature = "json")]
+#[derive(Clone, Copy, Debug, Default)]
+#[allow(clippy::exhaustive_structs)]
+pub struct JsonFormatter;
+
+#[cfg(feature = "json")]
+impl Formatter for JsonFormatter {
+    fn format(&self, record: &ErrorRecord) -> FormattedOutput {
+        serde_json::to_string(record).map_or_else(
+            |_| PlainFormatter.format(record),
+            |json| FormattedOutput::new(format!("{json}\n")),
+        )
+    }
+}
+
+/// The formatters set for each sink and [`std::io::stderr`]
+#[derive(Clone, Default)]
+pub(crate) struct Formatters {
+    /// Formatter of the outputs without their own formatter
+    default: Option<Arc<dyn Formatter>>,
+    /// Formatter of [`Sink::Channel`]
+    channel: Option<Arc<dyn Formatter>>,
+    /// Formatter of [`Sink::Webhook`]
+    webhook: Option<Arc<dyn Formatter>>,
+    /// Formatter of [`Sink::File`]
+    file: Option<Arc<dyn Formatter>>,
+    /// Formatter of [`std::io::stderr`]
+    stderr: Option<Arc<dyn Formatter>>,
+    /// Formatters of the Discord sinks for errors with the severities
+    #[cfg(feature = "discord")]
+    severities: Vec<(Severity, Arc<dyn Formatter>)>,
+}
+
+impl Formatters {
+    /// No formatters, so that every output uses the default format
+    pub(crate) const NONE: Self = Self {
+        default: None,
+        channel: None,
+        webhook: None,
+        file: None,
+        stderr: None,
+        #[cfg(feature = "discord")]
+        severities: Vec::new(),
+    };
+
+    /// Returns the formatter of the sink or of [`std::io::stderr`] if it's
+    /// `None`
+    fn get_mut(&mut self, sink: Option<Sink>) -> &mut Option<Arc<dyn Formatter>> {
+        match sink {
+            Some(Sink::Channel) => &mut self.channel,
+            Some(Sink::Webhook) => &mut self.webhook,
+            Some(Sink::File) => &mut self.file,
+            None => &mut self.stderr,
+        }
+    }
+
+    /// Sets the formatter of the Discord sinks for errors with the severity
+    #[cfg(feature = "discord")]
+    fn set_severity(&mut self, severity: Severity, formatter: Option<Arc<dyn Formatter>>) {
+        self.severities
+            .retain(|(set_severity, _)| *set_severity != severity);
+        self.severities
+            .extend(formatter.map(|set_formatter| (severity, set_formatter)));
+    }
+
+    /// Returns the formatter of the severity for the Discord sinks, or the
+    /// formatter of the sink or of [`std::io::stderr`] if it's `None`,
+    /// falling back to the default formatter
+    #[cfg_attr(not(feature = "discord"), allow(unused_variables))]
+    fn get(&self, sink: Option<Sink>, severity: Severity) -> Option<&dyn Formatter> {
+        #[cfg(feature = "discord")]
+        if let Some(Sink::Channel | Sink::Webhook) = sink {
+            if let Some((_, formatter)) = self
+                .severities
+                .iter()
+                .find(|(set_severity, _)| *set_severity == severity)
+            {
+                return Some(&**formatter);
+            }
+        }
+
+        match sink {
+            Some(Sink::Channel) => self.channel.as_deref(),
+            Some(Sink::Webhook) => self.webhook.as_deref(),
+            Some(Sink::File) => self.file.as_deref(),
+            None => self.stderr.as_deref(),
+        }
+        .or(self.default.as_deref())
+    }
+}
+
+impl fmt::Debug for Formatters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("Formatters");
+        debug
+            .field("default", &self.default.is_some())
+            .field("channel", &self.channel.is_some())
+            .field("webhook", &self.webhook.is_some())
+            .field("file", &self.file.is_some())
+            .field("stderr", &self.stderr.is_some());
+        #[cfg(feature = "discord")]
+        debug.field(
+            "severities",
+            &self
+                .severities
+                .iter()
+                .map(|(severity, _)| severity)
+                .collect::<Vec<_>>(),
+        );
+        debug.finish()
+    }
+}
+
+/// What's delivered to the sinks
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Message<'a> {
+    /// A handled error, rendered with the formatters
+    Record(&'a ErrorRecord),
+    /// A notice from the handler, such as a circuit breaker transition
+    #[cfg_attr(not(feature = "discord"), allow(dead_code))]
+    Notice(&'a str),
+}
+
+impl Message<'_> {
+    /// Returns the output for the sink or [`std::io::stderr`] if it's `None`
+    ///
+    /// ANSI escape codes are removed from the content for the sinks
+    pub(crate) fn output(self, config: &Config, sink: Option<Sink>) -> FormattedOutput {
+        let output = self.render(config, sink);
+        if sink.is_some() {
+            stripped(output)
+        } else {
+            output
+        }
+    }
+
+    /// Returns the output for the sink with the notes appended to its content,
+    /// such as the failures of the previous sinks
+    ///
+    /// The notes are appended to the rendered content without copying it, the
+    /// output of each sink is only rendered when it's delivered to
+    ///
+    /// The mentions of the record and the routes of its tags are prepended for
+    /// the Discord sinks
+    pub(crate) fn output_with_notes(
+        self,
+        config: &Config,
+        sink: Sink,
+        notes: &str,
+    ) -> FormattedOutput {
+        let mut output = self.output(config, Some(sink));
+        #[cfg(feature = "discord")]
+        if let (Self::Record(record), Sink::Channel | Sink::Webhook) = (self, sink) {
+            config.severity_styles.apply(&mut output, record.severity);
+            let mentions = record
+                .mentions
+                .iter()
+                .chain(
+                    config
+                        .tag_routes(&record.tags)
+                        .flat_map(|route| &route.mentions),
+                )
+                .map(String::as_str)
+                .collect::<Vec<_>>();
+            if !mentions.is_empty() {
+                let mut mention_line = mentions.join(" ");
+                if !output.content.starts_with('\n') {
+                    mention_line.push('\n');
+                }
+                output.content.insert_str(0, &mention_line);
+            }
+        }
+        output.content.push_str(notes);
+        output
+    }
+
+    /// Returns the output for an additional file, rendered with the given
+    /// formatter or like [`Sink::File`] if it's `None`
+    #[cfg(feature = "file")]
+    pub(crate) fn file_output(
+        self,
+        config: &Config,
+        formatter: Option<&dyn Formatter>,
+    ) -> FormattedOutput {
+        match (self, formatter) {
+            (Self::Record(record), Some(file_formatter)) => {
+                stripped(file_formatter.format_with_timestamp(
+                    record,
+                    &config.timestamp_format,
+                    config.timezone,
+                ))
+            }
+            _ => self.output(config, Some(Sink::File)),
+        }
+    }
+
+    /// Returns the severity of the handled error, `None` for notices
+    #[cfg(any(
+        feature = "discord",
+        feature = "file",
+        feature = "tracing",
+        feature = "log"
+    ))]
+    pub(crate) const fn severity(self) -> Option<Severity> {
+        match self {
+            Self::Record(record) => Some(record.severity),
+            Self::Notice(_) => None,
+        }
+    }
+
+    /// Returns the output for the sink or [`std::io::stderr`] if it's `None`
+    ///
+    /// Records are rendered with the sink's formatter, the default formatter
+    /// if it has none, or with the ID, the error and the fields by default,
+    /// colored by severity in [`std::io::stderr`] and after the timestamp and
+    /// followed by the trimmed backtrace in the file
+    fn render(self, config: &Config, sink: Option<Sink>) -> FormattedOutput {
+        let record = match self {
+            Self::Record(record) => record,
+            Self::Notice(notice) => return FormattedOutput::new(notice),
+        };
+
+        if let Some(formatter) = config.formatters.get(sink, record.severity) {
+            return formatter.format_with_timestamp(
+                record,
+                &config.timestamp_format,
+                config.timezone,
+            );
+        }
+
+        if sink.is_none() && config.console_output == ConsoleOutput::Stderr {
+            if let Some(colored) = color::colored(record) {
+                return FormattedOutput::new(colored);
+            }
+        }
+
+        #[cfg(feature = "file")]
+        if sink == Some(Sink::File) {
+            let timestamp = config
+                .timestamp_format
+                .format(record.handled_at, config.timezone);
+            let mut content = format!(
+                "\n\n{timestamp} {}",
+                record.message().trim_start_matches('\n')
+            );
+            if let Some(trace) = &record.backtrace {
+                content.push_str("\n\nBacktrace:\n");
+                content.push_str(&backtrace::trim(trace));
+            }
+            return FormattedOutput::new(content);
+        }
+
+        FormattedOutput::new(record.message())
+    }
+}
+
+/// Returns the output without ANSI escape codes in its content
+fn stripped(mut output: FormattedOutput) -> FormattedOutput {
+    if let Cow::Owned(stripped) = color::strip(&output.content) {
+        output.content = stripped;
+    }
+    output
+}
+
+#[cfg(feature = "discord")]
+impl<'a> Message<'a> {
+    /// Returns the backtrace to attach to Discord messages
+    pub(crate) fn backtrace(self) -> Option<&'a str> {
+        match self {
+            Self::Record(record) => record.backtrace.as_deref(),
+            Self::Notice(_) => None,
+        }
+    }
+
+    /// Returns the ID of the error, the idempotency key of its deliveries
+    pub(crate) const fn id(self) -> Option<ErrorId> {
+        match self {
+            Self::Record(record) => Some(record.id),
+            Self::Notice(_) => None,
+        }
+    }
+}
+
+impl ErrorHandler {
+    /// Set the formatter to render errors with for every sink and
+    /// [`std::io::stderr`] that has no formatter of its own
+    ///
+    /// This replaces the colors of [`std::io::stderr`] and the timestamp and
+    /// backtrace of the file
+    pub fn default_formatter(&mut self, formatter: impl Formatter + 'static) -> &mut Self {
+        self.config_mut().formatters.default = Some(Arc::new(formatter));
+        self
+    }
+
+    /// Set the formatter to render errors with for the sink, overriding
+    /// [`Self::default_formatter`]
+    ///
+    /// Errors are rendered with their ID, the error and the fields by default
+    pub fn formatter(&mut self, sink: Sink, formatter: impl Formatter + 'static) -> &mut Self {
+        *self.config_mut().formatters.get_mut(Some(sink)) = Some(Arc::new(formatter));
+        self
+    }
+
+    /// Set the formatter to render errors printed to [`std::io::stderr`] with,
+    /// overriding [`Self::default_formatter`]
+    pub fn stderr_formatter(&mut self, formatter: impl Formatter + 'static) -> &mut Self {
+        *self.config_mut().formatters.get_mut(None) = Some(Arc::new(formatter));
+        self
+    }
+
+    /// Change the formatter of the sink at runtime, `None` to use the default
+    /// format
+    ///
+    /// Same as [`Self::formatter`] but through a shared reference
+    pub fn set_formatter(&self, sink: Sink, formatter: Option<Arc<dyn Formatter>>) {
+        self.update_config(|config| *config.formatters.get_mut(Some(sink)) = formatter);
+    }
+
+    /// Set the formatter to render errors with the severity with for the
+    /// Discord sinks, overriding [`Self::formatter`] and
+    /// [`Self::default_formatter`]
+    ///
+    /// ```ignore
+    /// handler.severity_formatter(Severity::Warning, |record: &ErrorRecord| {
+    ///     FormattedOutput::new(format!("`{}` {}", record.id, record.error))
+    /// });
+    /// ```
+    #[cfg(feature = "discord")]
+    pub fn severity_formatter(
+        &mut self,
+        severity: Severity,
+        formatter: impl Formatter + 'static,
+    ) -> &mut Self {
+        self.config_mut()
+            .formatters
+            .set_severity(severity, Some(Arc::new(formatter)));
+        self
+    }
+
+    /// Change the formatter of the severity at runtime, `None` to use the
+    /// formatter of the sink
+    ///
+    /// Same as [`Self::severity_formatter`] but through a shared reference
+    #[cfg(feature = "discord")]
+    pub fn set_severity_formatter(
+        &self,
+        severity: Severity,
+        formatter: Option<Arc<dyn Formatter>>,
+    ) {
+        self.update_config(|config| config.formatters.set_severity(severity, formatter));
+    }
+
+    /// Change the default formatter at runtime, `None` to use the default
+    /// format
+    ///
+    /// Same as [`Self::default_formatter`] but through a shared reference
+    pub fn set_default_formatter(&self, formatter: Option<Arc<dyn Formatter>>) {
+        self.update_config(|config| config.formatters.default = formatter);
+    }
+}
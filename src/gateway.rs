@@ -0,0 +1,253 @@
+//! Reporting the problems of gateway shards
+
+use core::{fmt::Display, future::Future, time::Duration};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Mutex, PoisonError},
+};
+
+use tokio::time::Instant;
+use twilight_http::Client;
+use twilight_model::gateway::{event::Event, CloseCode};
+
+use crate::{ErrorHandler, ErrorId, ErrorReport, TwilightErrorKind, SHARD_FIELD};
+
+/// The number of reconnects in [`DEFAULT_RECONNECT_WINDOW`] reported by default
+const DEFAULT_RECONNECT_LIMIT: usize = 5;
+
+/// The interval reconnects are counted in by default
+const DEFAULT_RECONNECT_WINDOW: Duration = Duration::from_mins(5);
+
+/// The reconnects of every shard
+#[derive(Debug)]
+struct ReconnectState {
+    /// The number of reconnects in the window to report after
+    limit: usize,
+    /// The interval reconnects are counted in
+    window: Duration,
+    /// When each shard reconnected in the window
+    reconnects: HashMap<u64, VecDeque<Instant>>,
+}
+
+/// Counts the reconnects of shards to report reconnect storms
+#[derive(Debug)]
+pub(crate) struct GatewayTracker {
+    /// The reconnects of every shard
+    state: Mutex<ReconnectState>,
+}
+
+impl GatewayTracker {
+    /// Make a tracker with the default limit
+    pub(crate) fn new() -> Self {
+        Self {
+            state: Mutex::new(ReconnectState {
+                limit: DEFAULT_RECONNECT_LIMIT,
+                window: DEFAULT_RECONNECT_WINDOW,
+                reconnects: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Set the number of reconnects in the window to report after
+    fn configure(&self, limit: usize, window: Duration) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.limit = limit;
+        state.window = window;
+    }
+
+    /// Records a reconnect of the shard, returning the number of reconnects
+    /// in the window if it just reached the limit
+    fn record_reconnect(&self, shard_id: u64) -> Option<(usize, Duration)> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let limit = state.limit;
+        let window = state.window;
+        let reconnects = state.reconnects.entry(shard_id).or_default();
+
+        let now = Instant::now();
+        while reconnects
+            .front()
+            .is_some_and(|reconnected_at| now.duration_since(*reconnected_at) >= window)
+        {
+            reconnects.pop_front();
+        }
+        reconnects.push_back(now);
+
+        (reconnects.len() == limit).then_some((limit, window))
+    }
+}
+
+impl ErrorHandler {
+    /// Set the number of times a shard can reconnect in the given interval
+    /// before it's reported
+    ///
+    /// Defaults to 5 times in 5 minutes, see [`Self::handle_gateway_event`]
+    pub fn reconnect_limit(&mut self, limit: usize, window: Duration) -> &mut Self {
+        self.gateway.configure(limit, window);
+        self
+    }
+
+    /// Change the number of times a shard can reconnect in the given interval
+    /// before it's reported at runtime
+    ///
+    /// Same as [`Self::reconnect_limit`] but through a shared reference
+    pub fn set_reconnect_limit(&self, limit: usize, window: Duration) {
+        self.gateway.configure(limit, window);
+    }
+
+    /// Report the problems the event of the shard shows, call this with every
+    /// event the shard receives
+    ///
+    /// - Disconnects with close codes that can't be recovered from, such as
+    ///   an invalid token or disallowed intents, are handled as
+    ///   [`TwilightErrorKind::FatalClose`], other close codes as
+    ///   [`TwilightErrorKind::Close`]
+    /// - Invalidated sessions are handled as
+    ///   [`TwilightErrorKind::InvalidatedSession`]
+    /// - Reconnecting [`Self::reconnect_limit`] times is handled as
+    ///   [`TwilightErrorKind::ReconnectStorm`] once per interval
+    ///
+    /// [`Self::next_gateway_event`] calls this and also reports the errors of
+    /// receiving the events
+    ///
+    /// The shard ID is attached to every report, see
+    /// [`Self::twilight_severity`] for the severity of each kind
+    pub async fn handle_gateway_event(&self, http: &Client, shard_id: u64, event: &Event) {
+        if let Some(report) = self.gateway_report(shard_id, event) {
+            self.handle_report(http, report.field(SHARD_FIELD, shard_id))
+                .await;
+        }
+    }
+
+    /// Handle an error receiving a message of the shard, such as the
+    /// `ReceiveMessageError` of `twilight-gateway`, pass its `is_fatal()`
+    ///
+    /// Fatal errors are handled as [`TwilightErrorKind::FatalReceive`] and the
+    /// others as [`TwilightErrorKind::ReceiveFailed`], see
+    /// [`Self::twilight_severity`] for their severities, the shard ID is
+    /// attached
+    pub async fn handle_receive_error(
+        &self,
+        http: &Client,
+        shard_id: u64,
+        error: impl Display + Send,
+        fatal: bool,
+    ) -> ErrorId {
+        self.handle_report(
+            http,
+            receive_error_report(error, fatal).field(SHARD_FIELD, shard_id),
+        )
+        .await
+    }
+
+    /// Receive the next event of the shard, reporting the error if receiving
+    /// fails and the problems the event shows otherwise
+    ///
+    /// Returns the event, `None` if receiving failed but the shard can keep
+    /// going and the error if it's fatal according to `is_fatal`, see
+    /// [`Self::handle_gateway_event`] and [`Self::handle_receive_error`]
+    ///
+    /// ```ignore
+    /// loop {
+    ///     match handler
+    ///         .next_gateway_event(&http, shard_id, shard.next_event(), ReceiveMessageError::is_fatal)
+    ///         .await
+    ///     {
+    ///         Ok(Some(event)) => process(event).await,
+    ///         Ok(None) => continue,
+    ///         Err(_) => break,
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns the error of receiving if it's fatal
+    pub async fn next_gateway_event<E: Display + Send>(
+        &self,
+        http: &Client,
+        shard_id: u64,
+        receive: impl Future<Output = Result<Event, E>> + Send,
+        is_fatal: impl FnOnce(&E) -> bool + Send,
+    ) -> Result<Option<Event>, E> {
+        match receive.await {
+            Ok(event) => {
+                self.handle_gateway_event(http, shard_id, &event).await;
+                Ok(Some(event))
+            }
+            Err(err) => {
+                let fatal = is_fatal(&err);
+                let report = receive_error_report(&err, fatal).field(SHARD_FIELD, shard_id);
+                self.handle_report(http, report).await;
+                if fatal {
+                    Err(err)
+                } else {
+                    Ok(None)
+                }
+            }
+        }
+    }
+
+    /// Returns the report of the problem the event of the shard shows, none
+    /// if it doesn't show one
+    #[allow(clippy::wildcard_enum_match_arm)]
+    pub(crate) fn gateway_report(&self, shard_id: u64, event: &Event) -> Option<ErrorReport> {
+        let report = match event {
+            Event::ShardDisconnected(disconnected) => {
+                let code = disconnected.code.filter(|code| *code >= 4000)?;
+                let reason = disconnected.reason.as_deref().unwrap_or("no reason");
+                let kind = if CloseCode::try_from(code).is_ok_and(is_fatal) {
+                    TwilightErrorKind::FatalClose
+                } else {
+                    TwilightErrorKind::Close
+                };
+
+                ErrorReport::new(format_args!(
+                    "Shard disconnected with close code {code}: {reason}"
+                ))
+                .twilight_kind(kind)
+            }
+            Event::GatewayInvalidateSession(resumable) => ErrorReport::new(format_args!(
+                "Shard session was invalidated, resumable: {resumable}"
+            ))
+            .twilight_kind(TwilightErrorKind::InvalidatedSession),
+            Event::ShardReconnecting(_) => {
+                let (limit, window) = self.gateway.record_reconnect(shard_id)?;
+
+                ErrorReport::new(format_args!(
+                    "Shard reconnected {limit} times in the last {}s",
+                    window.as_secs()
+                ))
+                .twilight_kind(TwilightErrorKind::ReconnectStorm)
+            }
+            _ => return None,
+        };
+
+        Some(report.without_location())
+    }
+}
+
+/// Returns the report of an error receiving a message of a shard, with the
+/// kind of whether it's fatal
+pub(crate) fn receive_error_report(error: impl Display, fatal: bool) -> ErrorReport {
+    let kind = if fatal {
+        TwilightErrorKind::FatalReceive
+    } else {
+        TwilightErrorKind::ReceiveFailed
+    };
+
+    ErrorReport::new(format_args!("Shard failed to receive a message: {error}"))
+        .twilight_kind(kind)
+        .without_location()
+}
+
+/// Returns whether the shard can't connect again after closing with the code
+const fn is_fatal(code: CloseCode) -> bool {
+    matches!(
+        code,
+        CloseCode::AuthenticationFailed
+            | CloseCode::InvalidShard
+            | CloseCode::ShardingRequired
+            | CloseCode::InvalidApiVersion
+            | CloseCode::InvalidIntents
+            | CloseCode::DisallowedIntents
+    )
+}
@@ -0,0 +1,69 @@
+//! Checking whether the sinks work before an error happens
+
+use twilight_http::Client;
+
+#[cfg(feature = "file")]
+use crate::append_error;
+use crate::{create_message, execute_webhook, payload::Payload, ErrorHandler, Sink};
+
+/// The message sent through the sinks by [`ErrorHandler::test`]
+pub const TEST_MESSAGE: &str = "Error reporting is online";
+
+impl ErrorHandler {
+    /// Send [`TEST_MESSAGE`] through every sink that is set, returning
+    /// whether each of them works
+    ///
+    /// Call this on startup to find out about invalid channels, revoked webhook
+    /// tokens or unwritable files before a real error is lost to them
+    ///
+    /// Sinks disabled by the circuit breaker are tested too, and are enabled
+    /// again if they work, if [`Self::dry_run`] is set, the channel and the
+    /// webhook aren't tested and are reported as working
+    pub async fn test(&self, http: &Client) -> Vec<(Sink, Result<(), String>)> {
+        let config = self.config();
+        let mut results = Vec::new();
+
+        if config.dry_run && (config.channel.is_some() || config.owner.is_some()) {
+            results.push((Sink::Channel, Ok(())));
+        }
+        let channel = if config.dry_run {
+            None
+        } else {
+            self.resolve_channel(http, &config).await
+        };
+        let payload = Payload::new(&config, TEST_MESSAGE, &[], Vec::new());
+        if let Some(channel_id) = channel {
+            let result = match channel_id {
+                Ok(id) => create_message(http, id, &payload, None)
+                    .await
+                    .map(drop)
+                    .map_err(|err| err.to_string()),
+                Err(err) => Err(err),
+            };
+            self.record_result(Sink::Channel, result.is_ok());
+            results.push((Sink::Channel, result));
+        }
+        if let Some((webhook_id, token)) = &config.webhook {
+            if config.dry_run {
+                results.push((Sink::Webhook, Ok(())));
+            } else {
+                let result = execute_webhook(http, *webhook_id, token, &payload).await;
+                self.record_result(Sink::Webhook, result.is_ok());
+                results.push((Sink::Webhook, result.map_err(|err| err.to_string())));
+            }
+        }
+        #[cfg(feature = "file")]
+        if let Some(path) = &config.file {
+            let result = append_error(path, &format!("\n\n{TEST_MESSAGE}"));
+            self.record_result(Sink::File, result.is_ok());
+            results.push((Sink::File, result.map_err(|err| err.to_string())));
+        }
+        #[cfg(feature = "file")]
+        for file in &config.additional_files {
+            let result = append_error(&file.path, &format!("\n\n{TEST_MESSAGE}"));
+            results.push((Sink::File, result.map_err(|err| err.to_string())));
+        }
+
+        results
+    }
+}
@@ -0,0 +1,40 @@
+//! Telling that there were no errors, so that it's not mistaken for error
+//! reporting being broken
+
+use core::time::Duration;
+use std::sync::Arc;
+
+use tokio::{task::JoinHandle, time};
+use twilight_http::Client;
+
+use crate::{formatter::Message, ErrorHandler};
+
+impl ErrorHandler {
+    /// Spawn a Tokio task that delivers a notice to the sinks every
+    /// `interval` in which no errors were handled
+    ///
+    /// The notice is only delivered if there were no errors, the errors
+    /// themselves show that error reporting works otherwise
+    pub fn spawn_heartbeat(
+        self: Arc<Self>,
+        http: Arc<Client>,
+        interval: Duration,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = time::interval(interval);
+            ticker.tick().await;
+            let mut last_total = self.stats().total;
+
+            loop {
+                ticker.tick().await;
+                let total = self.stats().total;
+                if total == last_total {
+                    let notice = format!("\n\nNo errors in the last {}s", interval.as_secs());
+                    self.report(&http, &self.config(), Message::Notice(&notice), &[])
+                        .await;
+                }
+                last_total = total;
+            }
+        })
+    }
+}
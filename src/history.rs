@@ -0,0 +1,261 @@
+//! Keeping the recently handled errors in memory
+
+use core::fmt::Write as _;
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, PoisonError},
+    time::SystemTime,
+};
+#[cfg(feature = "json")]
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, ErrorKind, Write as _},
+    path::Path,
+};
+
+use crate::{ErrorHandler, ErrorId, Severity};
+
+/// The number of errors kept by default
+pub(crate) const DEFAULT_CAPACITY: usize = 100;
+
+/// An error that was handled, returned from [`ErrorHandler::recent_errors`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[non_exhaustive]
+pub struct ErrorRecord {
+    /// The unique ID of the error
+    pub id: ErrorId,
+    /// How serious the error was
+    pub severity: Severity,
+    /// The displayed error, with secrets redacted
+    pub error: String,
+    /// The type of the error, see [`crate::ErrorReport::kind`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub kind: Option<String>,
+    /// The tags set with [`crate::ErrorReport::tags`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tags: Vec<String>,
+    /// The ID set with [`crate::ErrorReport::correlation_id`]
+    pub correlation_id: Option<String>,
+    /// The fields set with [`crate::ErrorReport::field`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fields: Vec<(String, String)>,
+    /// The backtrace of where the error was handled, see
+    /// [`ErrorHandler::capture_backtraces`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub backtrace: Option<String>,
+    /// When the error was handled
+    pub handled_at: SystemTime,
+    /// The mentions prepended to the Discord messages of the error, outside
+    /// of its formatted content, such as the role of a crash loop alert
+    #[cfg(feature = "discord")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mentions: Vec<String>,
+}
+
+impl ErrorRecord {
+    /// Returns the error message to deliver to the sinks
+    #[allow(unused_must_use)]
+    pub(crate) fn message(&self) -> String {
+        let mut message = format!("\n\n[{}] {}", self.id, self.error);
+
+        if let Some(correlation_id) = &self.correlation_id {
+            write!(message, "\nCorrelation ID: {correlation_id}");
+        }
+        if let Some(kind) = &self.kind {
+            write!(message, "\nType: {kind}");
+        }
+        if !self.tags.is_empty() {
+            write!(message, "\nTags: {}", self.tags.join(", "));
+        }
+        for (name, value) in &self.fields {
+            write!(message, "\n{name}: {value}");
+        }
+
+        message
+    }
+
+    /// Returns whether the lowercase query is in the ID, the error or its
+    /// type, tags, correlation ID or fields
+    fn contains(&self, query_lowercase: &str) -> bool {
+        let matches = |text: &str| text.to_lowercase().contains(query_lowercase);
+
+        matches(&self.id.to_string())
+            || matches(&self.error)
+            || self.kind.as_deref().is_some_and(matches)
+            || self.tags.iter().any(|tag| matches(tag))
+            || self.correlation_id.as_deref().is_some_and(matches)
+            || self
+                .fields
+                .iter()
+                .any(|(name, value)| matches(name) || matches(value))
+    }
+}
+
+/// The most recently handled errors, oldest first
+#[derive(Debug)]
+pub(crate) struct History {
+    /// The number of errors to keep
+    capacity: Mutex<usize>,
+    /// The kept errors
+    records: Mutex<VecDeque<ErrorRecord>>,
+}
+
+impl History {
+    /// Make an empty history that keeps [`DEFAULT_CAPACITY`] errors
+    pub(crate) const fn new() -> Self {
+        Self {
+            capacity: Mutex::new(DEFAULT_CAPACITY),
+            records: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Set the number of errors to keep, dropping the oldest ones if there are
+    /// more
+    pub(crate) fn set_capacity(&self, capacity: usize) {
+        *self.capacity.lock().unwrap_or_else(PoisonError::into_inner) = capacity;
+        let mut records = self.records.lock().unwrap_or_else(PoisonError::into_inner);
+        while records.len() > capacity {
+            records.pop_front();
+        }
+    }
+
+    /// Keeps the error, dropping the oldest one if the history is full
+    pub(crate) fn push(&self, record: ErrorRecord) {
+        let capacity = *self.capacity.lock().unwrap_or_else(PoisonError::into_inner);
+        if capacity == 0 {
+            return;
+        }
+
+        let mut records = self.records.lock().unwrap_or_else(PoisonError::into_inner);
+        while records.len() >= capacity {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    /// Keeps the errors that were handled before the current ones, oldest
+    /// first, dropping the oldest ones if the history is full
+    #[cfg(feature = "json")]
+    fn extend_older(&self, older: Vec<ErrorRecord>) {
+        let capacity = *self.capacity.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut records = self.records.lock().unwrap_or_else(PoisonError::into_inner);
+
+        for record in older.into_iter().rev() {
+            if records.len() >= capacity {
+                break;
+            }
+            records.push_front(record);
+        }
+    }
+
+    /// Returns the kept errors, newest first
+    pub(crate) fn get(&self) -> Vec<ErrorRecord> {
+        self.records
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .rev()
+            .cloned()
+            .collect()
+    }
+}
+
+impl ErrorHandler {
+    /// Set the number of recently handled errors to keep in memory, `0` to
+    /// keep none
+    ///
+    /// Defaults to 100, see [`Self::recent_errors`]
+    pub fn history(&mut self, capacity: usize) -> &mut Self {
+        self.history.set_capacity(capacity);
+        self
+    }
+
+    /// Set the number of recently handled errors to keep in memory at runtime
+    ///
+    /// Same as [`Self::history`] but through a shared reference
+    pub fn set_history(&self, capacity: usize) {
+        self.history.set_capacity(capacity);
+    }
+
+    /// Returns the recently handled errors, newest first
+    ///
+    /// Errors below [`Self::min_severity`] are included too
+    pub fn recent_errors(&self) -> Vec<ErrorRecord> {
+        self.history.get()
+    }
+
+    /// Returns the recently handled errors that contain the query, newest
+    /// first
+    ///
+    /// The query is matched case-insensitively against the ID, the error and
+    /// its type, tags, correlation ID and fields, errors handled before
+    /// `since` or below `min_severity` are left out if they're set
+    pub fn search_errors(
+        &self,
+        query: &str,
+        since: Option<SystemTime>,
+        min_severity: Option<Severity>,
+    ) -> Vec<ErrorRecord> {
+        let query_lowercase = query.to_lowercase();
+
+        self.history
+            .get()
+            .into_iter()
+            .filter(|record| {
+                since.is_none_or(|time| record.handled_at >= time)
+                    && min_severity.is_none_or(|severity| record.severity >= severity)
+                    && record.contains(&query_lowercase)
+            })
+            .collect()
+    }
+
+    /// Write the recently handled errors to the JSON file at the given path,
+    /// such as when the bot is shutting down
+    ///
+    /// Load them with [`Self::load_history`] when it starts again, so that
+    /// [`Self::recent_errors`] includes the errors before the restart
+    ///
+    /// The errors are written to a temporary file next to it first, which
+    /// then replaces the file, so it's never left half written
+    ///
+    /// # Errors
+    /// Returns [`io::Error`] if writing the file fails
+    #[cfg(feature = "json")]
+    pub fn save_history(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut records = self.recent_errors();
+        records.reverse();
+
+        let mut temp_path = path.as_ref().as_os_str().to_owned();
+        temp_path.push(".tmp");
+
+        let mut writer = BufWriter::new(File::create(&temp_path)?);
+        serde_json::to_writer(&mut writer, &records).map_err(io::Error::from)?;
+        writer.flush()?;
+        drop(writer);
+
+        fs::rename(temp_path, path)
+    }
+
+    /// Read the errors written with [`Self::save_history`] from the JSON file
+    /// at the given path, keeping them before the errors handled since
+    ///
+    /// Nothing is read if the file doesn't exist, the oldest errors are
+    /// dropped if there are more than [`Self::history`]
+    ///
+    /// # Errors
+    /// Returns [`io::Error`] if reading or deserializing the file fails
+    #[cfg(feature = "json")]
+    pub fn load_history(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err),
+        };
+
+        let records = serde_json::from_str(&content).map_err(io::Error::from)?;
+        self.history.extend_older(records);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+//! Showing the details of `twilight_http` errors
+
+use core::{
+    error::Error,
+    fmt::{Display, Write as _},
+    iter,
+};
+
+use serde_json::Value;
+use twilight_http::{
+    api_error::ApiError, error::ErrorType, request::Method, routing::Route, Error as HttpError,
+};
+
+use crate::{ErrorReport, TwilightErrorKind};
+
+/// The maximum number of invalid fields shown
+const MAX_INVALID_FIELDS: usize = 10;
+
+/// The field of the method and route of the request an error is from
+const ROUTE_FIELD: &str = "Route";
+
+/// The Discord error codes of missing access and missing permissions
+const MISSING_PERMISSIONS_CODES: [u64; 2] = [50001, 50013];
+
+/// Returns whether the error is because the bot is missing access or
+/// permissions
+pub(crate) fn is_missing_permissions(error: &HttpError) -> bool {
+    matches!(
+        error.kind(),
+        ErrorType::Response {
+            error: ApiError::General(general),
+            ..
+        } if MISSING_PERMISSIONS_CODES.contains(&general.code)
+    )
+}
+
+/// Returns whether retrying the request might succeed, which isn't the case
+/// for errors caused by the request itself, such as missing permissions
+pub(crate) fn is_retryable(error: &HttpError) -> bool {
+    TwilightErrorKind::from_http_error(error) != Some(TwilightErrorKind::ClientError)
+}
+
+impl ErrorReport {
+    /// Make a report of the error with [`Severity::Error`]
+    ///
+    /// If the error is or is caused by a [`HttpError`], the status of the
+    /// response, the Discord error code and message and the fields Discord
+    /// found invalid are attached as fields, and it's handled with the
+    /// severity of its [`TwilightErrorKind`]
+    ///
+    /// [`Severity::Error`]: crate::Severity::Error
+    #[track_caller]
+    pub fn from_error(error: &(dyn Error + 'static)) -> Self {
+        let report = Self::new(error);
+
+        match iter::successors(Some(error), |&err| err.source())
+            .find_map(|err| err.downcast_ref::<HttpError>())
+        {
+            Some(http_error) => match TwilightErrorKind::from_http_error(http_error) {
+                Some(kind) => report.twilight_kind(kind).http_error_fields(http_error),
+                None => report.http_error_fields(http_error),
+            },
+            None => report,
+        }
+    }
+
+    /// Attach the method and route of the request the error is from, such as
+    /// `POST channels/123/messages`
+    ///
+    /// `twilight_http` errors don't include their request, so it's only
+    /// attached to the errors of [`crate::ErrorHandler::send`], use this along
+    /// with [`Self::from_error`] for requests sent otherwise, such as in
+    /// `ErrorReport::from_error(&err).route(&Route::GetGateway)`
+    pub fn route(self, route: &Route<'_>) -> Self {
+        self.request_route(route.method(), route)
+    }
+
+    /// Attach the method and path of the request the error is from
+    pub(crate) fn request_route(self, method: Method, path: impl Display) -> Self {
+        self.field(
+            ROUTE_FIELD,
+            format_args!("{} {path}", method.to_http().as_str()),
+        )
+    }
+
+    /// Attach the details of the `twilight_http` error
+    fn http_error_fields(self, error: &HttpError) -> Self {
+        let ErrorType::Response {
+            body,
+            error: api_error,
+            status,
+        } = error.kind()
+        else {
+            return self;
+        };
+
+        let mut report = self.field("Status", status);
+
+        if let ApiError::General(general) = api_error {
+            report = report
+                .field("Discord error code", general.code)
+                .field("Discord error message", &general.message);
+        }
+        if let ApiError::Ratelimited(ratelimited) = api_error {
+            report = report.field(
+                "Retry after",
+                format_args!(
+                    "{}s, global: {}",
+                    ratelimited.retry_after, ratelimited.global
+                ),
+            );
+        }
+
+        let invalid_fields = serde_json::from_slice::<Value>(body)
+            .ok()
+            .and_then(|value| value.get("errors").map(invalid_fields))
+            .unwrap_or_default();
+        if invalid_fields.is_empty() {
+            report
+        } else {
+            report.field("Invalid fields", invalid_fields)
+        }
+    }
+}
+
+/// Returns the fields in the `errors` object of a Discord error response, one
+/// per line, such as `embeds.0.title: Must be 256 or fewer in length.`
+#[allow(unused_must_use)]
+fn invalid_fields(errors: &Value) -> String {
+    let mut lines = Vec::new();
+    collect_invalid_fields(errors, "", &mut lines);
+
+    let mut rendered = lines
+        .iter()
+        .take(MAX_INVALID_FIELDS)
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join("\n");
+    if let Some(more) = lines
+        .len()
+        .checked_sub(MAX_INVALID_FIELDS)
+        .filter(|more| *more > 0)
+    {
+        write!(rendered, "\nand {more} more");
+    }
+
+    rendered
+}
+
+/// Adds the errors of the object and its nested objects at the path to the
+/// lines
+fn collect_invalid_fields(value: &Value, path: &str, lines: &mut Vec<String>) {
+    let Some(object) = value.as_object() else {
+        return;
+    };
+
+    for (key, nested) in object {
+        if key == "_errors" {
+            for error in nested.as_array().into_iter().flatten() {
+                let message = error.get("message").and_then(Value::as_str).unwrap_or("");
+                let code = error.get("code").and_then(Value::as_str).unwrap_or("");
+                lines.push(format!("{path}: {message} ({code})"));
+            }
+        } else if path.is_empty() {
+            collect_invalid_fields(nested, key, lines);
+        } else {
+            collect_invalid_fields(nested, &format!("{path}.{key}"), lines);
+        }
+    }
+}
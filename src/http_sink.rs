@@ -0,0 +1,171 @@
+//! Posting errors to an HTTP endpoint
+
+use core::time::Duration;
+use std::{
+    io::{self, ErrorKind},
+    sync::LazyLock,
+};
+
+use hyper::{body, client::HttpConnector, header, Body, Client, Request, StatusCode, Uri};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use tokio::time;
+
+use crate::{
+    config::Config,
+    delivery,
+    signing::{hex, hmac_sha256},
+    ErrorHandler, ErrorRecord,
+};
+
+/// The header of the signature of the body, `sha256=` followed by the
+/// HMAC-SHA256 of the body with the secret of
+/// [`ErrorHandler::http_signing_secret`] in lowercase hexadecimal
+pub const SIGNATURE_HEADER: &str = "X-Signature-256";
+
+/// How long posting an error can take before it fails
+const POST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The client the errors are posted with
+static CLIENT: LazyLock<Client<HttpsConnector<HttpConnector>>> = LazyLock::new(|| {
+    Client::builder().build(
+        HttpsConnectorBuilder::new()
+            .with_native_roots()
+            .https_or_http()
+            .enable_http1()
+            .build(),
+    )
+});
+
+/// Returns the value of [`SIGNATURE_HEADER`] for the body
+fn signature(secret: &str, payload: &[u8]) -> String {
+    format!("sha256={}", hex(&hmac_sha256(secret.as_bytes(), payload)))
+}
+
+/// Posts the record as JSON to the URL of [`ErrorHandler::http_endpoint`],
+/// returning the failure note if it failed
+pub(crate) async fn post(config: &Config, record: &ErrorRecord) -> Option<String> {
+    let url = config.http_endpoint.as_ref()?;
+    if config.dry_run {
+        return Some(format!("\n\nDry run, would post to {url}"));
+    }
+
+    let name = format!("HTTP endpoint {url}");
+    let result = delivery::traced(&name, async {
+        let payload = serde_json::to_vec(record).map_err(io::Error::from)?;
+        delivery::retrying(
+            config.retry_policy.as_ref(),
+            || async {
+                time::timeout(
+                    POST_TIMEOUT,
+                    post_once(url, config.http_signing_secret.as_deref(), &payload),
+                )
+                .await
+                .map_err(io::Error::from)?
+            },
+            |err| err.kind() != ErrorKind::InvalidInput,
+        )
+        .await
+    })
+    .await;
+
+    result
+        .err()
+        .map(|err| format!("\n\nFailed to post to the {name}: {err}"))
+}
+
+/// Posts the payload, signed if there's a secret, failing if the endpoint
+/// doesn't respond with a success
+async fn post_once(url: &str, secret: Option<&str>, payload: &[u8]) -> io::Result<()> {
+    let uri = url
+        .parse::<Uri>()
+        .map_err(|err| io::Error::new(ErrorKind::InvalidInput, err))?;
+    let mut request = Request::post(uri).header(header::CONTENT_TYPE, "application/json");
+    if let Some(key) = secret {
+        request = request.header(SIGNATURE_HEADER, signature(key, payload));
+    }
+
+    let response = CLIENT
+        .request(
+            request
+                .body(Body::from(payload.to_vec()))
+                .map_err(io::Error::other)?,
+        )
+        .await
+        .map_err(io::Error::other)?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+
+    let response_body = body::to_bytes(response.into_body())
+        .await
+        .unwrap_or_default();
+    let text = String::from_utf8_lossy(&response_body);
+    let message = if text.trim().is_empty() {
+        format!("the endpoint responded with {status}")
+    } else {
+        format!("the endpoint responded with {status}: {}", text.trim())
+    };
+    // client errors won't succeed on a retry, except for timeouts and rate limits
+    let kind = if status.is_client_error()
+        && status != StatusCode::REQUEST_TIMEOUT
+        && status != StatusCode::TOO_MANY_REQUESTS
+    {
+        ErrorKind::InvalidInput
+    } else {
+        ErrorKind::Other
+    };
+    Err(io::Error::new(kind, message))
+}
+
+impl ErrorHandler {
+    /// Also post errors as JSON records to the URL, such as an internal
+    /// dashboard or an incident tool
+    ///
+    /// They're posted in a spawned task when they're handled inside a Tokio
+    /// runtime, including by [`Self::handle_sync`], set
+    /// [`Self::http_signing_secret`] so that the endpoint can authenticate them
+    pub fn http_endpoint(&mut self, url: impl Into<String>) -> &mut Self {
+        self.config_mut().http_endpoint = Some(url.into());
+        self
+    }
+
+    /// Change the URL errors are posted to at runtime, `None` to stop posting
+    /// them
+    ///
+    /// Same as [`Self::http_endpoint`] but through a shared reference
+    pub fn set_http_endpoint(&self, url: Option<String>) {
+        self.update_config(|config| config.http_endpoint = url);
+    }
+
+    /// Sign the bodies posted to [`Self::http_endpoint`] with the shared
+    /// secret, in the [`SIGNATURE_HEADER`] header
+    ///
+    /// The endpoint can compute the signature of the body it received with
+    /// the secret and compare it in constant time to authenticate the error
+    pub fn http_signing_secret(&mut self, secret: impl Into<String>) -> &mut Self {
+        self.config_mut().http_signing_secret = Some(secret.into());
+        self
+    }
+
+    /// Change the secret the bodies are signed with at runtime, `None` to stop
+    /// signing them
+    ///
+    /// Same as [`Self::http_signing_secret`] but through a shared reference
+    pub fn set_http_signing_secret(&self, secret: Option<String>) {
+        self.update_config(|config| config.http_signing_secret = secret);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_is_hmac_of_the_body() {
+        assert_eq!(
+            signature("secret", br#"{"error":"boom"}"#),
+            "sha256=caf6f90d383205c8838deab4f9d437889da2dd32a29afa67c48b6b6d16936ce6"
+        );
+    }
+}
@@ -0,0 +1,86 @@
+//! Short unique IDs to refer to handled errors with
+
+use core::{
+    fmt,
+    hash::{BuildHasher as _, Hasher as _},
+    sync::atomic::{AtomicU64, Ordering},
+};
+use std::{
+    hash::RandomState,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The characters IDs are encoded with, Crockford's base 32
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// The number of characters in an encoded ID
+const ENCODED_LEN: u32 = 13;
+
+/// The epoch of the timestamps of IDs, 2024-01-01 in milliseconds since the
+/// Unix epoch, like Discord snowflakes have their own epoch
+const ID_EPOCH_MILLIS: u64 = 1_704_067_200_000;
+
+/// The number of random bits at the end of IDs, the timestamp is in the 40
+/// bits before them, which last until 2058
+const RANDOM_BITS: u32 = 24;
+
+/// The largest timestamp of an ID, later ones are clamped to it so that they
+/// don't overflow into losing their top bits
+const MAX_TIMESTAMP: u64 = u64::MAX >> RANDOM_BITS;
+
+/// Incremented for every ID so that IDs made at the same time are different
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// The unique ID of a handled error, included in every output and returned
+/// from [`crate::ErrorHandler::handle`]
+///
+/// It's displayed as 13 characters, such as `18Y19R5J2QQ21`, which sort by
+/// when the error was handled, ask users for it to find the exact report in
+/// the file or channel
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(transparent)
+)]
+pub struct ErrorId(u64);
+
+impl ErrorId {
+    /// Make a new unique ID from the current time and random bits
+    pub(crate) fn new() -> Self {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| {
+                u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+            })
+            .saturating_sub(ID_EPOCH_MILLIS)
+            .min(MAX_TIMESTAMP);
+
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+        let random = hasher.finish();
+
+        Self((millis << RANDOM_BITS) | (random & !(u64::MAX << RANDOM_BITS)))
+    }
+
+    /// Returns the ID as a number
+    #[must_use]
+    pub const fn get(self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for ErrorId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for index in (0..ENCODED_LEN).rev() {
+            let digit = self.0.checked_shr(index.saturating_mul(5)).unwrap_or(0) & 0b1_1111;
+            let character = ALPHABET
+                .get(usize::try_from(digit).unwrap_or_default())
+                .copied()
+                .unwrap_or(b'0');
+            write!(f, "{}", char::from(character))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,316 @@
+//! Grouping critical errors that keep happening into incidents with their
+//! own threads
+
+use core::time::Duration;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use tokio::{
+    task::JoinHandle,
+    time::{self, Instant},
+};
+use twilight_http::{Client, Error as HttpError};
+use twilight_model::{
+    channel::Message as ChannelMessage,
+    id::{marker::ChannelMarker, Id},
+};
+
+use crate::{
+    color, config::Config, formatter::Message, timestamp::format_duration, ErrorHandler,
+    ErrorRecord, Fingerprint,
+};
+
+/// How often the incidents are checked for inactivity
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The maximum number of characters of the error in the name of the thread
+const MAX_THREAD_NAME_ERROR_LEN: usize = 80;
+
+/// An open incident
+#[derive(Clone, Copy, Debug)]
+struct Incident {
+    /// The thread the errors of the incident are created in
+    thread_id: Id<ChannelMarker>,
+    /// When the incident was opened
+    opened_at: Instant,
+    /// When its error last happened
+    last_seen: Instant,
+    /// How many times its error happened
+    occurrences: u64,
+}
+
+/// The open incidents of each fingerprint
+#[derive(Debug, Default)]
+pub(crate) struct Incidents {
+    /// The open incidents of each fingerprint
+    open: Mutex<HashMap<Fingerprint, Incident>>,
+}
+
+impl Incidents {
+    /// Returns the thread of the incident of the message's error if it's
+    /// open, counting the error as an occurrence of it
+    pub(crate) fn thread(
+        &self,
+        config: &Config,
+        message: Message<'_>,
+    ) -> Option<Id<ChannelMarker>> {
+        config.incident_inactivity?;
+        let Message::Record(record) = message else {
+            return None;
+        };
+
+        let mut open = self.open.lock().unwrap_or_else(PoisonError::into_inner);
+        let incident = open.get_mut(&record.fingerprint())?;
+        incident.last_seen = Instant::now();
+        incident.occurrences = incident.occurrences.saturating_add(1);
+        Some(incident.thread_id)
+    }
+
+    /// Removes and returns the incidents whose error didn't happen for the
+    /// duration
+    fn take_inactive(&self, inactive_after: Duration) -> Vec<Incident> {
+        let now = Instant::now();
+        let mut open = self.open.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let mut inactive = Vec::new();
+        open.retain(|_, incident| {
+            let active = now.duration_since(incident.last_seen) < inactive_after;
+            if !active {
+                inactive.push(*incident);
+            }
+            active
+        });
+        inactive
+    }
+}
+
+impl ErrorHandler {
+    /// Opens an incident for the critical error with a thread from its
+    /// created message, returning the note of why it failed
+    pub(crate) async fn open_incident(
+        &self,
+        http: &Client,
+        record: &ErrorRecord,
+        created: &ChannelMessage,
+    ) -> Option<String> {
+        let stripped = color::strip(&record.error);
+        let error = stripped.lines().next().unwrap_or_default();
+        let name = format!(
+            "Incident: {}",
+            error
+                .chars()
+                .take(MAX_THREAD_NAME_ERROR_LEN)
+                .collect::<String>()
+        );
+
+        let created_thread =
+            match http.create_thread_from_message(created.channel_id, created.id, &name) {
+                Ok(request) => request.await,
+                Err(err) => return Some(format!("\n\nFailed to open an incident: {err}")),
+            };
+        let thread_id = match created_thread {
+            Ok(response) => match response.model().await {
+                Ok(thread) => thread.id,
+                Err(err) => return Some(format!("\n\nFailed to read the incident thread: {err}")),
+            },
+            Err(err) => return Some(format!("\n\nFailed to open an incident: {err}")),
+        };
+
+        let now = Instant::now();
+        self.incidents
+            .open
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(record.fingerprint())
+            .or_insert(Incident {
+                thread_id,
+                opened_at: now,
+                last_seen: now,
+                occurrences: 1,
+            });
+        None
+    }
+
+    /// Posts the summary of the incident in its thread
+    async fn post_resolution(
+        http: &Client,
+        incident: Incident,
+        reason: &str,
+    ) -> Result<(), HttpError> {
+        let summary = format!(
+            "Incident resolved {reason}, it lasted {} and the error happened {} times",
+            format_duration(incident.last_seen.duration_since(incident.opened_at)),
+            incident.occurrences
+        );
+
+        #[allow(clippy::unwrap_used)]
+        http.create_message(incident.thread_id)
+            .content(&summary)
+            .unwrap()
+            .await
+            .map(drop)
+    }
+
+    /// Set the handler to open an incident when a critical error is created
+    /// in the channel, creating the later errors with the same fingerprint in
+    /// a thread of its message
+    ///
+    /// Incidents are resolved with [`Self::resolve_incident`] or after their
+    /// error didn't happen for the given duration with
+    /// [`Self::spawn_incident_resolver`], which posts a summary of how long
+    /// it lasted and how many times the error happened in the thread
+    pub fn incident_mode(&mut self, inactive_after: Duration) -> &mut Self {
+        self.config_mut().incident_inactivity = Some(inactive_after);
+        self
+    }
+
+    /// Change how long incidents stay open without errors at runtime, `None`
+    /// to stop opening incidents
+    ///
+    /// Same as [`Self::incident_mode`] but through a shared reference,
+    /// incidents that are open stay open until they're resolved
+    pub fn set_incident_mode(&self, inactive_after: Option<Duration>) {
+        self.update_config(|config| config.incident_inactivity = inactive_after);
+    }
+
+    /// Returns the fingerprints of the errors that have an open incident
+    pub fn open_incidents(&self) -> Vec<Fingerprint> {
+        self.incidents
+            .open
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .keys()
+            .copied()
+            .collect()
+    }
+
+    /// Resolve the incident of the errors with the fingerprint, posting its
+    /// summary in its thread
+    ///
+    /// Nothing is done if the fingerprint doesn't have an open incident
+    ///
+    /// # Errors
+    /// Returns [`HttpError`] if posting the summary fails, the incident is
+    /// resolved either way
+    pub async fn resolve_incident(
+        &self,
+        http: &Client,
+        fingerprint: Fingerprint,
+    ) -> Result<(), HttpError> {
+        let incident = self
+            .incidents
+            .open
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&fingerprint);
+
+        match incident {
+            Some(open) => Self::post_resolution(http, open, "manually").await,
+            None => Ok(()),
+        }
+    }
+
+    /// Spawn a Tokio task that resolves the incidents whose error didn't
+    /// happen for the duration set with [`Self::incident_mode`]
+    ///
+    /// The incidents are checked every 30 seconds, failures to post their
+    /// summary are printed according to [`Self::console_output`]
+    pub fn spawn_incident_resolver(self: Arc<Self>, http: Arc<Client>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = time::interval(CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                let Some(inactive_after) = self.config().incident_inactivity else {
+                    continue;
+                };
+
+                let reason = format!("after {} without errors", format_duration(inactive_after));
+                for incident in self.incidents.take_inactive(inactive_after) {
+                    if let Err(err) = Self::post_resolution(&http, incident, &reason).await {
+                        self.config().print_notice(&format!(
+                            "Failed to post the resolution of the incident {}: {err}",
+                            incident.thread_id
+                        ));
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ErrorId, ErrorReport};
+
+    /// Returns a record of the error
+    fn record(error: &str) -> ErrorRecord {
+        ErrorReport::new(error).into_record(ErrorId::new(), &Config::new(), None, None)
+    }
+
+    /// Returns incidents with an open one for the error in the thread `1`
+    fn open_incident(error: &str) -> Incidents {
+        let incidents = Incidents::default();
+        let now = Instant::now();
+        incidents.open.lock().unwrap().insert(
+            record(error).fingerprint(),
+            Incident {
+                thread_id: Id::new(1),
+                opened_at: now,
+                last_seen: now,
+                occurrences: 1,
+            },
+        );
+        incidents
+    }
+
+    #[test]
+    fn errors_of_the_incident_go_to_its_thread() {
+        let incidents = open_incident("timed out after 5s");
+        let mut config = Config::new();
+        config.incident_inactivity = Some(Duration::from_mins(5));
+
+        let same = record("timed out after 9s");
+        assert_eq!(
+            incidents.thread(&config, Message::Record(&same)),
+            Some(Id::new(1))
+        );
+        assert_eq!(
+            incidents.thread(&config, Message::Record(&record("disk full"))),
+            None
+        );
+        assert_eq!(
+            incidents.thread(&config, Message::Notice("timed out after 5s")),
+            None
+        );
+
+        let open = incidents.open.lock().unwrap();
+        assert_eq!(open[&same.fingerprint()].occurrences, 2);
+    }
+
+    #[test]
+    fn errors_go_to_the_channel_without_incident_mode() {
+        let incidents = open_incident("timed out after 5s");
+
+        let same = record("timed out after 5s");
+        assert_eq!(
+            incidents.thread(&Config::new(), Message::Record(&same)),
+            None
+        );
+    }
+
+    #[test]
+    fn only_inactive_incidents_are_taken() {
+        let incidents = open_incident("timed out after 5s");
+
+        assert!(incidents.take_inactive(Duration::from_mins(5)).is_empty());
+        assert_eq!(incidents.open.lock().unwrap().len(), 1);
+
+        let inactive = incidents.take_inactive(Duration::ZERO);
+        assert_eq!(inactive.len(), 1);
+        assert_eq!(inactive[0].thread_id, Id::new(1));
+        assert!(incidents.open.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,266 @@
+//! Routing `tracing` events through the handler
+
+use core::fmt::{self, Write as _};
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex, PoisonError},
+};
+
+#[cfg(feature = "discord")]
+use tokio::runtime::Handle;
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    Event, Level, Span, Subscriber,
+};
+use tracing_subscriber::{layer::Context, Layer};
+#[cfg(feature = "discord")]
+use twilight_http::Client;
+
+use crate::{ErrorHandler, ErrorReport, Severity};
+
+/// The parents and fields of the open spans, recorded by every [`ErrorLayer`]
+static SPANS: LazyLock<Mutex<HashMap<Id, SpanEntry>>> = LazyLock::new(Mutex::default);
+
+/// The parent and fields of a span
+#[derive(Debug, Default)]
+struct SpanEntry {
+    /// The span this span is in
+    parent: Option<Id>,
+    /// The names and values of the recorded fields
+    fields: Vec<(String, String)>,
+}
+
+impl Visit for SpanEntry {
+    #[allow(clippy::use_debug)]
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.record_str(field, &format!("{value:?}"));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields.retain(|(name, _)| name != field.name());
+        self.fields
+            .push((field.name().to_owned(), value.to_owned()));
+    }
+}
+
+/// Returns the fields of the current span and the spans it's in, inner fields
+/// first
+///
+/// Only the spans recorded by an [`ErrorLayer`] are included
+pub(crate) fn current_span_fields() -> Vec<(String, String)> {
+    let spans = SPANS.lock().unwrap_or_else(PoisonError::into_inner);
+    let mut fields: Vec<(String, String)> = Vec::new();
+    let mut current = Span::current().id();
+
+    while let Some(entry) = current.as_ref().and_then(|id| spans.get(id)) {
+        for (name, value) in &entry.fields {
+            if fields.iter().all(|(existing, _)| existing != name) {
+                fields.push((name.clone(), value.clone()));
+            }
+        }
+        current.clone_from(&entry.parent);
+    }
+
+    fields
+}
+
+/// A [`Layer`] that handles `tracing` events with a handler, made with
+/// [`ErrorHandler::layer`]
+///
+/// Events from this crate are ignored so that failing sinks don't report
+/// themselves
+///
+/// The layer also records the fields of spans, which are attached to the
+/// errors handled in them, such as the fields of `#[instrument]` functions
+#[must_use]
+pub struct ErrorLayer {
+    /// The handler to handle the events with
+    handler: Arc<ErrorHandler>,
+    /// The client to use the Discord sinks with
+    #[cfg(feature = "discord")]
+    http: Arc<Client>,
+    /// The least serious level of events to handle
+    level: Level,
+    /// The prefixes of the targets to handle events from, empty to handle
+    /// every target
+    targets: Vec<String>,
+}
+
+impl ErrorLayer {
+    /// Set the least serious level of events to handle, defaults to
+    /// [`Level::ERROR`]
+    ///
+    /// [`Level::WARN`] and less serious events are handled with
+    /// [`Severity::Warning`], [`Level::ERROR`] events with [`Severity::Error`]
+    pub const fn level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Only handle events whose target starts with one of the given prefixes,
+    /// such as the name of the bot's crate
+    pub fn targets(mut self, targets: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.targets = targets.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Returns whether the event should be handled
+    fn enabled_for(&self, level: Level, target: &str) -> bool {
+        level <= self.level
+            && !target.starts_with(env!("CARGO_CRATE_NAME"))
+            && (self.targets.is_empty()
+                || self
+                    .targets
+                    .iter()
+                    .any(|prefix| target.starts_with(prefix.as_str())))
+    }
+}
+
+impl fmt::Debug for ErrorLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ErrorLayer")
+            .field("level", &self.level)
+            .field("targets", &self.targets)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: Subscriber> Layer<S> for ErrorLayer {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut entry = SpanEntry {
+            parent: attrs.parent().cloned().or_else(|| {
+                attrs
+                    .is_contextual()
+                    .then(|| ctx.current_span().id().cloned())
+                    .flatten()
+            }),
+            fields: Vec::new(),
+        };
+        attrs.record(&mut entry);
+
+        SPANS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(id.clone(), entry);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        if let Some(entry) = SPANS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get_mut(id)
+        {
+            values.record(entry);
+        }
+    }
+
+    fn on_close(&self, id: Id, _ctx: Context<'_, S>) {
+        SPANS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&id);
+    }
+
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let level = *metadata.level();
+        if !self.enabled_for(level, metadata.target()) {
+            return;
+        }
+
+        let mut visitor = EventVisitor::default();
+        event.record(&mut visitor);
+
+        let severity = if level == Level::ERROR {
+            Severity::Error
+        } else {
+            Severity::Warning
+        };
+        let mut report = ErrorReport::new(format_args!(
+            "{}: {}{}",
+            metadata.target(),
+            visitor.message,
+            visitor.fields
+        ))
+        .severity(severity);
+        report = match (metadata.file(), metadata.line()) {
+            (Some(file), Some(line)) => report.location(file, line),
+            _ => report.without_location(),
+        };
+        // the span is captured here since the spawned task isn't in it
+        report = report.fallback_fields(current_span_fields());
+        #[cfg(feature = "tracing-error")]
+        if self.handler.config().capture_span_traces {
+            report = report.span_trace();
+        }
+
+        #[cfg(feature = "discord")]
+        if let Ok(runtime) = Handle::try_current() {
+            let handler = Arc::clone(&self.handler);
+            let http = Arc::clone(&self.http);
+            runtime.spawn(async move { handler.handle_report(&http, report).await });
+            return;
+        }
+
+        self.handler.handle_report_sync(report);
+    }
+}
+
+/// Collects the message and fields of an event
+#[derive(Debug, Default)]
+struct EventVisitor {
+    /// The `message` field
+    message: String,
+    /// The other fields, each starting with a space
+    fields: String,
+}
+
+impl Visit for EventVisitor {
+    #[allow(unused_must_use, clippy::use_debug)]
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            write!(self.message, "{value:?}");
+        } else {
+            write!(self.fields, " {}={value:?}", field.name());
+        }
+    }
+
+    #[allow(unused_must_use)]
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            write!(self.fields, " {}={value}", field.name());
+        }
+    }
+}
+
+impl ErrorHandler {
+    /// Returns a [`Layer`] that handles `tracing` events of at least
+    /// [`Level::ERROR`], so that errors logged anywhere in the bot are
+    /// delivered to the sinks
+    ///
+    /// The events are handled in a spawned task if there's a Tokio runtime,
+    /// otherwise or without the `discord` feature with
+    /// [`Self::handle_report_sync`], in which case the client parameter is
+    /// removed
+    ///
+    /// ```ignore
+    /// tracing_subscriber::registry()
+    ///     .with(Arc::clone(&handler).layer(Arc::clone(&http)))
+    ///     .init();
+    /// ```
+    pub const fn layer(
+        self: Arc<Self>,
+        #[cfg(feature = "discord")] http: Arc<Client>,
+    ) -> ErrorLayer {
+        ErrorLayer {
+            handler: self,
+            #[cfg(feature = "discord")]
+            http,
+            level: Level::ERROR,
+            targets: Vec::new(),
+        }
+    }
+}
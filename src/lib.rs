@@ -7,70 +7,641 @@
     clippy::blanket_clippy_restriction_lints,
     clippy::single_char_lifetime_names,
     clippy::missing_inline_in_public_items,
+    clippy::missing_trait_methods,
+    clippy::multiple_inherent_impl,
     clippy::implicit_return,
-    clippy::pattern_type_mismatch
+    clippy::pattern_type_mismatch,
+    clippy::allow_attributes,
+    clippy::allow_attributes_without_reason,
+    clippy::arbitrary_source_item_ordering,
+    clippy::doc_paragraphs_missing_punctuation,
+    clippy::field_scoped_visibility_modifiers,
+    clippy::impl_trait_in_params,
+    clippy::multiple_crate_versions,
+    clippy::pub_use,
+    clippy::pub_with_shorthand,
+    clippy::question_mark_used,
+    clippy::redundant_pub_crate,
+    clippy::significant_drop_tightening,
+    clippy::separated_literal_suffix,
+    clippy::single_call_fn,
+    clippy::std_instead_of_alloc
 )]
+// the delivery to the sinks is unused if none of them are enabled
+#![cfg_attr(not(any(feature = "discord", feature = "file")), allow(dead_code))]
+#![cfg_attr(test, allow(clippy::indexing_slicing, clippy::unwrap_used))]
 
+mod assertions;
+mod backtrace;
+#[cfg(feature = "discord")]
+mod blocking;
+mod circuit_breaker;
+#[cfg(feature = "discord")]
+mod client;
+#[cfg(feature = "cloudwatch")]
+mod cloudwatch;
+#[cfg(feature = "discord")]
+mod coalesce;
+mod color;
+#[cfg(feature = "discord")]
+mod command;
+#[cfg(feature = "compression")]
+mod compress;
+mod config;
+#[cfg(feature = "serde")]
+mod config_file;
+mod console;
+mod context;
+#[cfg(feature = "discord")]
+mod crash_loop;
+mod delivery;
+mod diagnostics;
+pub mod env;
+#[cfg(feature = "discord")]
+mod escalation;
+#[cfg(feature = "discord")]
+mod expiry;
+#[cfg(feature = "discord")]
+mod fatal;
+#[cfg(feature = "file")]
+mod files;
+mod fingerprint;
+#[cfg(any(
+    feature = "discord",
+    feature = "nats",
+    feature = "cloudwatch",
+    feature = "http-sink"
+))]
+mod flush;
+mod formatter;
+#[cfg(feature = "discord")]
+mod gateway;
+#[cfg(feature = "discord")]
+mod health;
+#[cfg(feature = "discord")]
+mod heartbeat;
+mod history;
+#[cfg(feature = "discord")]
+mod http_error;
+#[cfg(feature = "http-sink")]
+mod http_sink;
+mod id;
+#[cfg(feature = "discord")]
+mod incident;
+#[cfg(feature = "tracing")]
+mod layer;
+#[cfg(feature = "discord")]
+mod locale;
+mod location;
+#[cfg(feature = "discord")]
+mod log_tail;
+#[cfg(feature = "log")]
+mod logger;
+mod metadata;
+mod metrics;
+#[cfg(feature = "nats")]
+mod nats;
+#[cfg(all(feature = "discord", feature = "json"))]
+mod outbox;
+#[cfg(feature = "discord")]
+mod owner;
+mod panic;
+#[cfg(feature = "discord")]
+mod payload;
+#[cfg(feature = "discord")]
+mod permissions;
+#[cfg(feature = "discord")]
+mod pin;
+#[cfg(feature = "discord")]
+mod rate_limit;
+#[cfg(feature = "discord")]
+mod raw_id;
+mod redact;
+#[cfg(all(feature = "discord", any(feature = "toml", feature = "json")))]
+mod reload;
+mod report;
+#[cfg(feature = "discord")]
+mod reporter;
+#[cfg(feature = "discord")]
+mod request;
+#[cfg(any(
+    feature = "discord",
+    feature = "nats",
+    feature = "cloudwatch",
+    feature = "http-sink"
+))]
+mod retry;
+mod scope;
+mod severity;
+#[cfg(feature = "discord")]
+mod shard;
+#[cfg(feature = "discord")]
+mod sharded;
+#[cfg(any(feature = "cloudwatch", feature = "http-sink"))]
+mod signing;
+mod snapshot;
+#[cfg(feature = "tracing-error")]
+mod span_trace;
+mod stats;
+#[cfg(feature = "discord")]
+mod style;
+mod tags;
+#[cfg(feature = "discord")]
+mod task;
+#[cfg(feature = "templates")]
+mod template;
+mod termination;
+mod timestamp;
+#[cfg(feature = "test-util")]
+mod transport;
+#[cfg(feature = "discord")]
+mod truncate;
+#[cfg(feature = "discord")]
+mod twilight_kind;
+mod uptime;
+#[cfg(feature = "discord")]
+mod watchdog;
+#[cfg(feature = "discord")]
+mod webhook;
+
+use core::{
+    fmt::{Display, Write as _},
+    time::Duration,
+};
+#[cfg(feature = "discord")]
+use core::{future::Future, sync::atomic::AtomicBool};
+use std::sync::{Arc, PoisonError, RwLock};
+#[cfg(feature = "file")]
 use std::{
-    fmt::{Display, Write},
     fs::OpenOptions,
-    io::Write as _,
-    path::PathBuf,
+    io::{self, Write as _},
+    path::{Path, PathBuf},
+};
+
+#[cfg(any(feature = "nats", feature = "http-sink"))]
+use tokio::runtime::Handle;
+use tokio::time::Instant;
+#[cfg(feature = "discord")]
+use twilight_http::{response::Response, Client, Error as HttpError};
+#[cfg(feature = "discord")]
+use twilight_model::{
+    channel::Message as ChannelMessage,
+    id::{
+        marker::{ChannelMarker, WebhookMarker},
+        Id,
+    },
 };
 
-use twilight_http::Client;
-use twilight_model::id::{
-    marker::{ChannelMarker, WebhookMarker},
-    Id,
+#[cfg(feature = "cloudwatch")]
+use crate::cloudwatch::CloudWatchBuffer;
+#[cfg(feature = "cloudwatch")]
+pub use crate::cloudwatch::{AwsCredentials, CloudWatchStream};
+#[cfg(any(feature = "toml", feature = "json"))]
+pub use crate::config_file::ConfigFormat;
+#[cfg(feature = "serde")]
+pub use crate::config_file::{CircuitBreakerConfig, ErrorHandlerConfig};
+#[cfg(any(feature = "tracing", feature = "log"))]
+pub use crate::console::CONSOLE_TARGET;
+#[cfg(any(
+    feature = "discord",
+    feature = "nats",
+    feature = "cloudwatch",
+    feature = "http-sink"
+))]
+use crate::flush::Pending;
+#[cfg(feature = "json")]
+pub use crate::formatter::JsonFormatter;
+#[cfg(feature = "http-sink")]
+pub use crate::http_sink::SIGNATURE_HEADER;
+#[cfg(feature = "tracing")]
+pub use crate::layer::ErrorLayer;
+#[cfg(feature = "log")]
+pub use crate::logger::ErrorLogger;
+#[cfg(feature = "nats")]
+pub use crate::nats::NatsAuth;
+#[cfg(all(feature = "discord", feature = "json"))]
+use crate::outbox::Outbox;
+#[cfg(any(
+    feature = "discord",
+    feature = "nats",
+    feature = "cloudwatch",
+    feature = "http-sink"
+))]
+pub use crate::retry::RetryPolicy;
+#[cfg(feature = "templates")]
+pub use crate::template::{Template, TemplateError, TemplateFormatter};
+#[cfg(feature = "test-util")]
+pub use crate::transport::{CaptureSink, Destination, SentMessage, Transport, TransportFuture};
+#[cfg(feature = "discord")]
+pub use crate::{
+    backtrace::BACKTRACE_FILENAME,
+    command::{
+        error_search, errors_command, recent_errors_page, ErrorSearch, BROWSER_CUSTOM_ID_PREFIX,
+        ERRORS_COMMAND_NAME, ERRORS_PER_PAGE, HOURS_OPTION_NAME, PAGE_OPTION_NAME,
+        QUERY_OPTION_NAME, RECENT_SUBCOMMAND_NAME, SEARCH_SUBCOMMAND_NAME, SEVERITY_OPTION_NAME,
+    },
+    context::{AsyncContextProvider, ContextFuture},
+    delivery::DeliverySemantics,
+    health::TEST_MESSAGE,
+    locale::DEFAULT_USER_ERROR_MESSAGE,
+    log_tail::LOG_TAIL_FILENAME,
+    payload::ERROR_FILENAME,
+    permissions::ChannelPermissionsError,
+    raw_id::WebhookSpec,
+    reporter::{QueueError, Reporter},
+    shard::{ShardErrorReporter, CLUSTER_FIELD, SHARD_FIELD},
+    truncate::Truncation,
+    twilight_kind::TwilightErrorKind,
+    webhook::WebhookDetails,
+};
+pub use crate::{
+    circuit_breaker::Sink,
+    config::ConfigError,
+    console::ConsoleOutput,
+    context::ContextProvider,
+    delivery::DeliveryMode,
+    diagnostics::{DiagnosticEvent, DIAGNOSTICS_TARGET},
+    fingerprint::Fingerprint,
+    formatter::{FormattedOutput, Formatter, MarkdownFormatter, PlainFormatter},
+    history::ErrorRecord,
+    id::ErrorId,
+    location::LOCATION_FIELD,
+    metrics::{DropReason, MetricsHook},
+    redact::REDACTED,
+    report::ErrorReport,
+    scope::{ScopedHandler, SCOPE_FIELD},
+    severity::Severity,
+    stats::{SinkStats, Stats},
+    tags::TagRoute,
+    termination::DEFAULT_TERMINATION_LOG,
+    timestamp::{TimestampFormat, Timezone},
+    uptime::UPTIME_FIELD,
+};
+use crate::{
+    circuit_breaker::{CircuitBreaker, Transition},
+    config::Config,
+    delivery::Delivery,
+    formatter::Message,
+    history::History,
+    stats::StatsRecorder,
+};
+#[cfg(feature = "discord")]
+use crate::{
+    coalesce::InFlight,
+    crash_loop::CrashLoops,
+    escalation::Occurrences,
+    expiry::Expiry,
+    gateway::GatewayTracker,
+    http_error::{is_missing_permissions, is_retryable},
+    incident::Incidents,
+    owner::OwnerChannel,
+    payload::Payload,
+    pin::Pins,
+    rate_limit::RateLimits,
+    watchdog::Watchdog,
+    webhook::{execute_additional, WebhookCheck},
 };
 
 /// The main struct to handle errors
+///
+/// The builder methods take `&mut self`, the `set_` and `remove_` methods can
+/// be used to change the configuration through a shared reference, such as
+/// from a command while the bot is running
+///
+/// It's [`Send`] and [`Sync`] with its state behind locks and atomics, so
+/// after building it, wrap it in an [`Arc`] and clone that to share it across
+/// shard tasks, every clone shares the same statistics, history and circuit
+/// breaker
 pub struct ErrorHandler {
-    /// Channel to create message in on error
-    channel: Option<Id<ChannelMarker>>,
-    /// Webhook to execute on error
-    webhook: Option<(Id<WebhookMarker>, String)>,
-    /// File to append to on error
-    file: Option<PathBuf>,
+    /// The configuration that can be changed at runtime
+    config: RwLock<Arc<Config>>,
+    /// Disables sinks that keep failing
+    circuit_breaker: CircuitBreaker,
+    /// Counts the reconnects of shards
+    #[cfg(feature = "discord")]
+    gateway: GatewayTracker,
+    /// Keeps the recently handled errors
+    history: History,
+    /// The cached DM channel of the owner
+    #[cfg(feature = "discord")]
+    owner_channel: OwnerChannel,
+    /// The pinned messages of critical errors
+    #[cfg(feature = "discord")]
+    pins: Pins,
+    /// The messages of non-critical errors to delete
+    #[cfg(feature = "discord")]
+    expiry: Expiry,
+    /// The open incidents of critical errors
+    #[cfg(feature = "discord")]
+    incidents: Incidents,
+    /// The errors that happened since startup and the crash loops among them
+    #[cfg(feature = "discord")]
+    crash_loops: CrashLoops,
+    /// Whether the error rate is above the escalation threshold
+    #[cfg(feature = "discord")]
+    escalated: AtomicBool,
+    /// When the errors with each fingerprint recently happened
+    #[cfg(feature = "discord")]
+    occurrences: Occurrences,
+    /// The errors being delivered to merge identical ones into
+    #[cfg(feature = "discord")]
+    in_flight: InFlight,
+    /// Whether the webhook exists
+    #[cfg(feature = "discord")]
+    webhook_check: WebhookCheck,
+    /// When the rate limits of the sinks end
+    #[cfg(feature = "discord")]
+    rate_limits: RateLimits,
+    /// Stops delivering to Discord for a while after it keeps failing
+    #[cfg(feature = "discord")]
+    watchdog: Watchdog,
+    /// The errors that weren't delivered to Discord yet
+    #[cfg(all(feature = "discord", feature = "json"))]
+    outbox: Outbox,
+    /// The errors being delivered or queued
+    #[cfg(any(
+        feature = "discord",
+        feature = "nats",
+        feature = "cloudwatch",
+        feature = "http-sink"
+    ))]
+    pending: Arc<Pending>,
+    /// The errors waiting to be pushed to AWS
+    #[cfg(feature = "cloudwatch")]
+    cloudwatch: CloudWatchBuffer,
+    /// Counts handled errors and delivery results
+    stats: StatsRecorder,
+    /// When the handler was made
+    started_at: Instant,
+    /// The number of times the application restarted today
+    restart: Option<u32>,
 }
 
 /// The error message to fall back to if the previous error message isn't valid
-/// as a webhook or message content (if it's too long)
+/// as a webhook or message content, nor as the description of an embed
+///
+/// Such as if it's longer than an embed allows with [`Truncation::Fallback`],
+/// the error message is then attached as [`ERROR_FILENAME`]
+#[cfg(feature = "discord")]
 pub const DEFAULT_ERROR_MESSAGE: &str = "An error occurred, check the `stderr` for more info";
 
 impl ErrorHandler {
     /// Make a handler that only prints errors to [`std::io::stderr`]
     #[must_use]
-    pub const fn new() -> Self {
+    pub fn new() -> Self {
         Self {
-            channel: None,
-            webhook: None,
-            file: None,
+            config: RwLock::new(Arc::new(Config::new())),
+            circuit_breaker: CircuitBreaker::new(),
+            #[cfg(feature = "discord")]
+            gateway: GatewayTracker::new(),
+            history: History::new(),
+            #[cfg(feature = "discord")]
+            owner_channel: OwnerChannel::default(),
+            #[cfg(feature = "discord")]
+            pins: Pins::default(),
+            #[cfg(feature = "discord")]
+            expiry: Expiry::default(),
+            #[cfg(feature = "discord")]
+            incidents: Incidents::default(),
+            #[cfg(feature = "discord")]
+            crash_loops: CrashLoops::default(),
+            #[cfg(feature = "discord")]
+            escalated: AtomicBool::new(false),
+            #[cfg(feature = "discord")]
+            occurrences: Occurrences::default(),
+            #[cfg(feature = "discord")]
+            in_flight: InFlight::default(),
+            #[cfg(feature = "discord")]
+            webhook_check: WebhookCheck::default(),
+            #[cfg(feature = "discord")]
+            rate_limits: RateLimits::default(),
+            #[cfg(feature = "discord")]
+            watchdog: Watchdog::new(),
+            #[cfg(all(feature = "discord", feature = "json"))]
+            outbox: Outbox::default(),
+            #[cfg(any(
+                feature = "discord",
+                feature = "nats",
+                feature = "cloudwatch",
+                feature = "http-sink"
+            ))]
+            pending: Arc::default(),
+            #[cfg(feature = "cloudwatch")]
+            cloudwatch: CloudWatchBuffer::default(),
+            stats: StatsRecorder::new(),
+            started_at: Instant::now(),
+            restart: None,
         }
     }
 
     /// Set the handler to create a message in the given channel on errors
     ///
-    /// The channel can also be DM channel, such as the owner's
+    /// The channel can also be DM channel, see [`Self::owner`] to create it
+    /// automatically
+    ///
+    /// If the bot is missing access or permissions to the channel, it isn't
+    /// used until a retry an hour later succeeds, which is noted through the
+    /// other sinks
+    #[cfg(feature = "discord")]
     pub fn channel(&mut self, channel_id: Id<ChannelMarker>) -> &mut Self {
-        self.channel = Some(channel_id);
+        self.config_mut().channel = Some(channel_id);
         self
     }
 
     /// Set the handler to execute the given webhook on errors
+    #[cfg(feature = "discord")]
     pub fn webhook(&mut self, webhook_id: Id<WebhookMarker>, token: String) -> &mut Self {
-        self.webhook = Some((webhook_id, token));
+        self.config_mut().webhook = Some((webhook_id, token));
         self
     }
 
     /// Set the file to append to on error
     ///
     /// The file will be created if it doesn't exist
-    pub fn file(&mut self, path: PathBuf) -> &mut Self {
-        self.file = Some(path);
+    #[cfg(feature = "file")]
+    pub fn file(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        self.config_mut().file = Some(path.into());
+        self
+    }
+
+    /// Set the file to append to on error from a borrowed path
+    ///
+    /// Same as [`Self::file`] but copies the path, for types such as
+    /// [`std::ffi::OsStr`] that don't convert into a [`PathBuf`]
+    #[cfg(feature = "file")]
+    pub fn file_path(&mut self, path: impl AsRef<Path>) -> &mut Self {
+        self.file(path.as_ref())
+    }
+
+    /// Set the severity errors need to be at least to be delivered to the
+    /// sinks
+    ///
+    /// Errors below it are only printed to [`std::io::stderr`], defaults to
+    /// [`Severity::Warning`]
+    pub fn min_severity(&mut self, severity: Severity) -> &mut Self {
+        self.config_mut().min_severity = severity;
         self
     }
 
+    /// Stop using a sink after it fails the given number of times in a row
+    ///
+    /// The disabled sink is retried once every `probe_interval` and is used
+    /// again if that succeeds, both of these changes are reported through the
+    /// other sinks
+    pub fn circuit_breaker(&mut self, threshold: u32, probe_interval: Duration) -> &mut Self {
+        self.circuit_breaker
+            .configure(Some((threshold, probe_interval)));
+        self
+    }
+
+    /// Set how the sinks are used when handling an error
+    ///
+    /// Defaults to [`DeliveryMode::Concurrent`]
+    pub fn delivery_mode(&mut self, mode: DeliveryMode) -> &mut Self {
+        self.config_mut().delivery_mode = mode;
+        self
+    }
+
+    /// Set whether to print the error and append it to the file before
+    /// delivering it to the Discord sinks
+    ///
+    /// The failures of the sinks are then printed and appended to the file
+    /// afterwards as a separate entry, so that a slow or hung sink doesn't
+    /// delay the local output, the file is also appended to first with
+    /// [`DeliveryMode::Fallback`], defaults to `false`
+    pub fn print_before_send(&mut self, print_first: bool) -> &mut Self {
+        self.config_mut().print_before_send = print_first;
+        self
+    }
+
+    /// Set whether to only print what would be sent to the channel and the
+    /// webhook instead of sending it
+    ///
+    /// This is useful for staging environments that use the production
+    /// configuration, the file is still appended to
+    pub fn dry_run(&mut self, dry_run: bool) -> &mut Self {
+        self.config_mut().dry_run = dry_run;
+        self
+    }
+
+    /// Set whether to redact secrets from error messages
+    ///
+    /// This replaces the bot token, the webhook token, tokens in webhook URLs
+    /// and authorization headers with [`REDACTED`] in every output, defaults
+    /// to `true`
+    pub fn redact_secrets(&mut self, redact: bool) -> &mut Self {
+        self.config_mut().redact_secrets = redact;
+        self
+    }
+
+    /// Change the channel to create messages in on errors at runtime
+    ///
+    /// Same as [`Self::channel`] but through a shared reference
+    #[cfg(feature = "discord")]
+    pub fn set_channel(&self, channel_id: Id<ChannelMarker>) {
+        self.update_config(|config| config.channel = Some(channel_id));
+    }
+
+    /// Stop creating messages in the channel on errors
+    #[cfg(feature = "discord")]
+    pub fn remove_channel(&self) {
+        self.update_config(|config| config.channel = None);
+    }
+
+    /// Change the webhook to execute on errors at runtime
+    ///
+    /// Same as [`Self::webhook`] but through a shared reference
+    #[cfg(feature = "discord")]
+    pub fn set_webhook(&self, webhook_id: Id<WebhookMarker>, token: String) {
+        self.update_config(|config| config.webhook = Some((webhook_id, token)));
+    }
+
+    /// Stop executing the webhook on errors
+    #[cfg(feature = "discord")]
+    pub fn remove_webhook(&self) {
+        self.update_config(|config| config.webhook = None);
+    }
+
+    /// Change the file to append to on errors at runtime
+    ///
+    /// Same as [`Self::file`] but through a shared reference
+    #[cfg(feature = "file")]
+    pub fn set_file(&self, path: impl Into<PathBuf>) {
+        let file = path.into();
+        self.update_config(|config| config.file = Some(file));
+    }
+
+    /// Stop appending to the file on errors
+    #[cfg(feature = "file")]
+    pub fn remove_file(&self) {
+        self.update_config(|config| config.file = None);
+    }
+
+    /// Change the minimum severity of errors delivered to the sinks at runtime
+    ///
+    /// Same as [`Self::min_severity`] but through a shared reference
+    pub fn set_min_severity(&self, severity: Severity) {
+        self.update_config(|config| config.min_severity = severity);
+    }
+
+    /// Change the circuit breaker settings at runtime, `None` to never disable
+    /// sinks
+    ///
+    /// Same as [`Self::circuit_breaker`] but through a shared reference, sinks
+    /// that are already disabled stay disabled until they're retried
+    pub fn set_circuit_breaker(&self, settings: Option<(u32, Duration)>) {
+        self.circuit_breaker.configure(settings);
+    }
+
+    /// Change whether to only print what would be sent at runtime
+    ///
+    /// Same as [`Self::dry_run`] but through a shared reference
+    pub fn set_dry_run(&self, dry_run: bool) {
+        self.update_config(|config| config.dry_run = dry_run);
+    }
+
+    /// Change whether to redact secrets from error messages at runtime
+    ///
+    /// Same as [`Self::redact_secrets`] but through a shared reference
+    pub fn set_redact_secrets(&self, redact: bool) {
+        self.update_config(|config| config.redact_secrets = redact);
+    }
+
+    /// Change how the sinks are used at runtime
+    ///
+    /// Same as [`Self::delivery_mode`] but through a shared reference
+    pub fn set_delivery_mode(&self, mode: DeliveryMode) {
+        self.update_config(|config| config.delivery_mode = mode);
+    }
+
+    /// Change whether to print the error before delivering it at runtime
+    ///
+    /// Same as [`Self::print_before_send`] but through a shared reference
+    pub fn set_print_before_send(&self, print_first: bool) {
+        self.update_config(|config| config.print_before_send = print_first);
+    }
+
+    /// Returns the configuration to use while building the handler
+    fn config_mut(&mut self) -> &mut Config {
+        Arc::make_mut(
+            self.config
+                .get_mut()
+                .unwrap_or_else(PoisonError::into_inner),
+        )
+    }
+
+    /// Changes the configuration, errors that are already being handled keep
+    /// using the previous one
+    fn update_config(&self, update: impl FnOnce(&mut Config)) {
+        let mut config = self.config.write().unwrap_or_else(PoisonError::into_inner);
+        update(Arc::make_mut(&mut config));
+    }
+
+    /// Returns the current configuration
+    fn config(&self) -> Arc<Config> {
+        Arc::clone(&self.config.read().unwrap_or_else(PoisonError::into_inner))
+    }
+
     /// Handle an error
     ///
     /// Prefer [`Self::handle_sync`] if [`Self::channel`] or [`Self::webhook`]
@@ -83,94 +654,780 @@ impl ErrorHandler {
     ///   message or [`DEFAULT_ERROR_MESSAGE`]
     /// - If [`Self::file`] was called, appends the error message to the file
     ///
-    /// Note that the fields are not set in a falling back manner, for example,
-    /// if both [`Self::channel`] and [`Self::webhook`] are called, it both
-    /// creates a message and executes the webhook
+    /// Note that the fields are not set in a falling back manner by default,
+    /// for example, if both [`Self::channel`] and [`Self::webhook`] are called,
+    /// it both creates a message and executes the webhook, use
+    /// [`DeliveryMode::Fallback`] to only use the next sink if the previous one
+    /// failed
     ///
-    /// # Panics
-    /// If the fallback message or webhook content is somehow invalid
-    #[allow(clippy::unwrap_used, unused_must_use, clippy::print_stderr)]
-    pub async fn handle(&self, http: &Client, error: impl Display + Send) {
-        let mut error_message = format!("\n\n{error}");
+    /// If [`Self::circuit_breaker`] was called, sinks that are disabled are
+    /// skipped and the sinks being disabled or enabled again is reported as a
+    /// separate error message
+    ///
+    /// The error is handled with [`Severity::Error`], use
+    /// [`Self::handle_with_severity`] to set it
+    ///
+    /// Returns the [`ErrorId`] of the error, which is included in every output
+    #[cfg(feature = "discord")]
+    #[track_caller]
+    pub fn handle<'a>(
+        &'a self,
+        http: &'a Client,
+        error: impl Display + Send,
+    ) -> impl Future<Output = ErrorId> + Send + 'a {
+        self.handle_with_severity(http, Severity::Error, error)
+    }
 
-        self.maybe_create_message(http, &mut error_message).await;
-        self.maybe_execute_webhook(http, &mut error_message).await;
-        self.maybe_append_error(&mut error_message);
+    /// Handle an error with the given severity
+    ///
+    /// Same as [`Self::handle`] but the error is only delivered to the sinks if
+    /// it's at least [`Self::min_severity`]
+    #[cfg(feature = "discord")]
+    #[track_caller]
+    pub fn handle_with_severity<'a>(
+        &'a self,
+        http: &'a Client,
+        severity: Severity,
+        error: impl Display + Send,
+    ) -> impl Future<Output = ErrorId> + Send + 'a {
+        self.handle_report(http, ErrorReport::new(error).severity(severity))
+    }
 
-        eprintln!("{error_message}");
+    /// Handle an error along with information about it
+    ///
+    /// Same as [`Self::handle`] but with the severity and information set in
+    /// the report
+    #[cfg(feature = "discord")]
+    pub async fn handle_report(&self, http: &Client, report: ErrorReport) -> ErrorId {
+        self.handle_report_routed(http, report, &[]).await
+    }
+
+    /// Handle an error along with information about it, also delivering it to
+    /// the given webhooks
+    #[cfg(feature = "discord")]
+    pub(crate) async fn handle_report_routed(
+        &self,
+        http: &Client,
+        mut report: ErrorReport,
+        routes: &[(Id<WebhookMarker>, String)],
+    ) -> ErrorId {
+        let _pending = self.pending.start();
+        let id = ErrorId::new();
+        let config = self.config();
+        report
+            .fields
+            .extend(config.context_providers.async_fields().await);
+        if config.include_uptime {
+            report.fields.push(self.uptime_field());
+        }
+        let record = report.into_record(id, &config, http.token(), Some(self.uptime_field()));
+        self.record_handled(record.severity);
+        self.history.push(record.clone());
+        config.write_termination_log(&record);
+
+        if record.severity < config.min_severity {
+            self.record_dropped(DropReason::BelowMinSeverity);
+            let message = Message::Record(&record);
+            config.print(message, &message.output(&config, None).content);
+            return id;
+        }
+
+        self.forward_record(&config, &record);
+
+        if self.watchdog.degraded()
+            || self.crash_looping(http, &config, &record).await
+            || self.escalated(http, &config).await
+        {
+            self.report_sync(&config, &record);
+            return id;
+        }
+
+        #[cfg(feature = "json")]
+        if self.defer_if_rate_limited(http, &config, &record).await {
+            return id;
+        }
+
+        let delivering = if config.coalesce_identical {
+            let Some(guard) = self.in_flight.start(record.fingerprint()) else {
+                self.record_dropped(DropReason::Coalesced);
+                return id;
+            };
+            Some(guard)
+        } else {
+            None
+        };
+
+        let mut all_routes = routes.to_vec();
+        all_routes.extend(
+            config
+                .tag_routes(&record.tags)
+                .flat_map(|route| route.webhooks.iter().cloned()),
+        );
+        // marked as being delivered so that Self::deliver_outbox doesn't
+        // deliver it again meanwhile
+        #[cfg(feature = "json")]
+        let outbox_guard = self.outbox.start(record.id);
+        #[cfg(feature = "json")]
+        self.push_outbox(&config, &record);
+        #[cfg_attr(not(feature = "json"), allow(unused_variables))]
+        let delivered = self
+            .report(http, &config, Message::Record(&record), &all_routes)
+            .await;
+        #[cfg(feature = "json")]
+        if config.outbox_delivered(&delivered) {
+            self.remove_outbox(&config, record.id);
+        }
+        #[cfg(feature = "json")]
+        drop(outbox_guard);
+        self.report_watchdog_notice(http, &config).await;
+
+        if let Some(guard) = delivering {
+            let merged = guard.finish();
+            if merged > 0 {
+                let notice = format!(
+                    "\n\nThe error {id} happened {merged} more times while it was being \
+                     delivered"
+                );
+                self.report(http, &config, Message::Notice(&notice), &[])
+                    .await;
+            }
+        }
+
+        id
+    }
+
+    /// Delivers the message to the sinks, the escalation webhook if it keeps
+    /// happening and the routed webhooks, prints it along with the failures
+    /// of the sinks, then reports the circuit breaker transitions that
+    /// happened, returning the Discord sinks it was delivered to
+    ///
+    /// With [`Self::print_before_send`], it's printed before delivering and
+    /// the failures are printed and appended to the file afterwards
+    #[cfg(feature = "discord")]
+    #[allow(unused_must_use)]
+    async fn report(
+        &self,
+        http: &Client,
+        config: &Config,
+        message: Message<'_>,
+        routes: &[(Id<WebhookMarker>, String)],
+    ) -> Vec<Sink> {
+        if config.print_before_send {
+            config.print(message, &message.output(config, None).content);
+        }
+
+        let mut notes = String::new();
+        let (mut transitions, delivered) = self.deliver(http, config, message, &mut notes).await;
+        if let Message::Record(record) = message {
+            notes.extend(self.escalate_if_repeated(http, config, record).await);
+        }
+        for webhook in routes {
+            let output = message.output_with_notes(config, Sink::Webhook, "");
+            notes.extend(
+                execute_additional(
+                    http,
+                    config,
+                    webhook,
+                    "routed webhook",
+                    message,
+                    &output,
+                    &output.content,
+                )
+                .await,
+            );
+        }
+
+        if config.print_before_send {
+            if !notes.is_empty() {
+                let follow_up = self.maybe_append_error(config, Message::Notice(&notes), "");
+                transitions.extend(follow_up.transition);
+                config.print_notice(&format!(
+                    "{}{}",
+                    notes.trim_start(),
+                    follow_up.note.unwrap_or_default()
+                ));
+            }
+        } else {
+            config.print(
+                message,
+                &format!("{}{notes}", message.output(config, None).content),
+            );
+        }
+
+        if !transitions.is_empty() {
+            let mut notice = String::new();
+            for transition in transitions {
+                write!(notice, "\n\n{transition}");
+            }
+
+            if config.print_before_send {
+                config.print_notice(notice.trim_start());
+            }
+
+            let mut notice_notes = String::new();
+            let (notice_transitions, _) = self
+                .deliver(http, config, Message::Notice(&notice), &mut notice_notes)
+                .await;
+            for transition in notice_transitions {
+                write!(notice_notes, "\n\n{transition}");
+            }
+
+            if config.print_before_send {
+                if !notice_notes.is_empty() {
+                    config.print_notice(notice_notes.trim_start());
+                }
+            } else {
+                config.print_notice(&format!("{notice}{notice_notes}"));
+            }
+        }
+
+        delivered
     }
 
     /// Handle an error, ignoring [`Self::channel`] and [`Self::webhook`]
     ///
-    /// Prefer this if you've only set [`Self::file`]
-    #[allow(clippy::print_stderr)]
-    pub fn handle_sync(&self, error: impl Display) {
-        let mut error_message = format!("\n\n{error}");
-
-        self.maybe_append_error(&mut error_message);
-
-        eprintln!("{error_message}");
-    }
-
-    /// Tries to create a message with the given error message or
-    /// [`DEFAULT_ERROR_MESSAGE`], writing the returned error to the error
-    /// message
-    #[allow(unused_must_use, clippy::unwrap_used)]
-    async fn maybe_create_message(&self, http: &Client, error_message: &mut String) {
-        if let Some(channel_id) = self.channel {
-            if let Err(err) = http
-                .create_message(channel_id)
-                .content(error_message)
-                .unwrap_or_else(|_| {
-                    {
-                        http.create_message(channel_id)
-                            .content(DEFAULT_ERROR_MESSAGE)
-                    }
-                    .unwrap()
-                })
-                .exec()
-                .await
-            {
-                write!(error_message, "\n\nFailed to create message: {err}");
+    /// Prefer this if you've only set [`Self::file`], use
+    /// `Self::handle_blocking` to also deliver to the Discord sinks from code
+    /// that isn't async
+    #[track_caller]
+    pub fn handle_sync(&self, error: impl Display) -> ErrorId {
+        self.handle_sync_with_severity(Severity::Error, error)
+    }
+
+    /// Handle an error with the given severity, ignoring [`Self::channel`] and
+    /// [`Self::webhook`]
+    ///
+    /// Same as [`Self::handle_sync`] but the error is only appended to the
+    /// file if it's at least [`Self::min_severity`]
+    #[track_caller]
+    pub fn handle_sync_with_severity(&self, severity: Severity, error: impl Display) -> ErrorId {
+        self.handle_report_sync(ErrorReport::new(error).severity(severity))
+    }
+
+    /// Handle an error along with information about it, ignoring
+    /// [`Self::channel`] and [`Self::webhook`]
+    ///
+    /// Same as [`Self::handle_sync`] but with the severity and information set
+    /// in the report
+    pub fn handle_report_sync(&self, mut report: ErrorReport) -> ErrorId {
+        let id = ErrorId::new();
+        let config = self.config();
+        if config.include_uptime {
+            report.fields.push(self.uptime_field());
+        }
+        let record = report.into_record(id, &config, None, Some(self.uptime_field()));
+        self.record_handled(record.severity);
+        self.history.push(record.clone());
+        config.write_termination_log(&record);
+
+        if record.severity < config.min_severity {
+            self.record_dropped(DropReason::BelowMinSeverity);
+            let message = Message::Record(&record);
+            config.print(message, &message.output(&config, None).content);
+            return id;
+        }
+
+        self.forward_record(&config, &record);
+
+        self.report_sync(&config, &record);
+
+        id
+    }
+
+    /// Forwards the record to the sinks that don't depend on the Discord
+    /// sinks, buffering it for the AWS log stream and publishing it to NATS
+    /// and the HTTP endpoint
+    #[cfg_attr(
+        not(any(feature = "cloudwatch", feature = "nats", feature = "http-sink")),
+        allow(unused_variables, clippy::unused_self, clippy::missing_const_for_fn)
+    )]
+    fn forward_record(&self, config: &Arc<Config>, record: &ErrorRecord) {
+        #[cfg(feature = "cloudwatch")]
+        config.buffer_cloudwatch(&self.cloudwatch, record);
+        #[cfg(any(feature = "nats", feature = "http-sink"))]
+        self.spawn_publish(config, record);
+    }
+
+    /// Publishes the record in a task spawned on the current Tokio runtime,
+    /// printing the failures, the task is waited for by [`Self::flush`]
+    #[cfg(any(feature = "nats", feature = "http-sink"))]
+    fn spawn_publish(&self, config: &Arc<Config>, record: &ErrorRecord) {
+        #[cfg(feature = "nats")]
+        let nats = config.nats.is_some();
+        #[cfg(not(feature = "nats"))]
+        let nats = false;
+        #[cfg(feature = "http-sink")]
+        let http_endpoint = config.http_endpoint.is_some();
+        #[cfg(not(feature = "http-sink"))]
+        let http_endpoint = false;
+        if !nats && !http_endpoint {
+            return;
+        }
+        let Ok(runtime) = Handle::try_current() else {
+            config.print_notice(&format!(
+                "Failed to publish the error {} outside of a Tokio runtime",
+                record.id
+            ));
+            return;
+        };
+
+        let publish_config = Arc::clone(config);
+        let publish_record = record.clone();
+        let pending = Arc::clone(&self.pending);
+        pending.add();
+        runtime.spawn(async move {
+            let mut notes = String::new();
+            #[cfg(feature = "nats")]
+            notes.extend(nats::publish(&publish_config, &publish_record).await);
+            #[cfg(feature = "http-sink")]
+            notes.extend(http_sink::post(&publish_config, &publish_record).await);
+            if !notes.is_empty() {
+                publish_config.print_notice(notes.trim_start());
             }
+            pending.done();
+        });
+    }
+
+    /// Appends the record to the file and prints it along with the failure of
+    /// the file and the circuit breaker transition that happened
+    #[allow(unused_must_use)]
+    fn report_sync(&self, config: &Config, record: &ErrorRecord) {
+        let message = Message::Record(record);
+        let mut error_message = message.output(config, None).content;
+
+        let delivery = self.maybe_append_error(config, message, "");
+        error_message.extend(delivery.note);
+        if let Some(transition) = delivery.transition {
+            write!(error_message, "\n\n{transition}");
         }
+
+        config.print(message, &error_message);
     }
 
-    /// Tries to execute the webhook with the given error message or
-    /// [`DEFAULT_ERROR_MESSAGE`], writing the returned error to the error
-    /// message
-    #[allow(unused_must_use, clippy::unwrap_used)]
-    async fn maybe_execute_webhook(&self, http: &Client, error_message: &mut String) {
-        if let Some((webhook_id, token)) = &self.webhook {
-            if let Err(err) = http
-                .execute_webhook(*webhook_id, token)
-                .content(error_message)
-                .unwrap_or_else(|_| {
-                    http.execute_webhook(*webhook_id, token)
-                        .content(DEFAULT_ERROR_MESSAGE)
-                        .unwrap()
-                })
-                .exec()
-                .await
-            {
-                write!(error_message, "\n\nFailed to execute webhook: {err}");
+    /// Delivers the message to every sink according to the delivery mode,
+    /// appending their failures to the notes and returning the circuit breaker
+    /// transitions that happened and the Discord sinks it was delivered to
+    #[cfg(feature = "discord")]
+    async fn deliver(
+        &self,
+        http: &Client,
+        config: &Config,
+        message: Message<'_>,
+        notes: &mut String,
+    ) -> (Vec<Transition>, Vec<Sink>) {
+        let mut transitions = Vec::new();
+        if config.print_before_send {
+            let mut file = self.maybe_append_error(config, message, "");
+            notes.extend(file.note.take());
+            transitions.extend(file.transition);
+        }
+        let append = |previous_notes: &str| {
+            if config.print_before_send {
+                Delivery::default()
+            } else {
+                self.maybe_append_error(config, message, previous_notes)
+            }
+        };
+
+        let deliveries: [Delivery; 3] = match config.delivery_mode {
+            DeliveryMode::Concurrent => tokio::join!(
+                self.maybe_create_message(http, config, message, ""),
+                self.maybe_execute_webhook(http, config, message, ""),
+                async { append("") }
+            )
+            .into(),
+            DeliveryMode::Sequential => {
+                let mut channel = self
+                    .maybe_create_message(http, config, message, notes)
+                    .await;
+                notes.extend(channel.note.take());
+                let mut webhook = self
+                    .maybe_execute_webhook(http, config, message, notes)
+                    .await;
+                notes.extend(webhook.note.take());
+                let mut file = append(notes);
+                notes.extend(file.note.take());
+                [channel, webhook, file]
+            }
+            DeliveryMode::Fallback => {
+                let mut webhook = self
+                    .maybe_execute_webhook(http, config, message, notes)
+                    .await;
+                notes.extend(webhook.note.take());
+                if webhook.delivered {
+                    transitions.extend(webhook.transition);
+                    return (transitions, vec![Sink::Webhook]);
+                }
+
+                let mut channel = self
+                    .maybe_create_message(http, config, message, notes)
+                    .await;
+                notes.extend(channel.note.take());
+                if channel.delivered {
+                    transitions.extend(
+                        [webhook, channel]
+                            .into_iter()
+                            .filter_map(|delivery| delivery.transition),
+                    );
+                    return (transitions, vec![Sink::Channel]);
+                }
+
+                let mut file = append(notes);
+                notes.extend(file.note.take());
+                [webhook, channel, file]
             }
+        };
+
+        // the Discord sinks are only delivered to here in this order, since
+        // the fallback mode returns above if they are
+        let delivered = [Sink::Channel, Sink::Webhook]
+            .into_iter()
+            .zip(&deliveries)
+            .filter_map(|(sink, delivery)| delivery.delivered.then_some(sink))
+            .collect();
+        transitions.extend(deliveries.into_iter().filter_map(|delivery| {
+            notes.extend(delivery.note);
+            delivery.transition
+        }));
+        (transitions, delivered)
+    }
+
+    /// Records the result of delivering to the sink, returning the circuit
+    /// breaker transition it caused
+    fn record_result(&self, sink: Sink, succeeded: bool) -> Option<Transition> {
+        self.record_sink(sink, succeeded);
+        #[cfg(feature = "discord")]
+        if sink != Sink::File {
+            self.watchdog.record(succeeded);
         }
+        let transition = self.circuit_breaker.record(sink, succeeded);
+        if let Some(changed) = transition {
+            self.diagnose(&changed.into());
+        }
+        transition
     }
 
-    /// Tries to append the given error message to the path, writing the
-    /// returned error to the error message
-    #[allow(unused_must_use)]
-    fn maybe_append_error(&self, error_message: &mut String) {
-        if let Some(path) = &self.file {
-            if let Err(err) = OpenOptions::new()
-                .append(true)
-                .create(true)
-                .open(path)
-                .and_then(|mut file| file.write_all(error_message.as_ref()))
-            {
-                write!(error_message, "\n\nFailed to append to file: {err}");
+    /// Tries to create a message with the output of the channel's formatter
+    /// followed by the notes, shortened with [`Self::truncation`], attaching
+    /// the backtrace and the end of the log file
+    #[cfg(feature = "discord")]
+    async fn maybe_create_message(
+        &self,
+        http: &Client,
+        config: &Config,
+        message: Message<'_>,
+        notes: &str,
+    ) -> Delivery {
+        if config.dry_run {
+            return match (config.channel, config.owner) {
+                (Some(channel_id), _) => {
+                    Delivery::dry_run(&format!("create a message in the channel {channel_id}"))
+                }
+                (None, Some(owner_id)) => {
+                    Delivery::dry_run(&format!("create a message in the DMs of {owner_id}"))
+                }
+                (None, None) => Delivery::default(),
+            };
+        }
+        if !self.circuit_breaker.allows(Sink::Channel) {
+            return Delivery::default();
+        }
+        #[cfg(feature = "test-util")]
+        if let Some(transport) = &config.transport {
+            return self
+                .send_to_transport(&**transport, config, message, notes, Sink::Channel)
+                .await;
+        }
+
+        let channel_id = match self.resolve_channel(http, config).await {
+            Some(Ok(channel_id)) => channel_id,
+            Some(Err(err)) => {
+                let transition = self.record_result(Sink::Channel, false);
+                return Delivery::new(&Err::<(), _>(err), "create the DM channel", transition)
+                    .redacted(config, http.token());
+            }
+            None => return Delivery::default(),
+        };
+        let thread_id = self.incidents.thread(config, message);
+
+        let output = message.output_with_notes(config, Sink::Channel, notes);
+        let payload = Payload::new(
+            config,
+            &output.content,
+            &output.embeds,
+            payload::attachments(config, message),
+        );
+        let result = delivery::traced(
+            Sink::Channel,
+            delivery::retrying(
+                config.sink_retry_policy(Sink::Channel).as_deref(),
+                || {
+                    self.send_rate_limited(Sink::Channel, || {
+                        create_message(
+                            http,
+                            thread_id.unwrap_or(channel_id),
+                            &payload,
+                            message.id(),
+                        )
+                    })
+                },
+                is_retryable,
+            ),
+        )
+        .await;
+
+        let transition = if result.as_ref().is_err_and(is_missing_permissions) {
+            self.record_sink(Sink::Channel, false);
+            let transition = self
+                .circuit_breaker
+                .record_missing_permissions(Sink::Channel);
+            if let Some(changed) = transition {
+                self.diagnose(&changed.into());
+            }
+            transition
+        } else {
+            self.record_result(Sink::Channel, result.is_ok())
+        };
+        let mut delivery = Delivery::new(&result, "create message", transition);
+        if let Ok(response) = result {
+            delivery.note = self
+                .track_created(http, config, message, response, thread_id.is_none())
+                .await;
+        }
+        delivery.redacted(config, http.token())
+    }
+
+    /// Pins the created message if it's of a critical error and
+    /// [`Self::pin_critical`] is set, or schedules its deletion with
+    /// [`Self::delete_non_critical_after`] otherwise, and opens an incident
+    /// for it with [`Self::incident_mode`] if it can, returning the notes of
+    /// why that failed
+    #[cfg(feature = "discord")]
+    async fn track_created(
+        &self,
+        http: &Client,
+        config: &Config,
+        message: Message<'_>,
+        response: Response<ChannelMessage>,
+        can_open_incident: bool,
+    ) -> Option<String> {
+        let Message::Record(record) = message else {
+            return None;
+        };
+        let critical = record.severity == Severity::Critical;
+        let pin = critical && config.pin_critical;
+        let delete_after = config.delete_non_critical_after.filter(|_| !critical);
+        let open_incident = critical && can_open_incident && config.incident_inactivity.is_some();
+        if !pin && delete_after.is_none() && !open_incident {
+            return None;
+        }
+
+        let created = match response.model().await {
+            Ok(created) => created,
+            Err(err) => return Some(format!("\n\nFailed to read the created message: {err}")),
+        };
+        if let Some(after) = delete_after {
+            self.expiry.push(after, created.channel_id, created.id);
+        }
+        let mut notes = if pin {
+            self.pin(http, record, &created).await
+        } else {
+            None
+        };
+        if open_incident {
+            if let Some(note) = self.open_incident(http, record, &created).await {
+                notes.get_or_insert_default().push_str(&note);
             }
         }
+        notes
+    }
+
+    /// Tries to execute the webhook with the output of the webhook's formatter
+    /// followed by the notes, shortened with [`Self::truncation`], attaching
+    /// the backtrace and the end of the log file
+    #[cfg(feature = "discord")]
+    async fn maybe_execute_webhook(
+        &self,
+        http: &Client,
+        config: &Config,
+        message: Message<'_>,
+        notes: &str,
+    ) -> Delivery {
+        let Some((webhook_id, token)) = &config.webhook else {
+            return Delivery::default();
+        };
+        if config.dry_run {
+            return Delivery::dry_run(&format!("execute the webhook {webhook_id}"));
+        }
+        if !self.circuit_breaker.allows(Sink::Webhook) {
+            return Delivery::default();
+        }
+        #[cfg(feature = "test-util")]
+        if let Some(transport) = &config.transport {
+            return self
+                .send_to_transport(&**transport, config, message, notes, Sink::Webhook)
+                .await;
+        }
+
+        let action = match self.webhook_check.get(http, *webhook_id, token).await {
+            Some(Ok(details)) => format!("execute webhook in {details}"),
+            Some(Err(err)) => {
+                let transition = self.record_result(Sink::Webhook, false);
+                return Delivery::new(&Err::<(), _>(err), "validate webhook", transition)
+                    .redacted(config, http.token());
+            }
+            None => "execute webhook".to_owned(),
+        };
+        let output = message.output_with_notes(config, Sink::Webhook, notes);
+        let payload = Payload::new(
+            config,
+            &output.content,
+            &output.embeds,
+            payload::attachments(config, message),
+        )
+        .webhook_identity(config, message);
+        let result = delivery::traced(
+            Sink::Webhook,
+            delivery::retrying(
+                config.sink_retry_policy(Sink::Webhook).as_deref(),
+                || {
+                    self.send_rate_limited(Sink::Webhook, || {
+                        execute_webhook(http, *webhook_id, token, &payload)
+                    })
+                },
+                is_retryable,
+            ),
+        )
+        .await;
+
+        let transition = self.record_result(Sink::Webhook, result.is_ok());
+        Delivery::new(&result, &action, transition).redacted(config, http.token())
+    }
+
+    /// Tries to append the message followed by the notes to the file and the
+    /// additional files
+    #[cfg(feature = "file")]
+    fn maybe_append_error(&self, config: &Config, message: Message<'_>, notes: &str) -> Delivery {
+        self.maybe_append_file(config, message, notes)
+            .and(append_additional(config, message, notes))
+    }
+
+    /// Tries to append the output of the file's formatter followed by the
+    /// notes to the path
+    #[cfg(feature = "file")]
+    fn maybe_append_file(&self, config: &Config, message: Message<'_>, notes: &str) -> Delivery {
+        let Some(path) = &config.file else {
+            return Delivery::default();
+        };
+        if !self.circuit_breaker.allows(Sink::File) {
+            return Delivery::default();
+        }
+
+        let output = message.output_with_notes(config, Sink::File, notes);
+        let result = delivery::traced_sync(Sink::File, || append_error(path, &output.content));
+
+        let transition = self.record_result(Sink::File, result.is_ok());
+        Delivery::new(&result, "append to file", transition).redacted(config, None)
     }
+
+    /// Doesn't deliver anything since the file sink isn't enabled
+    #[cfg(not(feature = "file"))]
+    #[allow(clippy::unused_self)]
+    fn maybe_append_error(&self, _: &Config, _: Message<'_>, _: &str) -> Delivery {
+        Delivery::default()
+    }
+}
+
+impl Default for ErrorHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Creates a message in the channel with the payload
+#[cfg(feature = "discord")]
+#[allow(clippy::unwrap_used)]
+async fn create_message(
+    http: &Client,
+    channel_id: Id<ChannelMarker>,
+    payload: &Payload<'_>,
+    id: Option<ErrorId>,
+) -> Result<Response<ChannelMessage>, HttpError> {
+    // the payload is validated already
+    let request = http
+        .create_message(channel_id)
+        .content(&payload.content)
+        .unwrap()
+        .embeds(&payload.embeds)
+        .unwrap()
+        .attachments(&payload.attachments)
+        .unwrap();
+
+    match id {
+        Some(error_id) => request.nonce(error_id.get()).await,
+        None => request.await,
+    }
+}
+
+/// Executes the webhook with the payload
+#[cfg(feature = "discord")]
+#[allow(clippy::unwrap_used)]
+async fn execute_webhook(
+    http: &Client,
+    webhook_id: Id<WebhookMarker>,
+    token: &str,
+    payload: &Payload<'_>,
+) -> Result<(), HttpError> {
+    // the payload is validated already
+    let mut request = http
+        .execute_webhook(webhook_id, token)
+        .content(&payload.content)
+        .unwrap()
+        .embeds(&payload.embeds)
+        .unwrap()
+        .attachments(&payload.attachments)
+        .unwrap();
+    if let Some(username) = payload.username {
+        request = request.username(username).unwrap();
+    }
+    if let Some(avatar_url) = payload.avatar_url {
+        request = request.avatar_url(avatar_url);
+    }
+    request.await.map(drop)
+}
+
+/// Appends the error message to the file, creating it if it doesn't exist
+#[cfg(feature = "file")]
+fn append_error(path: &Path, error_message: &str) -> Result<(), io::Error> {
+    OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)?
+        .write_all(error_message.as_ref())
+}
+
+/// Tries to append the message followed by the notes to the additional files
+/// whose severity it's at least
+#[cfg(feature = "file")]
+fn append_additional(config: &Config, message: Message<'_>, notes: &str) -> Delivery {
+    let mut delivery = Delivery::default();
+
+    for file in &config.additional_files {
+        if message
+            .severity()
+            .is_some_and(|severity| severity < file.min_severity)
+        {
+            continue;
+        }
+
+        let mut output = message.file_output(config, file.formatter.as_deref());
+        output.content.push_str(notes);
+        let result = delivery::traced_sync(file.path.display(), || {
+            append_error(&file.path, &output.content)
+        });
+        let action = format!("append to {}", file.path.display());
+        delivery = delivery.and(Delivery::new(&result, &action, None).redacted(config, None));
+    }
+
+    delivery
 }
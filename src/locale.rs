@@ -0,0 +1,134 @@
+//! Telling users that something went wrong in their language
+
+use twilight_model::{
+    channel::message::MessageFlags,
+    http::interaction::{InteractionResponse, InteractionResponseData, InteractionResponseType},
+};
+
+use crate::{ErrorHandler, ErrorId};
+
+/// The message of [`ErrorHandler::user_error_response`] for the locales
+/// without one, `{id}` is replaced with the ID of the error
+pub const DEFAULT_USER_ERROR_MESSAGE: &str =
+    "Something went wrong, please try again later (error `{id}`)";
+
+/// Replaced with the ID of the error in the messages
+#[allow(clippy::literal_string_with_formatting_args)]
+const ID_PLACEHOLDER: &str = "{id}";
+
+/// The locale whose message is used for the locales without one
+const FALLBACK_LOCALE: &str = "en-US";
+
+/// The messages shown to users when handling their interaction failed, by
+/// Discord locale
+#[derive(Clone, Debug, Default)]
+pub(crate) struct UserMessages(Vec<(String, String)>);
+
+impl UserMessages {
+    /// Returns an empty table
+    pub(crate) const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Sets or removes the message of the locale
+    fn set(&mut self, locale: String, message: Option<String>) {
+        self.0
+            .retain(|(other, _)| !other.eq_ignore_ascii_case(&locale));
+        if let Some(new_message) = message {
+            self.0.push((locale, new_message));
+        }
+    }
+
+    /// Returns the message of the locale, the one of its language such as
+    /// `es-ES` for `es-419`, the one of `en-US` or the default one in order
+    fn get(&self, locale: Option<&str>) -> &str {
+        let exact = |wanted: &str| {
+            self.0
+                .iter()
+                .find(|(other, _)| other.eq_ignore_ascii_case(wanted))
+        };
+        let same_language = |wanted: &str| {
+            self.0
+                .iter()
+                .find(|(other, _)| language(other).eq_ignore_ascii_case(language(wanted)))
+        };
+
+        locale
+            .and_then(|wanted| exact(wanted).or_else(|| same_language(wanted)))
+            .or_else(|| exact(FALLBACK_LOCALE))
+            .map_or(DEFAULT_USER_ERROR_MESSAGE, |(_, message)| message)
+    }
+}
+
+/// Returns the language of the locale, such as `pt` for `pt-BR`
+fn language(locale: &str) -> &str {
+    locale.split('-').next().unwrap_or(locale)
+}
+
+impl ErrorHandler {
+    /// Set the message shown to users with the Discord locale, such as `de`
+    /// or `pt-BR`, by [`Self::user_error_response`], `{id}` is replaced with
+    /// the ID of the error
+    ///
+    /// Locales without a message use the one of the same language, then the
+    /// one of `en-US`, then [`DEFAULT_USER_ERROR_MESSAGE`], the messages sent
+    /// to the sinks aren't translated
+    pub fn user_error_message(
+        &mut self,
+        locale: impl Into<String>,
+        message: impl Into<String>,
+    ) -> &mut Self {
+        self.config_mut()
+            .user_messages
+            .set(locale.into(), Some(message.into()));
+        self
+    }
+
+    /// Change the message shown to users with the locale at runtime, `None`
+    /// to remove it
+    ///
+    /// Same as [`Self::user_error_message`] but through a shared reference
+    pub fn set_user_error_message(&self, locale: impl Into<String>, message: Option<String>) {
+        let new_locale = locale.into();
+        self.update_config(|config| config.user_messages.set(new_locale, message));
+    }
+
+    /// Returns the message shown to users with the locale for the error, see
+    /// [`Self::user_error_message`]
+    #[must_use]
+    pub fn user_error_text(&self, locale: Option<&str>, id: ErrorId) -> String {
+        self.config()
+            .user_messages
+            .get(locale)
+            .replace(ID_PLACEHOLDER, &id.to_string())
+    }
+
+    /// Returns an ephemeral response telling the user that something went
+    /// wrong in their language, after handling the error of their interaction
+    ///
+    /// ```ignore
+    /// let id = handler.handle(&http, error).await;
+    /// let response = handler.user_error_response(interaction.locale.as_deref(), id);
+    /// interaction_client
+    ///     .create_response(interaction.id, &interaction.token, &response)
+    ///     .await?;
+    /// ```
+    #[must_use]
+    pub fn user_error_response(&self, locale: Option<&str>, id: ErrorId) -> InteractionResponse {
+        InteractionResponse {
+            kind: InteractionResponseType::ChannelMessageWithSource,
+            data: Some(InteractionResponseData {
+                allowed_mentions: None,
+                attachments: None,
+                choices: None,
+                components: None,
+                content: Some(self.user_error_text(locale, id)),
+                custom_id: None,
+                embeds: None,
+                flags: Some(MessageFlags::EPHEMERAL),
+                title: None,
+                tts: None,
+            }),
+        }
+    }
+}
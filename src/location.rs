@@ -0,0 +1,53 @@
+//! Attaching where errors were reported from
+
+use core::panic::Location;
+
+use crate::{ErrorHandler, ErrorReport};
+
+/// The name of the field the location of where the error was reported from is
+/// attached as
+pub const LOCATION_FIELD: &str = "Location";
+
+/// Returns the location formatted as `file:line`
+pub(crate) fn format(location: &Location<'_>) -> String {
+    format!("{}:{}", location.file(), location.line())
+}
+
+impl ErrorReport {
+    /// Set where the error was reported from, such as the location of a log
+    /// record
+    ///
+    /// Reports are made with the location of where they're made, or where
+    /// the handle method was called for the methods that take the error
+    /// itself, such as [`ErrorHandler::handle_sync`]
+    pub fn location(mut self, file: &str, line: u32) -> Self {
+        self.location = Some(format!("{file}:{line}"));
+        self
+    }
+
+    /// Remove the location of the report, for the reports made by the handler
+    /// itself whose location wouldn't be useful
+    pub(crate) fn without_location(mut self) -> Self {
+        self.location = None;
+        self
+    }
+}
+
+impl ErrorHandler {
+    /// Set whether to attach where errors were reported from as
+    /// [`LOCATION_FIELD`], in the form of `file:line`
+    ///
+    /// This tells generic errors such as `connection closed` apart by the
+    /// place they were handled at, defaults to `true`
+    pub fn include_location(&mut self, include: bool) -> &mut Self {
+        self.config_mut().include_location = include;
+        self
+    }
+
+    /// Change whether to attach where errors were reported from at runtime
+    ///
+    /// Same as [`Self::include_location`] but through a shared reference
+    pub fn set_include_location(&self, include: bool) {
+        self.update_config(|config| config.include_location = include);
+    }
+}
@@ -0,0 +1,82 @@
+//! Attaching the end of the application's log file to critical errors
+
+use std::{
+    fs::File,
+    io::{self, Read as _, Seek as _, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use twilight_model::http::attachment::Attachment;
+
+use crate::{config::Config, formatter::Message, ErrorHandler, Severity};
+
+/// The name of the file the end of the log file is attached to Discord
+/// messages as
+pub const LOG_TAIL_FILENAME: &str = "log.txt";
+
+/// The ID of the attachment of the log file, after the backtrace's and the
+/// error message's
+const LOG_TAIL_ATTACHMENT_ID: u64 = 2;
+
+/// Returns the attachment of the end of the log file if the message is of a
+/// critical error and [`ErrorHandler::attach_log_tail`] is set
+///
+/// If reading the log file fails, the failure is attached instead
+pub(crate) fn attachment(config: &Config, message: Message<'_>) -> Option<Attachment> {
+    let (path, bytes) = config.log_tail.as_ref()?;
+    let Message::Record(record) = message else {
+        return None;
+    };
+    if record.severity != Severity::Critical {
+        return None;
+    }
+
+    let tail = read_tail(path, *bytes)
+        .unwrap_or_else(|err| format!("Failed to read the log file `{}`: {err}", path.display()));
+    Some(Attachment::from_bytes(
+        LOG_TAIL_FILENAME.to_owned(),
+        tail.into_bytes(),
+        LOG_TAIL_ATTACHMENT_ID,
+    ))
+}
+
+/// Returns the last bytes of the file, starting at the first full line if the
+/// file is longer
+fn read_tail(path: &Path, bytes: u64) -> Result<String, io::Error> {
+    let mut file = File::open(path)?;
+    let start = file.metadata()?.len().saturating_sub(bytes);
+    file.seek(SeekFrom::Start(start))?;
+
+    let mut bytes_read = Vec::new();
+    file.take(bytes).read_to_end(&mut bytes_read)?;
+    let tail = String::from_utf8_lossy(&bytes_read);
+
+    if start == 0 {
+        return Ok(tail.into_owned());
+    }
+    Ok(tail
+        .split_once('\n')
+        .map_or(&*tail, |(_, lines)| lines)
+        .to_owned())
+}
+
+impl ErrorHandler {
+    /// Set the handler to attach the last given number of bytes of the log
+    /// file to the Discord messages of [`Severity::Critical`] errors
+    ///
+    /// The end of the log file is attached as [`LOG_TAIL_FILENAME`] starting
+    /// at its first full line, so responders have context without accessing
+    /// the server
+    pub fn attach_log_tail(&mut self, path: impl Into<PathBuf>, bytes: u64) -> &mut Self {
+        self.config_mut().log_tail = Some((path.into(), bytes));
+        self
+    }
+
+    /// Change the log file attached to critical errors and how much of it is
+    /// attached at runtime, `None` to stop attaching it
+    ///
+    /// Same as [`Self::attach_log_tail`] but through a shared reference
+    pub fn set_attach_log_tail(&self, log_tail: Option<(PathBuf, u64)>) {
+        self.update_config(|config| config.log_tail = log_tail);
+    }
+}
@@ -0,0 +1,118 @@
+//! Routing `log` records through the handler
+
+use core::fmt;
+use std::sync::Arc;
+
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+#[cfg(feature = "discord")]
+use tokio::runtime::Handle;
+#[cfg(feature = "discord")]
+use twilight_http::Client;
+
+use crate::{ErrorHandler, ErrorReport, Severity};
+
+/// A [`Log`] implementation that handles `log` records with a handler, made
+/// with [`ErrorHandler::logger`]
+///
+/// Records from this crate are ignored so that failing sinks don't report
+/// themselves
+#[must_use]
+pub struct ErrorLogger {
+    /// The handler to handle the records with
+    handler: Arc<ErrorHandler>,
+    /// The client to use the Discord sinks with
+    #[cfg(feature = "discord")]
+    http: Arc<Client>,
+    /// The least serious level of records to handle
+    level: LevelFilter,
+}
+
+impl ErrorLogger {
+    /// Set the least serious level of records to handle, defaults to
+    /// [`LevelFilter::Error`]
+    ///
+    /// [`Level::Warn`] and less serious records are handled with
+    /// [`Severity::Warning`], [`Level::Error`] records with [`Severity::Error`]
+    pub const fn level(mut self, level: LevelFilter) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Set the logger as the global logger of the `log` facade
+    ///
+    /// # Errors
+    /// Returns [`SetLoggerError`] if a global logger was already set
+    pub fn install(self) -> Result<(), SetLoggerError> {
+        let level = self.level;
+        log::set_boxed_logger(Box::new(self))?;
+        log::set_max_level(level);
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for ErrorLogger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ErrorLogger")
+            .field("level", &self.level)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Log for ErrorLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= self.level && !metadata.target().starts_with(env!("CARGO_CRATE_NAME"))
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let severity = if record.level() == Level::Error {
+            Severity::Error
+        } else {
+            Severity::Warning
+        };
+        let mut report = ErrorReport::new(format_args!("{}: {}", record.target(), record.args()))
+            .severity(severity);
+        report = match (record.file(), record.line()) {
+            (Some(file), Some(line)) => report.location(file, line),
+            _ => report.without_location(),
+        };
+
+        #[cfg(feature = "discord")]
+        if let Ok(runtime) = Handle::try_current() {
+            let handler = Arc::clone(&self.handler);
+            let http = Arc::clone(&self.http);
+            runtime.spawn(async move { handler.handle_report(&http, report).await });
+            return;
+        }
+
+        self.handler.handle_report_sync(report);
+    }
+
+    fn flush(&self) {}
+}
+
+impl ErrorHandler {
+    /// Returns a [`Log`] implementation that handles `log` records of at least
+    /// [`Level::Error`], so that errors logged by crates using the `log`
+    /// facade are delivered to the sinks
+    ///
+    /// The records are handled in a spawned task if there's a Tokio runtime,
+    /// otherwise or without the `discord` feature with
+    /// [`Self::handle_report_sync`], in which case the client parameter is
+    /// removed, use [`ErrorLogger::install`] to set it as the global logger
+    pub const fn logger(
+        self: Arc<Self>,
+        #[cfg(feature = "discord")] http: Arc<Client>,
+    ) -> ErrorLogger {
+        ErrorLogger {
+            handler: self,
+            #[cfg(feature = "discord")]
+            http,
+            level: LevelFilter::Error,
+        }
+    }
+}
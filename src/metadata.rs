@@ -0,0 +1,79 @@
+//! Attaching the host and process to every report
+
+use core::fmt::Display;
+use std::{env, fs, process, sync::LazyLock};
+
+use crate::{config::Config, ErrorHandler};
+
+/// The name of the host the process runs on, if it could be found
+static HOSTNAME: LazyLock<Option<String>> = LazyLock::new(|| {
+    ["/proc/sys/kernel/hostname", "/etc/hostname"]
+        .into_iter()
+        .find_map(|path| fs::read_to_string(path).ok())
+        .or_else(|| env::var("HOSTNAME").ok())
+        .or_else(|| env::var("COMPUTERNAME").ok())
+        .map(|hostname| hostname.trim().to_owned())
+        .filter(|hostname| !hostname.is_empty())
+});
+
+/// Returns the host, the process ID and the application's name, version and
+/// commit as fields
+pub(crate) fn fields(config: &Config) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+
+    if let Some(hostname) = &*HOSTNAME {
+        fields.push(("Host".to_owned(), hostname.clone()));
+    }
+    fields.push(("PID".to_owned(), process::id().to_string()));
+    if let Some(app) = &config.app {
+        fields.push(("App".to_owned(), app.clone()));
+    }
+    if let Some(commit) = &config.commit {
+        fields.push(("Commit".to_owned(), commit.clone()));
+    }
+
+    fields
+}
+
+impl ErrorHandler {
+    /// Set whether to attach the host, the process ID and the application set
+    /// with [`Self::app`] to every report, so that the reports of multiple
+    /// processes in one channel can be told apart, defaults to `true`
+    pub fn include_metadata(&mut self, include: bool) -> &mut Self {
+        self.config_mut().include_metadata = include;
+        self
+    }
+
+    /// Change whether to attach the host and process to every report at
+    /// runtime
+    ///
+    /// Same as [`Self::include_metadata`] but through a shared reference
+    pub fn set_include_metadata(&self, include: bool) {
+        self.update_config(|config| config.include_metadata = include);
+    }
+
+    /// Set the name and version of the application attached to every report,
+    /// such as the ones of the bot's crate
+    ///
+    /// ```ignore
+    /// handler.app(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+    /// ```
+    pub fn app(&mut self, name: impl Display, version: impl Display) -> &mut Self {
+        self.config_mut().app = Some(format!("{name} {version}"));
+        self
+    }
+
+    /// Set the commit the application was built from, attached to every
+    /// report
+    ///
+    /// Pass `None` to unset it, such as when an environment variable set at
+    /// build time isn't set
+    ///
+    /// ```ignore
+    /// handler.commit(option_env!("GIT_COMMIT"));
+    /// ```
+    pub fn commit(&mut self, commit: Option<impl Into<String>>) -> &mut Self {
+        self.config_mut().commit = commit.map(Into::into);
+        self
+    }
+}
@@ -0,0 +1,99 @@
+//! Bridging the handler's activity into metrics systems
+
+use core::fmt;
+use std::sync::Arc;
+
+use crate::{ErrorHandler, Severity, Sink};
+
+/// Why an error wasn't delivered to the sinks, passed to
+/// [`MetricsHook::on_dropped`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DropReason {
+    /// It's below [`ErrorHandler::min_severity`], so it was only printed
+    BelowMinSeverity,
+    /// An identical error was being delivered, see
+    /// [`ErrorHandler::coalesce_identical`]
+    #[cfg(feature = "discord")]
+    Coalesced,
+    /// The queue of [`crate::Reporter`] is full
+    #[cfg(feature = "discord")]
+    QueueFull,
+}
+
+impl fmt::Display for DropReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::BelowMinSeverity => "below_min_severity",
+            #[cfg(feature = "discord")]
+            Self::Coalesced => "coalesced",
+            #[cfg(feature = "discord")]
+            Self::QueueFull => "queue_full",
+        })
+    }
+}
+
+/// Receives the activity of the handler to record it in a metrics system,
+/// set with [`ErrorHandler::metrics_hook`]
+///
+/// Every method does nothing by default, they're called while handling the
+/// error so they should only update counters or similar
+pub trait MetricsHook: Send + Sync {
+    /// Called when an error with the severity is handled, including the ones
+    /// that are dropped
+    fn on_handled(&self, _severity: Severity) {}
+
+    /// Called after delivering to the sink with whether it succeeded
+    fn on_sink_result(&self, _sink: Sink, _ok: bool) {}
+
+    /// Called when an error isn't delivered to the sinks
+    fn on_dropped(&self, _reason: DropReason) {}
+}
+
+/// The hook called with the activity of the handler
+#[derive(Clone)]
+pub(crate) struct Metrics(Arc<dyn MetricsHook>);
+
+impl fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Metrics")
+    }
+}
+
+impl ErrorHandler {
+    /// Records that an error with the severity was handled
+    pub(crate) fn record_handled(&self, severity: Severity) {
+        self.stats.record_error(severity);
+        if let Some(metrics) = &self.config().metrics_hook {
+            metrics.0.on_handled(severity);
+        }
+    }
+
+    /// Records the result of delivering to the sink
+    pub(crate) fn record_sink(&self, sink: Sink, succeeded: bool) {
+        self.stats.record_sink(sink, succeeded);
+        if let Some(metrics) = &self.config().metrics_hook {
+            metrics.0.on_sink_result(sink, succeeded);
+        }
+    }
+
+    /// Records that an error wasn't delivered to the sinks
+    pub(crate) fn record_dropped(&self, reason: DropReason) {
+        if let Some(metrics) = &self.config().metrics_hook {
+            metrics.0.on_dropped(reason);
+        }
+    }
+
+    /// Set the hook called with the errors handled, the results of
+    /// delivering them and the ones dropped, to bridge them into a metrics
+    /// system without this crate depending on it
+    pub fn metrics_hook(&mut self, hook: impl MetricsHook + 'static) -> &mut Self {
+        self.config_mut().metrics_hook = Some(Metrics(Arc::new(hook)));
+        self
+    }
+
+    /// Stop calling the metrics hook
+    pub fn remove_metrics_hook(&self) {
+        self.update_config(|config| config.metrics_hook = None);
+    }
+}
@@ -0,0 +1,355 @@
+//! Publishing errors to a NATS subject
+
+use core::{fmt, time::Duration};
+use std::io::{self, ErrorKind};
+
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader},
+    net::TcpStream,
+    time,
+};
+
+use crate::{config::Config, delivery, ErrorHandler, ErrorRecord};
+
+/// How long publishing an error can take before it fails
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The NATS server and subject errors are published to
+#[derive(Clone, Debug)]
+pub(crate) struct NatsSink {
+    /// The address of the server, such as `localhost:4222`
+    address: String,
+    /// The subject the records are published to
+    subject: String,
+}
+
+/// The credentials to connect to the NATS server with, set with
+/// [`ErrorHandler::nats_auth`]
+#[derive(Clone)]
+pub struct NatsAuth(Credentials);
+
+/// The kind of credentials
+#[derive(Clone)]
+enum Credentials {
+    /// A username and password
+    UserPassword {
+        /// The username
+        user: String,
+        /// The password
+        password: String,
+    },
+    /// A token
+    Token(String),
+}
+
+impl NatsAuth {
+    /// Make credentials with a username and password
+    #[must_use]
+    pub fn user_password(user: impl Into<String>, password: impl Into<String>) -> Self {
+        Self(Credentials::UserPassword {
+            user: user.into(),
+            password: password.into(),
+        })
+    }
+
+    /// Make credentials with a token
+    #[must_use]
+    pub fn token(token: impl Into<String>) -> Self {
+        Self(Credentials::Token(token.into()))
+    }
+}
+
+impl fmt::Debug for NatsAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Credentials::UserPassword { user, .. } => f
+                .debug_struct("NatsAuth")
+                .field("user", user)
+                .field("password", &"..")
+                .finish(),
+            Credentials::Token(_) => f.debug_struct("NatsAuth").field("token", &"..").finish(),
+        }
+    }
+}
+
+/// Publishes the record as JSON to the subject of [`ErrorHandler::nats`],
+/// returning the failure note if it failed
+pub(crate) async fn publish(config: &Config, record: &ErrorRecord) -> Option<String> {
+    let nats = config.nats.as_ref()?;
+    if config.dry_run {
+        return Some(format!(
+            "\n\nDry run, would publish to the NATS subject {}",
+            nats.subject
+        ));
+    }
+
+    let name = format!("NATS subject {}", nats.subject);
+    let result = delivery::traced(&name, async {
+        let payload = serde_json::to_vec(record).map_err(io::Error::from)?;
+        delivery::retrying(
+            config.retry_policy.as_ref(),
+            || async {
+                time::timeout(
+                    PUBLISH_TIMEOUT,
+                    publish_once(nats, config.nats_auth.as_ref(), &payload),
+                )
+                .await
+                .map_err(io::Error::from)?
+            },
+            |err| err.kind() != ErrorKind::InvalidInput,
+        )
+        .await
+    })
+    .await;
+
+    result
+        .err()
+        .map(|err| format!("\n\nFailed to publish to the {name}: {err}"))
+}
+
+/// Connects to the server, publishes the payload and waits for the server to
+/// process it
+///
+/// A connection is made for every error since they're rare, the publish is
+/// followed by a `PING` so that the `PONG` confirms it was processed
+async fn publish_once(nats: &NatsSink, auth: Option<&NatsAuth>, payload: &[u8]) -> io::Result<()> {
+    if nats.subject.is_empty() || nats.subject.contains(char::is_whitespace) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "the subject is empty or contains whitespace",
+        ));
+    }
+
+    let mut stream = BufReader::new(TcpStream::connect(&nats.address).await?);
+    let mut line = String::new();
+    stream.read_line(&mut line).await?;
+    if !line.starts_with("INFO") {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("unexpected greeting: {}", line.trim_end()),
+        ));
+    }
+
+    let mut request = format!(
+        "CONNECT {}\r\nPUB {} {}\r\n",
+        connect_options(auth),
+        nats.subject,
+        payload.len()
+    )
+    .into_bytes();
+    request.extend_from_slice(payload);
+    request.extend_from_slice(b"\r\nPING\r\n");
+    stream.get_mut().write_all(&request).await?;
+
+    loop {
+        line.clear();
+        if stream.read_line(&mut line).await? == 0 {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        if line.starts_with("PONG") {
+            return Ok(());
+        }
+        if line.starts_with("-ERR") {
+            // the server closes the connection after an authorization error
+            // or a payload over its limit, which won't succeed on a retry
+            let kind = if line.contains("Authorization Violation")
+                || line.contains("Maximum Payload Violation")
+            {
+                ErrorKind::InvalidInput
+            } else {
+                ErrorKind::Other
+            };
+            return Err(io::Error::new(kind, line.trim_end().to_owned()));
+        }
+        if line.starts_with("PING") {
+            stream.get_mut().write_all(b"PONG\r\n").await?;
+        }
+    }
+}
+
+/// Returns the JSON options of the `CONNECT` message with the credentials
+fn connect_options(auth: Option<&NatsAuth>) -> Value {
+    let mut options = json!({
+        "verbose": false,
+        "pedantic": false,
+        "name": env!("CARGO_PKG_NAME"),
+    });
+    if let (Some(NatsAuth(credentials)), Value::Object(object)) = (auth, &mut options) {
+        match credentials {
+            Credentials::UserPassword { user, password } => {
+                object.insert("user".to_owned(), json!(user));
+                object.insert("pass".to_owned(), json!(password));
+            }
+            Credentials::Token(token) => {
+                object.insert("auth_token".to_owned(), json!(token));
+            }
+        }
+    }
+
+    options
+}
+
+impl ErrorHandler {
+    /// Also publish errors as JSON records to the subject of the NATS server
+    /// at the address, such as `localhost:4222`, to aggregate the errors of
+    /// every bot process in an existing message infrastructure
+    ///
+    /// They're published in a spawned task when they're handled inside a Tokio
+    /// runtime, including by [`Self::handle_sync`], set [`Self::nats_auth`] if
+    /// the server requires authentication
+    pub fn nats(&mut self, address: impl Into<String>, subject: impl Into<String>) -> &mut Self {
+        self.config_mut().nats = Some(NatsSink {
+            address: address.into(),
+            subject: subject.into(),
+        });
+        self
+    }
+
+    /// Change the NATS server address and subject errors are published to at
+    /// runtime, `None` to stop publishing errors to NATS
+    ///
+    /// Same as [`Self::nats`] but through a shared reference
+    pub fn set_nats(&self, nats: Option<(String, String)>) {
+        self.update_config(|config| {
+            config.nats = nats.map(|(address, subject)| NatsSink { address, subject });
+        });
+    }
+
+    /// Connect to the NATS server with the credentials
+    pub fn nats_auth(&mut self, auth: NatsAuth) -> &mut Self {
+        self.config_mut().nats_auth = Some(auth);
+        self
+    }
+
+    /// Change the credentials to connect to the NATS server with at runtime,
+    /// `None` to connect without authentication
+    ///
+    /// Same as [`Self::nats_auth`] but through a shared reference
+    pub fn set_nats_auth(&self, auth: Option<NatsAuth>) {
+        self.update_config(|config| config.nats_auth = auth);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{net::TcpListener, task::JoinHandle};
+
+    use super::*;
+
+    /// Starts a server that sends the greeting, reads the request until its
+    /// `PING` and then sends the replies, reading a line before each one
+    /// after the first, returning its address and the lines it read
+    async fn server(
+        greeting: &'static str,
+        replies: &'static [&'static str],
+    ) -> (NatsSink, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let nats = NatsSink {
+            address: listener.local_addr().unwrap().to_string(),
+            subject: "errors".to_owned(),
+        };
+
+        let task = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(socket);
+            stream
+                .get_mut()
+                .write_all(greeting.as_bytes())
+                .await
+                .unwrap();
+
+            let mut request = String::new();
+            while !request.ends_with("PING\r\n") {
+                if stream.read_line(&mut request).await.unwrap() == 0 {
+                    return request;
+                }
+            }
+            if let Some((first, rest)) = replies.split_first() {
+                stream.get_mut().write_all(first.as_bytes()).await.unwrap();
+                for reply in rest {
+                    stream.read_line(&mut request).await.unwrap();
+                    stream.get_mut().write_all(reply.as_bytes()).await.unwrap();
+                }
+            }
+            request
+        });
+
+        (nats, task)
+    }
+
+    #[tokio::test]
+    async fn publish_is_framed_and_confirmed() {
+        let (nats, task) = server("INFO {}\r\n", &["PONG\r\n"]).await;
+
+        publish_once(&nats, Some(&NatsAuth::token("secret")), br#"{"a":1}"#)
+            .await
+            .unwrap();
+
+        let request = task.await.unwrap();
+        let (connect, publish) = request.split_once("\r\n").unwrap();
+        let options: Value =
+            serde_json::from_str(connect.strip_prefix("CONNECT ").unwrap()).unwrap();
+        assert_eq!(options["auth_token"], "secret");
+        assert_eq!(options["verbose"], false);
+        assert_eq!(publish, "PUB errors 7\r\n{\"a\":1}\r\nPING\r\n");
+    }
+
+    #[tokio::test]
+    async fn server_ping_is_answered() {
+        let (nats, task) = server("INFO {}\r\n", &["PING\r\n", "PONG\r\n"]).await;
+
+        publish_once(&nats, None, b"{}").await.unwrap();
+
+        assert!(task.await.unwrap().ends_with("PING\r\nPONG\r\n"));
+    }
+
+    #[tokio::test]
+    async fn errors_that_fail_again_are_invalid_input() {
+        for reply in [
+            &["-ERR 'Authorization Violation'\r\n"],
+            &["-ERR 'Maximum Payload Violation'\r\n"],
+        ] {
+            let (nats, _task) = server("INFO {}\r\n", reply).await;
+
+            let err = publish_once(&nats, None, b"{}").await.unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        }
+    }
+
+    #[tokio::test]
+    async fn other_errors_are_retried() {
+        let (nats, _task) = server("INFO {}\r\n", &["-ERR 'Stale Connection'\r\n"]).await;
+
+        let err = publish_once(&nats, None, b"{}").await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Other);
+        assert_eq!(err.to_string(), "-ERR 'Stale Connection'");
+    }
+
+    #[tokio::test]
+    async fn unexpected_greeting_is_invalid_data() {
+        let (nats, _task) = server("HELLO\r\n", &[]).await;
+
+        let err = publish_once(&nats, None, b"{}").await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn closed_connection_is_unexpected_eof() {
+        let (nats, _task) = server("INFO {}\r\n", &[]).await;
+
+        let err = publish_once(&nats, None, b"{}").await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[tokio::test]
+    async fn subject_with_whitespace_is_invalid_input() {
+        let nats = NatsSink {
+            address: "127.0.0.1:1".to_owned(),
+            subject: "bot errors".to_owned(),
+        };
+
+        let err = publish_once(&nats, None, b"{}").await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,276 @@
+//! Keeping errors in a file until they're delivered to Discord
+
+use std::{
+    collections::HashSet,
+    fs::{self, OpenOptions},
+    io::{self, ErrorKind, Write as _},
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+};
+
+use twilight_http::Client;
+
+use crate::{
+    config::Config, formatter::Message, DeliveryMode, DeliverySemantics, DiagnosticEvent,
+    ErrorHandler, ErrorId, ErrorRecord, Sink,
+};
+
+/// The errors that weren't delivered to Discord yet, kept in a file with one
+/// JSON record per line
+#[derive(Debug, Default)]
+pub(crate) struct Outbox {
+    /// Held while the file is changed
+    lock: Mutex<()>,
+    /// The IDs of the records being delivered, which aren't delivered from
+    /// the file again meanwhile
+    delivering: Mutex<HashSet<ErrorId>>,
+}
+
+impl Outbox {
+    /// Returns the guard of the delivery of the record, `None` if it's
+    /// already being delivered
+    pub(crate) fn start(&self, id: ErrorId) -> Option<Delivering<'_>> {
+        let started = self
+            .delivering
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(id);
+        // made lazily, since dropping it would unmark the record
+        started.then(|| Delivering { outbox: self, id })
+    }
+
+    /// Appends the record to the file
+    fn push(&self, path: &Path, record: &ErrorRecord) -> io::Result<()> {
+        let mut line = serde_json::to_string(record).map_err(io::Error::from)?;
+        line.push('\n');
+
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?
+            .write_all(line.as_bytes())
+    }
+
+    /// Returns the records in the file, skipping the lines that aren't valid
+    fn read(&self, path: &Path) -> io::Result<Vec<ErrorRecord>> {
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        Ok(read_lines(path)?
+            .iter()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    /// Removes the records with the IDs from the file, removing the file if
+    /// it's empty afterwards
+    fn remove(&self, path: &Path, ids: &[ErrorId]) -> io::Result<()> {
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut kept = String::new();
+        for line in read_lines(path)? {
+            let delivered = serde_json::from_str::<ErrorRecord>(&line)
+                .is_ok_and(|record| ids.contains(&record.id));
+            if !delivered {
+                kept.push_str(&line);
+                kept.push('\n');
+            }
+        }
+
+        if kept.is_empty() {
+            match fs::remove_file(path) {
+                Err(err) if err.kind() != ErrorKind::NotFound => Err(err),
+                _ => Ok(()),
+            }
+        } else {
+            fs::write(path, kept)
+        }
+    }
+}
+
+/// A record of the outbox being delivered, it's delivered from the file
+/// again once this is dropped, such as if handling it is cancelled
+#[derive(Debug)]
+pub(crate) struct Delivering<'a> {
+    /// The outbox the record is in
+    outbox: &'a Outbox,
+    /// The ID of the record
+    id: ErrorId,
+}
+
+impl Drop for Delivering<'_> {
+    fn drop(&mut self) {
+        self.outbox
+            .delivering
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.id);
+    }
+}
+
+/// Returns the non-empty lines of the file, none if it doesn't exist
+fn read_lines(path: &Path) -> io::Result<Vec<String>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(str::to_owned)
+            .collect()),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err),
+    }
+}
+
+impl Config {
+    /// Returns whether the error delivered to the Discord sinks can be
+    /// removed from the outbox
+    ///
+    /// That's when it's delivered to every set sink with
+    /// [`DeliverySemantics::AtLeastOnce`], or to any sink if there are none
+    /// or with [`DeliveryMode::Fallback`]
+    pub(crate) fn outbox_delivered(&self, delivered: &[Sink]) -> bool {
+        let mut required = [Sink::Channel, Sink::Webhook].into_iter().filter(|sink| {
+            let set = match sink {
+                Sink::Channel => self.channel.is_some() || self.owner.is_some(),
+                Sink::Webhook => self.webhook.is_some(),
+                Sink::File => false,
+            };
+            set && self.delivery_semantics(*sink) == DeliverySemantics::AtLeastOnce
+        });
+
+        match required.next() {
+            Some(first) if self.delivery_mode != DeliveryMode::Fallback => {
+                delivered.contains(&first) && required.all(|sink| delivered.contains(&sink))
+            }
+            _ => !delivered.is_empty(),
+        }
+    }
+}
+
+impl ErrorHandler {
+    /// Writes the record to the outbox before it's delivered
+    pub(crate) fn push_outbox(&self, config: &Config, record: &ErrorRecord) {
+        let Some(path) = &config.outbox else {
+            return;
+        };
+        if let Err(err) = self.outbox.push(path, record) {
+            config.print_notice(&format!(
+                "Failed to write the error {} to the outbox: {err}",
+                record.id
+            ));
+        }
+    }
+
+    /// Removes the delivered record from the outbox
+    pub(crate) fn remove_outbox(&self, config: &Config, id: ErrorId) {
+        let Some(path) = &config.outbox else {
+            return;
+        };
+        if let Err(err) = self.outbox.remove(path, &[id]) {
+            config.print_notice(&format!(
+                "Failed to remove the error {id} from the outbox: {err}"
+            ));
+        }
+    }
+
+    /// Writes the record to the outbox instead of delivering it to Discord if
+    /// [`Self::defer_rate_limited`] is set and the Discord sinks are rate
+    /// limited, still appending it to the file and printing it, returning
+    /// whether it was deferred
+    pub(crate) async fn defer_if_rate_limited(
+        &self,
+        http: &Client,
+        config: &Config,
+        record: &ErrorRecord,
+    ) -> bool {
+        if !config.defer_rate_limited
+            || config.outbox.is_none()
+            || !self.discord_rate_limited(http, config).await
+        {
+            return false;
+        }
+
+        self.push_outbox(config, record);
+        self.report_sync(config, record);
+        self.diagnose(&DiagnosticEvent::Deferred(record.id));
+        true
+    }
+
+    /// Keep errors in the file at the path until they're delivered to a
+    /// Discord sink, one JSON record per line
+    ///
+    /// Errors are written to it before they're delivered and removed after,
+    /// call [`Self::deliver_outbox`] when the bot starts to deliver the ones
+    /// that couldn't be delivered before it stopped, such as during a Discord
+    /// outage
+    pub fn outbox(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        self.config_mut().outbox = Some(path.into());
+        self
+    }
+
+    /// Change the file errors are kept in until they're delivered at
+    /// runtime, `None` to not keep them
+    ///
+    /// Same as [`Self::outbox`] but through a shared reference, the errors in
+    /// the previous file are kept there
+    pub fn set_outbox(&self, path: Option<PathBuf>) {
+        self.update_config(|config| config.outbox = path);
+    }
+
+    /// Set whether to keep errors in the outbox instead of delivering them to
+    /// Discord when the client is globally rate limited or the buckets of
+    /// every Discord sink are exhausted, so that requests aren't spent on
+    /// rate limits
+    ///
+    /// They're still appended to the file and printed right away, call
+    /// [`Self::deliver_outbox`] periodically to deliver them later, this does
+    /// nothing without [`Self::outbox`]
+    pub fn defer_rate_limited(&mut self, defer: bool) -> &mut Self {
+        self.config_mut().defer_rate_limited = defer;
+        self
+    }
+
+    /// Change whether to keep errors in the outbox when the Discord sinks are
+    /// rate limited at runtime
+    ///
+    /// Same as [`Self::defer_rate_limited`] but through a shared reference
+    pub fn set_defer_rate_limited(&self, defer: bool) {
+        self.update_config(|config| config.defer_rate_limited = defer);
+    }
+
+    /// Deliver the errors left in the outbox, returning the number of them
+    /// that were delivered
+    ///
+    /// The errors that still can't be delivered to a Discord sink are kept
+    /// for the next call, they're also appended to the file and printed
+    /// again, the errors that are being delivered when it's called, such as
+    /// by [`Self::handle`], are skipped
+    ///
+    /// # Errors
+    /// Returns [`io::Error`] if reading or writing the outbox fails
+    pub async fn deliver_outbox(&self, http: &Client) -> io::Result<usize> {
+        let config = self.config();
+        let Some(path) = config.outbox.clone() else {
+            return Ok(0);
+        };
+
+        let mut delivered = Vec::new();
+        // held until the delivered records are removed, so that they aren't
+        // delivered by another call meanwhile
+        let mut guards = Vec::new();
+        for record in self.outbox.read(&path)? {
+            let Some(guard) = self.outbox.start(record.id) else {
+                continue;
+            };
+            let sinks = self
+                .report(http, &config, Message::Record(&record), &[])
+                .await;
+            if config.outbox_delivered(&sinks) {
+                delivered.push(record.id);
+            }
+            guards.push(guard);
+        }
+        self.outbox.remove(&path, &delivered)?;
+        drop(guards);
+
+        Ok(delivered.len())
+    }
+}
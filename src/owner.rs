@@ -0,0 +1,84 @@
+//! Delivering errors to the DMs of the bot's owner
+
+use std::sync::{Mutex, PoisonError};
+
+use twilight_http::Client;
+use twilight_model::id::{
+    marker::{ChannelMarker, UserMarker},
+    Id,
+};
+
+use crate::{config::Config, ErrorHandler};
+
+/// The DM channel of the owner, created when it's first used
+#[derive(Debug, Default)]
+pub(crate) struct OwnerChannel {
+    /// The owner and the ID of their DM channel
+    cached: Mutex<Option<(Id<UserMarker>, Id<ChannelMarker>)>>,
+}
+
+impl OwnerChannel {
+    /// Returns the DM channel of the owner, creating it if it isn't cached
+    /// for them
+    pub(crate) async fn get(
+        &self,
+        http: &Client,
+        owner_id: Id<UserMarker>,
+    ) -> Result<Id<ChannelMarker>, String> {
+        let cached = *self.cached.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some((cached_owner_id, channel_id)) = cached {
+            if cached_owner_id == owner_id {
+                return Ok(channel_id);
+            }
+        }
+
+        let channel = http
+            .create_private_channel(owner_id)
+            .await
+            .map_err(|err| err.to_string())?
+            .model()
+            .await
+            .map_err(|err| err.to_string())?;
+
+        *self.cached.lock().unwrap_or_else(PoisonError::into_inner) = Some((owner_id, channel.id));
+        Ok(channel.id)
+    }
+}
+
+impl ErrorHandler {
+    /// Returns the channel to create messages in, the DM channel of the owner
+    /// if [`Self::channel`] isn't set, `None` if neither is set
+    pub(crate) async fn resolve_channel(
+        &self,
+        http: &Client,
+        config: &Config,
+    ) -> Option<Result<Id<ChannelMarker>, String>> {
+        match (config.channel, config.owner) {
+            (Some(channel_id), _) => Some(Ok(channel_id)),
+            (None, Some(owner_id)) => Some(self.owner_channel.get(http, owner_id).await),
+            (None, None) => None,
+        }
+    }
+
+    /// Set the handler to create a message in the DMs of the given user on
+    /// errors, such as the bot's owner
+    ///
+    /// The DM channel is created when it's first used and cached, it's only
+    /// used if [`Self::channel`] isn't set and counts as [`crate::Sink::Channel`]
+    pub fn owner(&mut self, user_id: Id<UserMarker>) -> &mut Self {
+        self.config_mut().owner = Some(user_id);
+        self
+    }
+
+    /// Change the user to DM on errors at runtime
+    ///
+    /// Same as [`Self::owner`] but through a shared reference
+    pub fn set_owner(&self, user_id: Id<UserMarker>) {
+        self.update_config(|config| config.owner = Some(user_id));
+    }
+
+    /// Stop sending errors to the DMs of the user
+    pub fn remove_owner(&self) {
+        self.update_config(|config| config.owner = None);
+    }
+}
@@ -0,0 +1,105 @@
+//! Reporting panics through the handler
+
+use core::any::Any;
+use std::{
+    backtrace::{Backtrace, BacktraceStatus},
+    panic::{self, PanicHookInfo},
+    sync::Arc,
+    thread,
+};
+
+#[cfg(feature = "discord")]
+use tokio::{runtime::Builder, time};
+#[cfg(feature = "discord")]
+use twilight_http::Client;
+
+use crate::{ErrorHandler, ErrorReport, Severity};
+
+impl ErrorHandler {
+    /// Report every panic through the handler with [`Severity::Critical`]
+    ///
+    /// The report includes the panic message, its location and the backtrace
+    /// if it's enabled with `RUST_BACKTRACE`, the previous panic hook is
+    /// called afterwards
+    ///
+    /// The panicking thread waits at most [`Self::fatal_deadline`] for the
+    /// report to be delivered, which is done on a separate thread so that
+    /// this works inside a Tokio runtime, without the `discord` feature it's
+    /// handled with
+    /// [`Self::handle_report_sync`] and the client parameter is removed
+    pub fn install_panic_hook(self: Arc<Self>, #[cfg(feature = "discord")] http: Arc<Client>) {
+        let previous_hook = panic::take_hook();
+
+        panic::set_hook(Box::new(move |info| {
+            // the message already has the location of the panic
+            let report = ErrorReport::new(panic_message(info))
+                .severity(Severity::Critical)
+                .without_location();
+            #[cfg(not(feature = "discord"))]
+            self.handle_report_sync(report);
+            #[cfg(feature = "discord")]
+            {
+                let handler = Arc::clone(&self);
+                let client = Arc::clone(&http);
+
+                let reported = thread::spawn(move || {
+                    match Builder::new_current_thread().enable_all().build() {
+                        Ok(runtime) => runtime.block_on(async {
+                            let deadline = handler.config().fatal_deadline;
+                            if time::timeout(deadline, handler.handle_report(&client, report))
+                                .await
+                                .is_err()
+                            {
+                                handler.config().print_notice(&format!(
+                                    "Continuing the panic before it was delivered to every \
+                                     sink after {}s",
+                                    deadline.as_secs()
+                                ));
+                            }
+                        }),
+                        Err(_) => {
+                            handler.handle_report_sync(report);
+                        }
+                    }
+                })
+                .join();
+                drop(reported);
+            };
+
+            previous_hook(info);
+        }));
+    }
+}
+
+/// Returns the message, location and backtrace of the panic
+fn panic_message(info: &PanicHookInfo<'_>) -> String {
+    let message = payload_message(info.payload());
+
+    let location = info.location().map_or_else(String::new, |location| {
+        format!(
+            " at {}:{}:{}",
+            location.file(),
+            location.line(),
+            location.column()
+        )
+    });
+
+    let thread = thread::current();
+    let thread_name = thread.name().unwrap_or("<unnamed>");
+
+    let backtrace = Backtrace::capture();
+    if backtrace.status() == BacktraceStatus::Captured {
+        format!("Thread '{thread_name}' panicked{location}: {message}\n{backtrace}")
+    } else {
+        format!("Thread '{thread_name}' panicked{location}: {message}")
+    }
+}
+
+/// Returns the message the panic was started with
+pub(crate) fn payload_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>")
+}
@@ -0,0 +1,142 @@
+//! Choosing what's sent to Discord before building the request
+
+use std::borrow::Cow;
+
+use twilight_model::{channel::message::Embed, http::attachment::Attachment};
+use twilight_validate::{message, request};
+
+#[cfg(feature = "compression")]
+use crate::compress;
+use crate::{
+    backtrace, config::Config, formatter::Message, log_tail, truncate::MAX_CONTENT_LEN,
+    DEFAULT_ERROR_MESSAGE,
+};
+
+/// The name of the file the error message is attached to Discord messages as
+/// if it isn't valid as their content
+pub const ERROR_FILENAME: &str = "error.txt";
+
+/// The ID of the attachment of the error message, the backtrace's is 0
+const ERROR_ATTACHMENT_ID: u64 = 1;
+
+/// The validated content, embeds and attachments of a Discord message, so
+/// that each request is built exactly once
+#[derive(Debug)]
+pub(crate) struct Payload<'a> {
+    /// The error message, shortened if it's too long, empty if it's in an
+    /// embed or [`DEFAULT_ERROR_MESSAGE`] if it's attached
+    pub(crate) content: Cow<'a, str>,
+    /// The embeds, along with the error message if it's in one, or none if
+    /// they aren't valid
+    pub(crate) embeds: Cow<'a, [Embed]>,
+    /// The attachments, along with the error message if it isn't valid as
+    /// the content
+    pub(crate) attachments: Vec<Attachment>,
+    /// The username of the webhook or none if it isn't valid
+    pub(crate) username: Option<&'a str>,
+    /// The URL of the avatar of the webhook
+    pub(crate) avatar_url: Option<&'a str>,
+}
+
+impl<'a> Payload<'a> {
+    /// Chooses the payload of the error message
+    ///
+    /// If the error message isn't valid as the content, such as if it's too
+    /// long, it's sent as the description of an embed before the others if
+    /// they're still valid with it, otherwise it's shortened according to
+    /// [`crate::ErrorHandler::truncation`], and with
+    /// [`crate::Truncation::Fallback`] or if it's still invalid,
+    /// [`DEFAULT_ERROR_MESSAGE`] is sent instead with the error message
+    /// attached as [`ERROR_FILENAME`], the attachments are compressed with
+    /// [`crate::ErrorHandler::compress_attachments`]
+    pub(crate) fn new(
+        config: &Config,
+        error_message: &'a str,
+        embeds: &'a [Embed],
+        mut attachments: Vec<Attachment>,
+    ) -> Self {
+        let valid_embeds = if message::embeds(embeds).is_ok() {
+            embeds
+        } else {
+            &[]
+        };
+        let (content, all_embeds) = if message::content(error_message).is_ok() {
+            (Cow::Borrowed(error_message), Cow::Borrowed(valid_embeds))
+        } else if let Some(with_message) = with_message_embed(valid_embeds, error_message) {
+            (Cow::Borrowed(""), Cow::Owned(with_message))
+        } else if let Some(truncated) = config
+            .truncation
+            .truncate(error_message, MAX_CONTENT_LEN)
+            .filter(|truncated| message::content(truncated).is_ok())
+        {
+            (truncated, Cow::Borrowed(valid_embeds))
+        } else {
+            attachments.push(Attachment::from_bytes(
+                ERROR_FILENAME.to_owned(),
+                error_message.as_bytes().to_vec(),
+                ERROR_ATTACHMENT_ID,
+            ));
+            (
+                Cow::Borrowed(DEFAULT_ERROR_MESSAGE),
+                Cow::Borrowed(valid_embeds),
+            )
+        };
+        #[cfg(feature = "compression")]
+        compress::compress_large(&mut attachments, config.compress_attachments_over);
+
+        Self {
+            content,
+            embeds: all_embeds,
+            attachments,
+            username: None,
+            avatar_url: None,
+        }
+    }
+
+    /// Uses the username and avatar set for the severity of the message with
+    /// [`crate::ErrorHandler::severity_webhook_username`] and
+    /// [`crate::ErrorHandler::severity_webhook_avatar`] if it's executed with
+    /// a webhook
+    pub(crate) fn webhook_identity(mut self, config: &'a Config, message: Message<'_>) -> Self {
+        if let Some(severity) = message.severity() {
+            let (username, avatar_url) = config.severity_styles.webhook_identity(severity);
+            self.username = username.filter(|name| request::webhook_username(name).is_ok());
+            self.avatar_url = avatar_url;
+        }
+        self
+    }
+}
+
+/// Returns the embeds with an embed with the error message as its
+/// description first, none if they aren't valid with it
+fn with_message_embed(embeds: &[Embed], error_message: &str) -> Option<Vec<Embed>> {
+    let mut with_message = Vec::with_capacity(embeds.len().saturating_add(1));
+    with_message.push(Embed {
+        author: None,
+        color: None,
+        description: Some(error_message.to_owned()),
+        fields: Vec::new(),
+        footer: None,
+        image: None,
+        kind: "rich".to_owned(),
+        provider: None,
+        thumbnail: None,
+        timestamp: None,
+        title: None,
+        url: None,
+        video: None,
+    });
+    with_message.extend_from_slice(embeds);
+
+    message::embeds(&with_message)
+        .is_ok()
+        .then_some(with_message)
+}
+
+/// Returns the attachments of the message, its backtrace and the end of the
+/// log file
+pub(crate) fn attachments(config: &Config, message: Message<'_>) -> Vec<Attachment> {
+    let mut attachments = backtrace::attachments(message.backtrace());
+    attachments.extend(log_tail::attachment(config, message));
+    attachments
+}
@@ -0,0 +1,175 @@
+//! Checking that the bot can create messages in the channel
+
+use core::{error::Error, fmt};
+
+use twilight_http::{response::DeserializeBodyError, Client, Error as HttpError};
+use twilight_model::guild::Permissions;
+use twilight_util::permission_calculator::PermissionCalculator;
+
+use crate::ErrorHandler;
+
+/// The permissions needed to create messages with embeds and the backtrace
+/// attached in channels
+const REQUIRED: Permissions = Permissions::VIEW_CHANNEL
+    .union(Permissions::SEND_MESSAGES)
+    .union(Permissions::EMBED_LINKS)
+    .union(Permissions::ATTACH_FILES);
+
+/// The permissions needed to create messages with embeds and the backtrace
+/// attached in threads
+const REQUIRED_IN_THREADS: Permissions = Permissions::VIEW_CHANNEL
+    .union(Permissions::SEND_MESSAGES_IN_THREADS)
+    .union(Permissions::EMBED_LINKS)
+    .union(Permissions::ATTACH_FILES);
+
+/// The names of the required permissions as Discord shows them
+const PERMISSION_NAMES: [(Permissions, &str); 5] = [
+    (Permissions::VIEW_CHANNEL, "View Channel"),
+    (Permissions::SEND_MESSAGES, "Send Messages"),
+    (
+        Permissions::SEND_MESSAGES_IN_THREADS,
+        "Send Messages in Threads",
+    ),
+    (Permissions::EMBED_LINKS, "Embed Links"),
+    (Permissions::ATTACH_FILES, "Attach Files"),
+];
+
+/// The error returned from [`ErrorHandler::check_channel_permissions`]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ChannelPermissionsError {
+    /// The bot is missing the permissions in the channel
+    Missing(Permissions),
+    /// Fetching the channel, the guild or the bot's member failed
+    Http(HttpError),
+    /// The response to fetching the channel, the guild or the bot's member
+    /// isn't valid
+    Deserialize(DeserializeBodyError),
+}
+
+impl fmt::Display for ChannelPermissionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing(missing) => {
+                let names = PERMISSION_NAMES
+                    .iter()
+                    .filter(|(permission, _)| missing.contains(*permission))
+                    .map(|(_, name)| *name)
+                    .collect::<Vec<_>>();
+                write!(
+                    f,
+                    "the bot is missing permissions in the channel: {}",
+                    names.join(", ")
+                )
+            }
+            Self::Http(err) => write!(f, "failed to fetch the channel: {err}"),
+            Self::Deserialize(err) => write!(f, "invalid response to fetching the channel: {err}"),
+        }
+    }
+}
+
+impl Error for ChannelPermissionsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Missing(_) => None,
+            Self::Http(err) => Some(err),
+            Self::Deserialize(err) => Some(err),
+        }
+    }
+}
+
+impl From<HttpError> for ChannelPermissionsError {
+    fn from(err: HttpError) -> Self {
+        Self::Http(err)
+    }
+}
+
+impl From<DeserializeBodyError> for ChannelPermissionsError {
+    fn from(err: DeserializeBodyError) -> Self {
+        Self::Deserialize(err)
+    }
+}
+
+impl ErrorHandler {
+    /// Check that the bot can create messages with embeds and attachments in
+    /// [`Self::channel`], returning the permissions it's missing otherwise
+    ///
+    /// Call this on startup to find out about a misconfigured channel before
+    /// a real error is lost to it, DM channels and an unset channel are
+    /// always fine
+    ///
+    /// # Errors
+    /// Returns [`ChannelPermissionsError::Missing`] with the missing
+    /// permissions, or the error of fetching the channel, the guild or the
+    /// bot's member
+    pub async fn check_channel_permissions(
+        &self,
+        http: &Client,
+    ) -> Result<(), ChannelPermissionsError> {
+        let Some(channel_id) = self.config().channel else {
+            return Ok(());
+        };
+
+        let channel = http.channel(channel_id).await?.model().await?;
+        let Some(guild_id) = channel.guild_id else {
+            return Ok(());
+        };
+        let (overwrites_channel, required) = match channel.parent_id {
+            Some(parent_id) if channel.kind.is_thread() => (
+                http.channel(parent_id).await?.model().await?,
+                REQUIRED_IN_THREADS,
+            ),
+            _ => (channel, REQUIRED),
+        };
+
+        let user = http.current_user().await?.model().await?;
+        let guild = http.guild(guild_id).await?.model().await?;
+        let member = http.guild_member(guild_id, user.id).await?.model().await?;
+
+        let everyone = guild
+            .roles
+            .iter()
+            .find(|role| role.id.cast() == guild_id)
+            .map_or(Permissions::empty(), |role| role.permissions);
+        let member_roles = guild
+            .roles
+            .iter()
+            .filter(|role| member.roles.contains(&role.id))
+            .map(|role| (role.id, role.permissions))
+            .collect::<Vec<_>>();
+
+        let permissions = PermissionCalculator::new(guild_id, user.id, everyone, &member_roles)
+            .owner_id(guild.owner_id)
+            .in_channel(
+                overwrites_channel.kind,
+                overwrites_channel
+                    .permission_overwrites
+                    .as_deref()
+                    .unwrap_or_default(),
+            );
+
+        let missing = required.difference(permissions);
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(ChannelPermissionsError::Missing(missing))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_permissions_are_named() {
+        let err = ChannelPermissionsError::Missing(
+            Permissions::EMBED_LINKS | Permissions::SEND_MESSAGES_IN_THREADS,
+        );
+
+        assert_eq!(
+            err.to_string(),
+            "the bot is missing permissions in the channel: Send Messages in Threads, Embed Links"
+        );
+    }
+}
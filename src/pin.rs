@@ -0,0 +1,94 @@
+//! Pinning the messages of critical errors until they're resolved
+
+use std::{
+    collections::HashMap,
+    sync::{Mutex, PoisonError},
+};
+
+use twilight_http::{Client, Error as HttpError};
+use twilight_model::{
+    channel::Message as ChannelMessage,
+    id::{
+        marker::{ChannelMarker, MessageMarker},
+        Id,
+    },
+};
+
+use crate::{ErrorHandler, ErrorRecord, Fingerprint};
+
+/// The channel and ID of a pinned message
+type PinnedMessage = (Id<ChannelMarker>, Id<MessageMarker>);
+
+/// The pinned messages of each fingerprint
+#[derive(Debug, Default)]
+pub(crate) struct Pins {
+    /// The pinned messages of each fingerprint
+    pinned: Mutex<HashMap<Fingerprint, Vec<PinnedMessage>>>,
+}
+
+impl ErrorHandler {
+    /// Pins the created message of the error, returning the note of why it
+    /// failed
+    pub(crate) async fn pin(
+        &self,
+        http: &Client,
+        record: &ErrorRecord,
+        created: &ChannelMessage,
+    ) -> Option<String> {
+        if let Err(err) = http.create_pin(created.channel_id, created.id).await {
+            return Some(format!("\n\nFailed to pin the message: {err}"));
+        }
+
+        self.pins
+            .pinned
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(record.fingerprint())
+            .or_default()
+            .push((created.channel_id, created.id));
+        None
+    }
+
+    /// Set whether to pin the messages of critical errors in the channel
+    ///
+    /// They're unpinned when their fingerprint is marked resolved with
+    /// [`Self::resolve`], defaults to `false`
+    pub fn pin_critical(&mut self, pin: bool) -> &mut Self {
+        self.config_mut().pin_critical = pin;
+        self
+    }
+
+    /// Change whether to pin the messages of critical errors at runtime
+    ///
+    /// Same as [`Self::pin_critical`] but through a shared reference
+    pub fn set_pin_critical(&self, pin: bool) {
+        self.update_config(|config| config.pin_critical = pin);
+    }
+
+    /// Mark the errors with the fingerprint resolved, unpinning their
+    /// messages
+    ///
+    /// The fingerprint can be taken from the records returned by
+    /// [`Self::recent_errors`], see [`crate::ErrorRecord::fingerprint`]
+    ///
+    /// # Errors
+    /// Returns the last error of unpinning the messages, they're all tried
+    /// and forgotten either way
+    pub async fn resolve(&self, http: &Client, fingerprint: Fingerprint) -> Result<(), HttpError> {
+        let pinned = self
+            .pins
+            .pinned
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&fingerprint)
+            .unwrap_or_default();
+
+        let mut result = Ok(());
+        for (channel_id, message_id) in pinned {
+            if let Err(err) = http.delete_pin(channel_id, message_id).await {
+                result = Err(err);
+            }
+        }
+        result
+    }
+}
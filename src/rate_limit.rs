@@ -0,0 +1,160 @@
+//! Waiting for the rate limits Discord responds with
+
+use core::{future::Future, time::Duration};
+use std::{
+    collections::HashMap,
+    sync::{Mutex, PoisonError},
+};
+
+use tokio::time::{self, Instant};
+use twilight_http::{
+    api_error::ApiError, error::ErrorType, routing::Path, Client, Error as HttpError,
+};
+
+use crate::{config::Config, DiagnosticEvent, ErrorHandler, Sink};
+
+/// The longest rate limit that's waited for, longer ones fail the delivery
+const MAX_WAIT: Duration = Duration::from_mins(1);
+
+/// When the rate limit of each sink ends
+#[derive(Debug, Default)]
+pub(crate) struct RateLimits {
+    /// When the rate limit of each sink ends
+    limited_until: Mutex<HashMap<Sink, Instant>>,
+}
+
+impl RateLimits {
+    /// Returns how long to wait before using the sink
+    fn remaining(&self, sink: Sink) -> Option<Duration> {
+        let mut limited_until = self
+            .limited_until
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let until = *limited_until.get(&sink)?;
+
+        let remaining = until.checked_duration_since(Instant::now());
+        if remaining.is_none() {
+            limited_until.remove(&sink);
+        }
+        remaining
+    }
+
+    /// Records that the sink is rate limited for the duration
+    fn limit(&self, sink: Sink, retry_after: Duration) {
+        let Some(until) = Instant::now().checked_add(retry_after) else {
+            return;
+        };
+        self.limited_until
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(sink, until);
+    }
+}
+
+impl ErrorHandler {
+    /// Sends with the closure, waiting for the rate limit of the sink first
+    ///
+    /// If Discord responds with a rate limit, the sink is throttled until it
+    /// ends and the request is sent again once after it if it's shorter than
+    /// [`MAX_WAIT`]
+    pub(crate) async fn send_rate_limited<T, Fut: Future<Output = Result<T, HttpError>>>(
+        &self,
+        sink: Sink,
+        mut send: impl FnMut() -> Fut,
+    ) -> Result<T, HttpError> {
+        if let Some(remaining) = self.rate_limits.remaining(sink) {
+            time::sleep(remaining.min(MAX_WAIT)).await;
+        }
+
+        let result = send().await;
+        let Some(retry_after) = result.as_ref().err().and_then(rate_limited_for) else {
+            return result;
+        };
+
+        self.rate_limits.limit(sink, retry_after);
+        if retry_after > MAX_WAIT {
+            self.diagnose(&DiagnosticEvent::RetryExhausted { sink, retry_after });
+            return result;
+        }
+
+        time::sleep(retry_after).await;
+        let retried = send().await;
+        if let Some(retry_again_after) = retried.as_ref().err().and_then(rate_limited_for) {
+            self.rate_limits.limit(sink, retry_again_after);
+            self.diagnose(&DiagnosticEvent::RetryExhausted {
+                sink,
+                retry_after: retry_again_after,
+            });
+        }
+
+        retried
+    }
+
+    /// Returns whether the client is globally rate limited or every set
+    /// Discord sink is, by the rate limits Discord responded with or the
+    /// buckets of the client's ratelimiter
+    ///
+    /// The owner's DM channel isn't known before it's created, so it isn't
+    /// checked
+    #[cfg_attr(not(feature = "json"), allow(dead_code))]
+    pub(crate) async fn discord_rate_limited(&self, http: &Client, config: &Config) -> bool {
+        let mut paths = Vec::new();
+        if let Some(channel) = config.channel {
+            paths.push((Sink::Channel, Path::ChannelsIdMessages(channel.get())));
+        }
+        if let Some((webhook, token)) = &config.webhook {
+            paths.push((
+                Sink::Webhook,
+                Path::WebhooksIdToken(webhook.get(), token.clone()),
+            ));
+        }
+        if paths.is_empty() {
+            return false;
+        }
+
+        let ratelimiter = http.ratelimiter();
+        if let Some(limiter) = ratelimiter {
+            if limiter.is_globally_locked().await.unwrap_or(false) {
+                return true;
+            }
+        }
+
+        for (sink, path) in &paths {
+            if self.rate_limits.remaining(*sink).is_some() {
+                continue;
+            }
+            let Some(limiter) = ratelimiter else {
+                return false;
+            };
+            let exhausted = limiter
+                .bucket(path)
+                .await
+                .ok()
+                .flatten()
+                .is_some_and(|bucket| {
+                    bucket.remaining() == 0
+                        && bucket
+                            .time_remaining()
+                            .is_some_and(|remaining| !remaining.is_zero())
+                });
+            if !exhausted {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Returns how long to wait before retrying if the error is a rate limit
+fn rate_limited_for(error: &HttpError) -> Option<Duration> {
+    let ErrorType::Response {
+        error: ApiError::Ratelimited(ratelimited),
+        ..
+    } = error.kind()
+    else {
+        return None;
+    };
+
+    Duration::try_from_secs_f64(ratelimited.retry_after).ok()
+}
@@ -0,0 +1,82 @@
+//! Setting the Discord sinks from raw IDs, such as the values of a
+//! configuration file
+
+use core::str::FromStr;
+
+use twilight_model::id::{marker::WebhookMarker, Id};
+
+use crate::{config::parse_webhook_url, ConfigError, ErrorHandler};
+
+/// A webhook ID and token, parsed from `{id}/{token}` or a webhook URL
+///
+/// ```ignore
+/// let WebhookSpec { id, token, .. } = "123456789012345678/token".parse()?;
+/// handler.webhook(id, token);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct WebhookSpec {
+    /// The ID of the webhook
+    pub id: Id<WebhookMarker>,
+    /// The token of the webhook
+    pub token: String,
+}
+
+impl FromStr for WebhookSpec {
+    type Err = ConfigError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let parsed = if spec.contains("/webhooks/") {
+            parse_webhook_url(spec)
+        } else {
+            spec.split_once('/').and_then(|(id, token)| {
+                let webhook_id = Id::new_checked(id.parse().ok()?)?;
+                (!token.is_empty() && !token.contains('/')).then(|| (webhook_id, token.to_owned()))
+            })
+        };
+
+        parsed
+            .map(|(id, token)| Self { id, token })
+            .ok_or_else(|| ConfigError::InvalidWebhookSpec(spec.to_owned()))
+    }
+}
+
+impl ErrorHandler {
+    /// Set the channel to create messages in from its raw ID
+    ///
+    /// Same as [`Self::channel`] but without making the [`Id`] first
+    ///
+    /// # Errors
+    /// Returns [`ConfigError::ZeroId`] if the ID is 0
+    pub fn channel_id(&mut self, channel_id: u64) -> Result<&mut Self, ConfigError> {
+        let id = Id::new_checked(channel_id).ok_or(ConfigError::ZeroId)?;
+        Ok(self.channel(id))
+    }
+
+    /// Set the owner to DM from their raw user ID
+    ///
+    /// Same as [`Self::owner`] but without making the [`Id`] first
+    ///
+    /// # Errors
+    /// Returns [`ConfigError::ZeroId`] if the ID is 0
+    pub fn owner_id(&mut self, user_id: u64) -> Result<&mut Self, ConfigError> {
+        let id = Id::new_checked(user_id).ok_or(ConfigError::ZeroId)?;
+        Ok(self.owner(id))
+    }
+
+    /// Set the webhook to execute from its raw ID and token
+    ///
+    /// Same as [`Self::webhook`] but without making the [`Id`] first, see
+    /// [`WebhookSpec`] to parse them from one string
+    ///
+    /// # Errors
+    /// Returns [`ConfigError::ZeroId`] if the ID is 0 and
+    /// [`ConfigError::InvalidWebhookSpec`] if the token is empty
+    pub fn webhook_id(&mut self, webhook_id: u64, token: &str) -> Result<&mut Self, ConfigError> {
+        let id = Id::new_checked(webhook_id).ok_or(ConfigError::ZeroId)?;
+        if token.is_empty() {
+            return Err(ConfigError::InvalidWebhookSpec(format!("{webhook_id}/")));
+        }
+        Ok(self.webhook(id, token.to_owned()))
+    }
+}
@@ -0,0 +1,120 @@
+//! Removing secrets and personal information from error messages before
+//! they're sent anywhere
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::{config::Config, ErrorHandler};
+
+/// The text secrets in error messages are replaced with
+pub const REDACTED: &str = "[redacted]";
+
+/// The patterns of secrets that are always redacted if
+/// [`crate::ErrorHandler::redact_secrets`] is set
+#[allow(clippy::unwrap_used)]
+static SECRET_PATTERNS: LazyLock<[Regex; 3]> = LazyLock::new(|| {
+    [
+        // bot tokens
+        Regex::new(r"[A-Za-z0-9_-]{23,28}\.[A-Za-z0-9_-]{6,7}\.[A-Za-z0-9_-]{27,}").unwrap(),
+        // webhook URLs
+        Regex::new(r"(?i)(webhooks/\d+/)[A-Za-z0-9_-]+").unwrap(),
+        // authorization headers
+        Regex::new(
+            r"(?i)(authorization[\x22']?\s*[:=]\s*[\x22']?)(bot |bearer |basic )?[^\s\x22',]+",
+        )
+        .unwrap(),
+    ]
+});
+
+/// The pattern of email addresses for [`crate::ErrorHandler::redact_pii`]
+#[allow(clippy::unwrap_used)]
+static EMAIL_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}").unwrap());
+
+/// The pattern of IPv4 and IPv6 addresses for
+/// [`crate::ErrorHandler::redact_pii`]
+#[allow(clippy::unwrap_used)]
+static IP_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"\b(?:\d{1,3}\.){3}\d{1,3}\b|\b(?:[0-9A-Fa-f]{1,4}:){7}[0-9A-Fa-f]{1,4}\b|\b(?:[0-9A-Fa-f]{1,4}:){1,6}:[0-9A-Fa-f]{0,4}\b",
+    )
+    .unwrap()
+});
+
+impl ErrorHandler {
+    /// Add a rule to replace the matches of the pattern in error messages
+    ///
+    /// The replacement can refer to the groups in the pattern, such as `$1`,
+    /// see [`Regex::replace_all`], rules are applied in the order they're
+    /// added and regardless of [`Self::redact_secrets`], which makes them
+    /// suitable for keeping personal information such as user content out of
+    /// the reports
+    pub fn redact_with(&mut self, pattern: Regex, replacement: impl Into<String>) -> &mut Self {
+        self.config_mut()
+            .redaction_rules
+            .push((pattern, replacement.into()));
+        self
+    }
+
+    /// Add a pattern to replace with [`REDACTED`] in error messages
+    ///
+    /// Same as [`Self::redact_with`] with [`REDACTED`] as the replacement
+    pub fn redact(&mut self, pattern: Regex) -> &mut Self {
+        self.redact_with(pattern, REDACTED)
+    }
+
+    /// Replace email addresses with `[email]` and IP addresses with `[ip]` in
+    /// error messages
+    pub fn redact_pii(&mut self) -> &mut Self {
+        self.redact_with(EMAIL_PATTERN.clone(), "[email]")
+            .redact_with(IP_PATTERN.clone(), "[ip]")
+    }
+
+    /// Replace the rules added with [`Self::redact_with`] at runtime
+    ///
+    /// Each rule is a pattern and its replacement
+    pub fn set_redaction_rules(&self, rules: Vec<(Regex, String)>) {
+        self.update_config(|config| config.redaction_rules = rules);
+    }
+}
+
+impl Config {
+    /// Returns the text with secrets replaced with [`REDACTED`]
+    ///
+    /// The bot token, the webhook token and [`SECRET_PATTERNS`] are redacted if
+    /// `redact_secrets` is set, the redaction rules are always applied
+    pub(crate) fn redact(&self, text: &str, bot_token: Option<&str>) -> String {
+        let mut redacted = text.to_owned();
+
+        if self.redact_secrets {
+            #[cfg(feature = "discord")]
+            let webhook_token = self.webhook.as_ref().map(|(_, token)| token.as_str());
+            #[cfg(not(feature = "discord"))]
+            let webhook_token = None;
+            let raw_bot_token = bot_token.map(|token| token.trim_start_matches("Bot "));
+            for secret in [bot_token, raw_bot_token, webhook_token]
+                .into_iter()
+                .flatten()
+            {
+                if !secret.is_empty() {
+                    redacted = redacted.replace(secret, REDACTED);
+                }
+            }
+
+            for pattern in SECRET_PATTERNS.iter() {
+                redacted = pattern
+                    .replace_all(&redacted, format!("${{1}}{REDACTED}"))
+                    .into_owned();
+            }
+        }
+
+        for (pattern, replacement) in &self.redaction_rules {
+            redacted = pattern
+                .replace_all(&redacted, replacement.as_str())
+                .into_owned();
+        }
+
+        redacted
+    }
+}
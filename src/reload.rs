@@ -0,0 +1,84 @@
+//! Applying changes to the configuration file while the bot is running
+
+use core::time::Duration;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use tokio::{task::JoinHandle, time};
+use twilight_http::Client;
+
+use crate::{formatter::Message, ConfigFormat, DiagnosticEvent, ErrorHandler, ErrorReport};
+
+impl ErrorHandler {
+    /// Check the configuration file at the given path for changes every
+    /// `interval`, applying them with [`Self::apply_config`]
+    ///
+    /// Reloads are announced through the sinks of the new configuration, and
+    /// failing to read or apply the file is handled as an error with the
+    /// previous configuration, which is then kept
+    ///
+    /// Abort the returned handle to stop watching the file
+    ///
+    /// # Panics
+    /// If called outside of a Tokio runtime
+    pub fn watch_config(
+        self: Arc<Self>,
+        http: Arc<Client>,
+        path: impl Into<PathBuf>,
+        format: ConfigFormat,
+        interval: Duration,
+    ) -> JoinHandle<()> {
+        let config_path = path.into();
+        tokio::spawn(async move {
+            let mut last_modified = modified(&config_path);
+            let mut ticks = time::interval(interval);
+            ticks.tick().await;
+
+            loop {
+                ticks.tick().await;
+
+                let modified = modified(&config_path);
+                if modified == last_modified {
+                    continue;
+                }
+                last_modified = modified;
+
+                match format
+                    .read(&config_path)
+                    .and_then(|config| self.apply_config(config))
+                {
+                    Ok(()) => {
+                        self.diagnose(&DiagnosticEvent::ConfigReloaded);
+                        let config = self.config();
+                        let notice = format!(
+                            "\n\nReloaded the configuration from `{}`",
+                            config_path.display()
+                        );
+                        self.report(&http, &config, Message::Notice(&notice), &[])
+                            .await;
+                    }
+                    Err(err) => {
+                        self.diagnose(&DiagnosticEvent::ConfigReloadFailed(err.to_string()));
+                        let report = ErrorReport::new(format_args!(
+                            "Failed to reload the configuration from `{}`: {err}",
+                            config_path.display()
+                        ))
+                        .without_location();
+                        self.handle_report(&http, report).await;
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Returns when the file was last modified, `None` if it couldn't be read
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
@@ -0,0 +1,244 @@
+//! Errors to handle along with information about them
+
+use core::{any, fmt::Display, panic::Location};
+use std::time::SystemTime;
+
+#[cfg(feature = "eyre")]
+use crate::color;
+#[cfg(feature = "tracing")]
+use crate::layer::current_span_fields;
+#[cfg(feature = "discord")]
+use crate::TwilightErrorKind;
+use crate::{
+    backtrace, config::Config, location, metadata, snapshot, ErrorId, ErrorRecord, Severity,
+    LOCATION_FIELD,
+};
+
+/// An error to handle with [`crate::ErrorHandler::handle_report`], along with
+/// information about it
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct ErrorReport {
+    /// The displayed error
+    pub(crate) error: String,
+    /// How serious the error is
+    pub(crate) severity: Severity,
+    /// The type of the error
+    pub(crate) kind: Option<String>,
+    /// The tags the error is routed by
+    pub(crate) tags: Vec<String>,
+    /// The ID to join the report with the application's own logs
+    pub(crate) correlation_id: Option<String>,
+    /// The names and values of the information about the error
+    pub(crate) fields: Vec<(String, String)>,
+    /// Where the error was reported from, in the form of `file:line`
+    pub(crate) location: Option<String>,
+    /// The kind of the Twilight error, whose severity it's handled with
+    #[cfg(feature = "discord")]
+    pub(crate) twilight_kind: Option<TwilightErrorKind>,
+    /// The displayed span trace of where the error was handled
+    #[cfg(feature = "tracing-error")]
+    pub(crate) span_trace: Option<String>,
+}
+
+impl ErrorReport {
+    /// Make a report of the error with [`Severity::Error`]
+    #[track_caller]
+    pub fn new(error: impl Display) -> Self {
+        Self {
+            error: error.to_string(),
+            severity: Severity::Error,
+            kind: None,
+            tags: Vec::new(),
+            correlation_id: None,
+            fields: Vec::new(),
+            location: Some(location::format(Location::caller())),
+            #[cfg(feature = "discord")]
+            twilight_kind: None,
+            #[cfg(feature = "tracing-error")]
+            span_trace: None,
+        }
+    }
+
+    /// Make a report of the error with [`Severity::Error`], setting its kind
+    /// to the name of its type, such as `std::io::Error`
+    #[track_caller]
+    pub fn typed<E: Display>(error: E) -> Self {
+        Self::new(&error).kind(any::type_name::<E>())
+    }
+
+    /// Make a report of the `anyhow` error with [`Severity::Error`], including
+    /// its chain of contexts and its backtrace if it was captured
+    #[cfg(feature = "anyhow")]
+    #[allow(clippy::use_debug)]
+    #[track_caller]
+    pub fn anyhow(error: &anyhow::Error) -> Self {
+        Self::new(format_args!("{error:?}"))
+    }
+
+    /// Make a report of the `eyre` report with [`Severity::Error`], including
+    /// its chain of causes and sections such as suggestions and span traces
+    ///
+    /// Colors added by hooks such as `color-eyre` are removed
+    #[cfg(feature = "eyre")]
+    #[allow(clippy::use_debug)]
+    #[track_caller]
+    pub fn eyre(report: &eyre::Report) -> Self {
+        let rendered = format!("{report:?}");
+        Self::new(color::strip(&rendered))
+    }
+
+    /// Set how serious the error is, overriding the severity of its
+    /// [`crate::TwilightErrorKind`]
+    pub const fn severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        #[cfg(feature = "discord")]
+        {
+            self.twilight_kind = None;
+        };
+        self
+    }
+
+    /// Set the kind of the error, such as the name of its type or a category
+    /// to group errors by
+    ///
+    /// It's included in every output, recognizing the kind of an error is
+    /// quicker than reading its message
+    pub fn kind(mut self, kind: impl Into<String>) -> Self {
+        self.kind = Some(kind.into());
+        self
+    }
+
+    /// Tag the error, such as with the parts of the application it happened
+    /// in
+    ///
+    /// The tags are included in every output and the error is handled with
+    /// the routes of its tags, see [`crate::ErrorHandler::tag_route`]
+    pub fn tags(mut self, tags: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.tags.extend(tags.into_iter().map(Into::into));
+        self
+    }
+
+    /// Attach an existing ID to the error, such as the ID of the request or
+    /// interaction it happened in
+    ///
+    /// It's included in every output along with the [`ErrorId`], so that the
+    /// report can be joined with the application's tracing on one identifier
+    pub fn correlation_id(mut self, correlation_id: impl Into<String>) -> Self {
+        self.correlation_id = Some(correlation_id.into());
+        self
+    }
+
+    /// Attach a field of information to the error, such as the guild or
+    /// command it happened in
+    ///
+    /// Fields are included in every output, a field with the same name as a
+    /// previous one replaces it
+    pub fn field(mut self, name: impl Into<String>, value: impl Display) -> Self {
+        let field_name = name.into();
+        self.fields.retain(|(existing, _)| *existing != field_name);
+        self.fields.push((field_name, value.to_string()));
+        self
+    }
+
+    /// Attach the fields that aren't set yet
+    #[cfg(feature = "tracing")]
+    pub(crate) fn fallback_fields(mut self, fields: Vec<(String, String)>) -> Self {
+        for (name, value) in fields {
+            if self.fields.iter().all(|(existing, _)| *existing != name) {
+                self.fields.push((name, value));
+            }
+        }
+        self
+    }
+
+    /// Returns the record of the report handled with the given ID, with the
+    /// secrets redacted
+    ///
+    /// The fields of the current span are attached if the `tracing` feature
+    /// is enabled, see [`crate::ErrorLayer`], followed by the host and process,
+    /// see [`crate::ErrorHandler::include_metadata`], and the state of the
+    /// process if it's critical, see [`crate::ErrorHandler::snapshot_critical`],
+    /// with the uptime field of the handler if it's given
+    pub(crate) fn into_record(
+        mut self,
+        id: ErrorId,
+        config: &Config,
+        bot_token: Option<&str>,
+        uptime: Option<(String, String)>,
+    ) -> ErrorRecord {
+        #[cfg(feature = "tracing")]
+        {
+            self = self.fallback_fields(current_span_fields());
+        };
+        #[cfg(feature = "discord")]
+        if let Some(kind) = self.twilight_kind {
+            self.severity = config.twilight_severity(kind);
+        }
+        if let Some(location) = self.location.take().filter(|_| config.include_location) {
+            self.fields.push((LOCATION_FIELD.to_owned(), location));
+        }
+        self.fields.extend(config.context_providers.fields());
+        if config.include_metadata {
+            self.fields.extend(metadata::fields(config));
+        }
+        if self.severity == Severity::Critical && config.snapshot_critical {
+            self.fields
+                .extend(snapshot::fields(uptime.filter(|_| !config.include_uptime)));
+        }
+        #[cfg(feature = "tracing-error")]
+        if config.capture_span_traces {
+            self = self.span_trace();
+        }
+        #[cfg(feature = "tracing-error")]
+        if let Some(span_trace) = &self.span_trace {
+            self.error = format!("{}\n\nSpan trace:\n{span_trace}", self.error);
+        }
+
+        let severity = config
+            .tag_routes(&self.tags)
+            .filter_map(|route| route.severity)
+            .max()
+            .unwrap_or(self.severity);
+
+        ErrorRecord {
+            id,
+            severity,
+            error: config.redact(&self.error, bot_token),
+            kind: self.kind,
+            tags: self.tags,
+            correlation_id: self
+                .correlation_id
+                .map(|correlation_id| config.redact(&correlation_id, bot_token)),
+            fields: self
+                .fields
+                .into_iter()
+                .map(|(name, value)| (name, config.redact(&value, bot_token)))
+                .collect(),
+            backtrace: config
+                .capture_backtraces
+                .then(backtrace::capture)
+                .flatten()
+                .map(|backtrace| config.redact(&backtrace, bot_token)),
+            handled_at: SystemTime::now(),
+            #[cfg(feature = "discord")]
+            mentions: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "anyhow")]
+impl From<anyhow::Error> for ErrorReport {
+    #[track_caller]
+    fn from(error: anyhow::Error) -> Self {
+        Self::anyhow(&error)
+    }
+}
+
+#[cfg(feature = "eyre")]
+impl From<eyre::Report> for ErrorReport {
+    #[track_caller]
+    fn from(report: eyre::Report) -> Self {
+        Self::eyre(&report)
+    }
+}
@@ -0,0 +1,142 @@
+//! Queueing errors to be handled by a separate task
+
+use core::{error::Error, fmt};
+use std::sync::Arc;
+
+use tokio::{
+    sync::mpsc::{self, error::TrySendError, Sender},
+    task::JoinHandle,
+};
+use twilight_http::Client;
+
+use crate::{DiagnosticEvent, DropReason, ErrorHandler, ErrorReport};
+
+/// The reason a report couldn't be queued, with the report to handle it
+/// another way
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum QueueError {
+    /// The queue is full, only returned from [`Reporter::try_report`]
+    QueueFull(Box<ErrorReport>),
+    /// The task handling the queue stopped, such as if it was aborted
+    Closed(Box<ErrorReport>),
+}
+
+impl QueueError {
+    /// Returns the report that couldn't be queued
+    pub fn into_report(self) -> ErrorReport {
+        match self {
+            Self::QueueFull(report) | Self::Closed(report) => *report,
+        }
+    }
+}
+
+impl fmt::Display for QueueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::QueueFull(_) => f.write_str("the error queue is full"),
+            Self::Closed(_) => f.write_str("the error queue is closed"),
+        }
+    }
+}
+
+impl Error for QueueError {}
+
+/// A handle to queue errors to be handled by the task made with
+/// [`ErrorHandler::spawn_reporter`], cheap to clone
+///
+/// The task ends when every handle is dropped, after handling the queued
+/// errors
+#[derive(Clone)]
+pub struct Reporter {
+    /// The sender of the queue
+    sender: Sender<ErrorReport>,
+    /// The handler, to report diagnostic events
+    handler: Arc<ErrorHandler>,
+}
+
+impl Reporter {
+    /// Queue the report without waiting, returning
+    /// [`QueueError::QueueFull`] if the queue is full
+    ///
+    /// Use this in latency-sensitive code such as event handlers, the report
+    /// can be handled another way if it's full, such as with
+    /// [`ErrorHandler::handle_report_sync`]
+    ///
+    /// # Errors
+    /// Returns the error with the report if it couldn't be queued
+    pub fn try_report(&self, report: ErrorReport) -> Result<(), QueueError> {
+        self.handler.pending.add();
+        self.sender.try_send(report).map_err(|err| {
+            self.handler.pending.done();
+            match err {
+                TrySendError::Full(full_report) => {
+                    self.handler.diagnose(&DiagnosticEvent::QueueFull);
+                    self.handler.record_dropped(DropReason::QueueFull);
+                    QueueError::QueueFull(Box::new(full_report))
+                }
+                TrySendError::Closed(closed_report) => QueueError::Closed(Box::new(closed_report)),
+            }
+        })
+    }
+
+    /// Queue the report, waiting for space in the queue if it's full
+    ///
+    /// # Errors
+    /// Returns [`QueueError::Closed`] with the report if the task handling
+    /// the queue stopped
+    pub async fn report(&self, report: ErrorReport) -> Result<(), QueueError> {
+        self.handler.pending.add();
+        self.sender.send(report).await.map_err(|err| {
+            self.handler.pending.done();
+            QueueError::Closed(Box::new(err.0))
+        })
+    }
+
+    /// Returns the number of reports waiting in the queue
+    #[must_use]
+    pub fn queued(&self) -> usize {
+        self.sender
+            .max_capacity()
+            .saturating_sub(self.sender.capacity())
+    }
+}
+
+impl fmt::Debug for Reporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Reporter")
+            .field("queued", &self.queued())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ErrorHandler {
+    /// Spawn a Tokio task that handles the errors queued with the returned
+    /// [`Reporter`] one at a time, queueing at most `capacity` of them
+    ///
+    /// This lets the code that reports errors decide what to do when errors
+    /// are handled slower than they happen, such as during a Discord outage
+    ///
+    /// # Panics
+    /// Panics if `capacity` is 0
+    pub fn spawn_reporter(
+        self: Arc<Self>,
+        http: Arc<Client>,
+        capacity: usize,
+    ) -> (Reporter, JoinHandle<()>) {
+        let (sender, mut receiver) = mpsc::channel(capacity);
+        let reporter = Reporter {
+            sender,
+            handler: Arc::clone(&self),
+        };
+
+        let join_handle = tokio::spawn(async move {
+            while let Some(report) = receiver.recv().await {
+                self.handle_report(&http, report).await;
+                self.pending.done();
+            }
+        });
+
+        (reporter, join_handle)
+    }
+}
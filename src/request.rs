@@ -0,0 +1,55 @@
+//! Sending requests that report their failures
+
+use twilight_http::{request::TryIntoRequest, response::Response, Client, Error as HttpError};
+
+use crate::{ErrorHandler, ErrorReport};
+
+impl ErrorHandler {
+    /// Send the request, handling the error if it fails
+    ///
+    /// The error is handled along with the method and route of the request
+    /// and the details of the response if there is one, see
+    /// [`ErrorReport::from_error`], the result is returned as is
+    ///
+    /// ```ignore
+    /// let message = handler
+    ///     .send::<Message>(&http, http.create_message(channel_id).content("Pong!")?)
+    ///     .await?
+    ///     .model()
+    ///     .await?;
+    /// ```
+    ///
+    /// # Errors
+    /// Returns [`HttpError`] if building or sending the request fails
+    pub async fn send<T: Unpin>(
+        &self,
+        http: &Client,
+        request: impl TryIntoRequest + Send,
+    ) -> Result<Response<T>, HttpError> {
+        let raw_request = match request.try_into_request() {
+            Ok(raw_request) => raw_request,
+            Err(err) => {
+                let report = ErrorReport::new(format_args!("Failed to build request: {err}"))
+                    .without_location();
+                self.handle_report(http, report).await;
+                return Err(err);
+            }
+        };
+
+        let method = raw_request.method();
+        let path = raw_request.path().to_owned();
+        let result = http.request::<T>(raw_request).await;
+
+        if let Err(err) = &result {
+            self.handle_report(
+                http,
+                ErrorReport::from_error(err)
+                    .request_route(method, path)
+                    .without_location(),
+            )
+            .await;
+        }
+
+        result
+    }
+}
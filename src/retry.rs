@@ -0,0 +1,204 @@
+//! How the network sinks retry failed deliveries
+
+use core::{
+    error::Error,
+    fmt,
+    hash::{BuildHasher as _, Hasher as _},
+    time::Duration,
+};
+use std::{hash::RandomState, sync::Arc};
+
+use crate::ErrorHandler;
+
+/// Returns whether to retry the error
+type Retryable = Arc<dyn Fn(&(dyn Error + 'static)) -> bool + Send + Sync>;
+
+/// How a network sink retries failed deliveries, set with
+/// [`ErrorHandler::retry_policy`]
+///
+/// Defaults to 3 attempts, waiting a second before the first retry and
+/// doubling the wait after each one up to 30 seconds, without jitter
+///
+/// Errors that won't succeed on a retry, such as missing permissions or
+/// invalid requests, are never retried
+#[derive(Clone)]
+#[must_use]
+pub struct RetryPolicy {
+    /// The most times the delivery is attempted, including the first one
+    max_attempts: u32,
+    /// The wait before the first retry
+    base_delay: Duration,
+    /// The factor the wait is multiplied by after each retry
+    multiplier: f64,
+    /// The fraction of each wait that's randomly removed
+    jitter: f64,
+    /// The longest wait between two attempts
+    max_delay: Duration,
+    /// The duration after which the delivery isn't retried anymore
+    deadline: Option<Duration>,
+    /// Returns whether to retry the error, every error the sink can retry if
+    /// it's `None`
+    retryable: Option<Retryable>,
+}
+
+impl RetryPolicy {
+    /// Make the default policy
+    pub const fn new() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_secs(1),
+            multiplier: 2.0_f64,
+            jitter: 0.0_f64,
+            max_delay: Duration::from_secs(30),
+            deadline: None,
+            retryable: None,
+        }
+    }
+
+    /// Returns the policy of [`crate::DeliverySemantics::RetryUntil`], which
+    /// retries until the duration passes
+    #[cfg(feature = "discord")]
+    pub(crate) const fn until(deadline: Duration) -> Self {
+        Self {
+            max_attempts: u32::MAX,
+            base_delay: Duration::from_secs(1),
+            multiplier: 2.0_f64,
+            jitter: 0.0_f64,
+            max_delay: Duration::MAX,
+            deadline: Some(deadline),
+            retryable: None,
+        }
+    }
+
+    /// Set the most times the delivery is attempted, including the first one,
+    /// 1 to never retry
+    pub const fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts;
+        self
+    }
+
+    /// Set the wait before the first retry
+    pub const fn base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    /// Set the factor the wait is multiplied by after each retry, 1 for a
+    /// constant wait, it's at least 1
+    pub const fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Set the fraction of each wait between 0 and 1 that's randomly removed,
+    /// so that the bot processes failing together don't retry together
+    pub const fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Set the longest wait between two attempts
+    pub const fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Only retry the errors the predicate returns `true` for, among the ones
+    /// the sink can retry
+    ///
+    /// The error is a [`twilight_http::Error`] for the Discord sinks and a
+    /// [`std::io::Error`] for the others, such as in
+    /// `policy.retry_if(|err| err.downcast_ref::<std::io::Error>().is_some())`
+    pub fn retry_if(
+        mut self,
+        retryable: impl Fn(&(dyn Error + 'static)) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.retryable = Some(Arc::new(retryable));
+        self
+    }
+
+    /// Returns whether the delivery can be attempted again after the attempts
+    pub(crate) const fn allows(&self, attempts: u32) -> bool {
+        attempts < self.max_attempts
+    }
+
+    /// Returns the duration after which the delivery isn't retried anymore
+    pub(crate) const fn deadline(&self) -> Option<Duration> {
+        self.deadline
+    }
+
+    /// Returns whether the predicate allows retrying the error
+    pub(crate) fn retries(&self, error: &(dyn Error + 'static)) -> bool {
+        self.retryable
+            .as_ref()
+            .is_none_or(|retryable| retryable(error))
+    }
+
+    /// Returns the wait before the first retry
+    pub(crate) fn first_delay(&self) -> Duration {
+        self.base_delay.min(self.max_delay)
+    }
+
+    /// Returns the wait after the delay
+    #[allow(clippy::float_arithmetic)]
+    pub(crate) fn next_delay(&self, delay: Duration) -> Duration {
+        Duration::try_from_secs_f64(delay.as_secs_f64() * self.multiplier.max(1.0_f64))
+            .unwrap_or(Duration::MAX)
+            .min(self.max_delay)
+    }
+
+    /// Returns the delay with a random fraction of it removed according to
+    /// the jitter
+    #[allow(clippy::float_arithmetic)]
+    pub(crate) fn jittered(&self, delay: Duration) -> Duration {
+        if self.jitter <= 0.0_f64 {
+            return delay;
+        }
+
+        let random =
+            u32::try_from(RandomState::new().build_hasher().finish() >> 32_u32).unwrap_or(u32::MAX);
+        let removed = self.jitter.min(1.0_f64) * f64::from(random) / f64::from(u32::MAX);
+        Duration::try_from_secs_f64(delay.as_secs_f64() * (1.0_f64 - removed)).unwrap_or(delay)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("base_delay", &self.base_delay)
+            .field("multiplier", &self.multiplier)
+            .field("jitter", &self.jitter)
+            .field("max_delay", &self.max_delay)
+            .field("deadline", &self.deadline)
+            .field("retryable", &self.retryable.is_some())
+            .finish()
+    }
+}
+
+impl ErrorHandler {
+    /// Set how the network sinks retry failed deliveries, the channel,
+    /// webhook, NATS, HTTP endpoint and AWS log stream sinks, they're
+    /// delivered to once by default
+    ///
+    /// The Discord sinks with [`crate::DeliverySemantics::RetryUntil`] retry
+    /// until its duration passes instead
+    pub fn retry_policy(&mut self, policy: RetryPolicy) -> &mut Self {
+        self.config_mut().retry_policy = Some(policy);
+        self
+    }
+
+    /// Change how the network sinks retry failed deliveries at runtime,
+    /// `None` to deliver to them once
+    ///
+    /// Same as [`Self::retry_policy`] but through a shared reference
+    pub fn set_retry_policy(&self, policy: Option<RetryPolicy>) {
+        self.update_config(|config| config.retry_policy = policy);
+    }
+}
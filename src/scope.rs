@@ -0,0 +1,143 @@
+//! Reporting the errors of a part of the application with its own label and
+//! routing
+
+use core::fmt::{self, Display};
+#[cfg(feature = "discord")]
+use core::future::Future;
+use std::sync::Arc;
+
+#[cfg(feature = "discord")]
+use twilight_http::Client;
+#[cfg(feature = "discord")]
+use twilight_model::id::{marker::WebhookMarker, Id};
+
+use crate::{ErrorHandler, ErrorId, ErrorReport};
+
+/// The name of the field the scope is attached as
+pub const SCOPE_FIELD: &str = "Scope";
+
+/// A handler for a part of the application, made with
+/// [`ErrorHandler::scoped`]
+///
+/// It uses the configuration of the handler it's made from, so changing it at
+/// runtime also changes the scoped handlers, every report is labeled with the
+/// scope as the [`SCOPE_FIELD`] field
+///
+/// ```ignore
+/// let music = Arc::clone(&handler)
+///     .scoped("music")
+///     .webhook(music_webhook_id, music_webhook_token);
+///
+/// music.handle(&http, "the player disconnected").await;
+/// ```
+#[derive(Clone)]
+#[must_use]
+pub struct ScopedHandler {
+    /// The handler the errors are handled with
+    handler: Arc<ErrorHandler>,
+    /// The name of the scope
+    scope: String,
+    /// The webhooks the errors of the scope are also delivered to
+    #[cfg(feature = "discord")]
+    webhooks: Vec<(Id<WebhookMarker>, String)>,
+}
+
+impl ScopedHandler {
+    /// Also deliver the errors of the scope to the webhook, such as the
+    /// webhook of the team responsible for it
+    ///
+    /// It's delivered in addition to the sinks of the handler, with the
+    /// webhook's formatter
+    #[cfg(feature = "discord")]
+    pub fn webhook(mut self, webhook_id: Id<WebhookMarker>, token: String) -> Self {
+        self.webhooks.push((webhook_id, token));
+        self
+    }
+
+    /// Make a handler for a part of this scope, labeled with both scopes
+    /// separated by a `/`, also delivering to the webhooks of this scope
+    pub fn scoped(&self, scope: impl Display) -> Self {
+        Self {
+            scope: format!("{}/{scope}", self.scope),
+            ..self.clone()
+        }
+    }
+
+    /// Returns the name of the scope
+    #[must_use]
+    pub fn scope(&self) -> &str {
+        &self.scope
+    }
+
+    /// Returns the handler the errors are handled with
+    #[must_use]
+    pub const fn handler(&self) -> &Arc<ErrorHandler> {
+        &self.handler
+    }
+
+    /// Handle an error of the scope
+    ///
+    /// Same as [`ErrorHandler::handle`] but labeled with the scope and also
+    /// delivered to the webhooks of the scope
+    #[cfg(feature = "discord")]
+    #[track_caller]
+    pub fn handle<'a>(
+        &'a self,
+        http: &'a Client,
+        error: impl Display + Send,
+    ) -> impl Future<Output = ErrorId> + Send + 'a {
+        self.handle_report(http, ErrorReport::new(error))
+    }
+
+    /// Handle an error of the scope along with information about it
+    ///
+    /// Same as [`Self::handle`] but with the severity and information set in
+    /// the report
+    #[cfg(feature = "discord")]
+    pub async fn handle_report(&self, http: &Client, report: ErrorReport) -> ErrorId {
+        self.handler
+            .handle_report_routed(http, report.field(SCOPE_FIELD, &self.scope), &self.webhooks)
+            .await
+    }
+
+    /// Handle an error of the scope, ignoring the Discord sinks
+    ///
+    /// Same as [`ErrorHandler::handle_sync`] but labeled with the scope
+    #[track_caller]
+    pub fn handle_sync(&self, error: impl Display) -> ErrorId {
+        self.handle_report_sync(ErrorReport::new(error))
+    }
+
+    /// Handle an error of the scope along with information about it, ignoring
+    /// the Discord sinks
+    ///
+    /// Same as [`Self::handle_sync`] but with the severity and information set
+    /// in the report
+    #[allow(clippy::must_use_candidate)]
+    pub fn handle_report_sync(&self, report: ErrorReport) -> ErrorId {
+        self.handler
+            .handle_report_sync(report.field(SCOPE_FIELD, &self.scope))
+    }
+}
+
+impl fmt::Debug for ScopedHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScopedHandler")
+            .field("scope", &self.scope)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ErrorHandler {
+    /// Make a handler for a part of the application, such as `music`, that
+    /// labels its errors with the scope and can deliver them to its own
+    /// webhooks, see [`ScopedHandler`]
+    pub fn scoped(self: Arc<Self>, scope: impl Into<String>) -> ScopedHandler {
+        ScopedHandler {
+            handler: self,
+            scope: scope.into(),
+            #[cfg(feature = "discord")]
+            webhooks: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,43 @@
+//! How serious a handled error is
+
+use core::fmt;
+
+/// How serious an error is, ordered from the least to the most serious
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "lowercase")
+)]
+#[non_exhaustive]
+pub enum Severity {
+    /// Something unexpected happened but the bot can carry on as normal
+    Warning,
+    /// An operation failed, the default for [`crate::ErrorHandler::handle`]
+    #[default]
+    Error,
+    /// The bot can't function properly until this is fixed
+    Critical,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+            Self::Critical => "critical",
+        })
+    }
+}
+
+impl Severity {
+    /// Returns the severity displayed as the given name, ignoring its case
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "warning" => Some(Self::Warning),
+            "error" => Some(Self::Error),
+            "critical" => Some(Self::Critical),
+            _ => None,
+        }
+    }
+}
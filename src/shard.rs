@@ -0,0 +1,183 @@
+//! Reporting the errors of a gateway shard labeled with its shard and cluster
+
+use core::{
+    fmt::{self, Display},
+    future::Future,
+};
+use std::sync::Arc;
+
+use twilight_http::Client;
+use twilight_model::gateway::event::Event;
+
+use crate::{gateway::receive_error_report, ErrorHandler, ErrorId, ErrorReport};
+
+/// The name of the field the shard ID is attached as
+pub const SHARD_FIELD: &str = "Shard";
+
+/// The name of the field the cluster ID is attached as
+pub const CLUSTER_FIELD: &str = "Cluster";
+
+/// A reporter for the errors of a gateway shard, made with
+/// [`ErrorHandler::shard_reporter`]
+///
+/// Every report is labeled with the shard ID as the [`SHARD_FIELD`] field and
+/// the cluster ID as the [`CLUSTER_FIELD`] field if it's set
+///
+/// ```ignore
+/// let reporter = Arc::clone(&handler).shard_reporter(shard.id().number()).cluster(1);
+///
+/// loop {
+///     match reporter
+///         .next_event(&http, shard.next_event(), ReceiveMessageError::is_fatal)
+///         .await
+///     {
+///         Ok(Some(event)) => process(event).await,
+///         Ok(None) => continue,
+///         Err(_) => break,
+///     }
+/// }
+/// ```
+#[derive(Clone)]
+#[must_use]
+pub struct ShardErrorReporter {
+    /// The handler the errors are handled with
+    handler: Arc<ErrorHandler>,
+    /// The ID of the shard
+    shard_id: u64,
+    /// The ID of the cluster the shard is in
+    cluster_id: Option<u64>,
+}
+
+impl ShardErrorReporter {
+    /// Also label the reports with the ID of the cluster the shard is in
+    pub const fn cluster(mut self, cluster_id: u64) -> Self {
+        self.cluster_id = Some(cluster_id);
+        self
+    }
+
+    /// Returns the ID of the shard
+    #[must_use]
+    pub const fn shard_id(&self) -> u64 {
+        self.shard_id
+    }
+
+    /// Returns the ID of the cluster the shard is in
+    #[must_use]
+    pub const fn cluster_id(&self) -> Option<u64> {
+        self.cluster_id
+    }
+
+    /// Returns the handler the errors are handled with
+    #[must_use]
+    pub const fn handler(&self) -> &Arc<ErrorHandler> {
+        &self.handler
+    }
+
+    /// Returns the report labeled with the shard and the cluster
+    fn label(&self, report: ErrorReport) -> ErrorReport {
+        let with_shard = report.field(SHARD_FIELD, self.shard_id);
+        match self.cluster_id {
+            Some(cluster_id) => with_shard.field(CLUSTER_FIELD, cluster_id),
+            None => with_shard,
+        }
+    }
+
+    /// Handle an error of the shard
+    ///
+    /// Same as [`ErrorHandler::handle`] but labeled with the shard and the
+    /// cluster
+    #[track_caller]
+    pub fn handle<'a>(
+        &'a self,
+        http: &'a Client,
+        error: impl Display + Send,
+    ) -> impl Future<Output = ErrorId> + Send + 'a {
+        self.handle_report(http, ErrorReport::new(error))
+    }
+
+    /// Handle an error of the shard along with information about it
+    ///
+    /// Same as [`Self::handle`] but with the severity and information set in
+    /// the report
+    pub async fn handle_report(&self, http: &Client, report: ErrorReport) -> ErrorId {
+        self.handler.handle_report(http, self.label(report)).await
+    }
+
+    /// Report the problems the event of the shard shows, call this with every
+    /// event the shard receives
+    ///
+    /// Same as [`ErrorHandler::handle_gateway_event`] but also labeled with
+    /// the cluster
+    pub async fn handle_event(&self, http: &Client, event: &Event) {
+        if let Some(report) = self.handler.gateway_report(self.shard_id, event) {
+            self.handle_report(http, self.label(report)).await;
+        }
+    }
+
+    /// Handle an error receiving a message of the shard
+    ///
+    /// Same as [`ErrorHandler::handle_receive_error`] but also labeled with
+    /// the cluster
+    pub async fn handle_receive_error(
+        &self,
+        http: &Client,
+        error: impl Display + Send,
+        fatal: bool,
+    ) -> ErrorId {
+        self.handle_report(http, receive_error_report(error, fatal))
+            .await
+    }
+
+    /// Receive the next event of the shard, reporting the error if receiving
+    /// fails and the problems the event shows otherwise
+    ///
+    /// Same as [`ErrorHandler::next_gateway_event`] but also labeled with the
+    /// cluster
+    ///
+    /// # Errors
+    /// Returns the error of receiving if it's fatal
+    pub async fn next_event<E: Display + Send>(
+        &self,
+        http: &Client,
+        receive: impl Future<Output = Result<Event, E>> + Send,
+        is_fatal: impl FnOnce(&E) -> bool + Send,
+    ) -> Result<Option<Event>, E> {
+        match receive.await {
+            Ok(event) => {
+                self.handle_event(http, &event).await;
+                Ok(Some(event))
+            }
+            Err(err) => {
+                let fatal = is_fatal(&err);
+                let report = receive_error_report(&err, fatal);
+                self.handle_report(http, report).await;
+                if fatal {
+                    Err(err)
+                } else {
+                    Ok(None)
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ShardErrorReporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShardErrorReporter")
+            .field("shard_id", &self.shard_id)
+            .field("cluster_id", &self.cluster_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ErrorHandler {
+    /// Make a reporter for the gateway shard with the ID that labels its
+    /// errors with it, see [`ShardErrorReporter`]
+    pub const fn shard_reporter(self: Arc<Self>, shard_id: u64) -> ShardErrorReporter {
+        ShardErrorReporter {
+            handler: self,
+            shard_id,
+            cluster_id: None,
+        }
+    }
+}
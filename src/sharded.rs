@@ -0,0 +1,48 @@
+//! Maps split into shards so that concurrent handlers rarely wait on each
+//! other
+
+use core::{
+    array,
+    hash::{BuildHasher as _, Hash},
+};
+use std::{
+    collections::HashMap,
+    hash::RandomState,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+/// The number of shards of each map
+const SHARDS: usize = 16;
+/// The bits of the hash of a key that choose its shard
+const SHARD_MASK: u64 = 0b1111;
+
+/// A map where only the shard of the key is locked
+#[derive(Debug)]
+pub(crate) struct ShardedMap<K, V> {
+    /// The shards of the map
+    shards: [Mutex<HashMap<K, V>>; SHARDS],
+    /// The hasher to choose the shard of a key with
+    hasher: RandomState,
+}
+
+impl<K: Hash, V> ShardedMap<K, V> {
+    /// Returns the locked shard of the key
+    #[allow(clippy::indexing_slicing)]
+    pub(crate) fn shard(&self, key: &K) -> MutexGuard<'_, HashMap<K, V>> {
+        // the mask keeps the index below the number of shards
+        let index = usize::try_from(self.hasher.hash_one(key) & SHARD_MASK).unwrap_or_default();
+
+        self.shards[index]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<K, V> Default for ShardedMap<K, V> {
+    fn default() -> Self {
+        Self {
+            shards: array::from_fn(|_| Mutex::new(HashMap::new())),
+            hasher: RandomState::new(),
+        }
+    }
+}
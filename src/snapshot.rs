@@ -0,0 +1,71 @@
+//! Attaching the state of the process to critical errors
+
+use std::fs;
+
+use tokio::runtime::Handle;
+
+use crate::ErrorHandler;
+
+/// Returns the uptime field if it's given, the resident memory and the Tokio
+/// runtime metrics as fields
+///
+/// The memory is only included on Linux and the runtime metrics only if
+/// there's a Tokio runtime
+pub(crate) fn fields(uptime: Option<(String, String)>) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+
+    fields.extend(uptime);
+
+    if let Some(memory) = resident_memory() {
+        fields.push(("Memory".to_owned(), memory));
+    }
+
+    if let Ok(runtime) = Handle::try_current() {
+        let metrics = runtime.metrics();
+        fields.extend([
+            (
+                "Tokio workers".to_owned(),
+                metrics.num_workers().to_string(),
+            ),
+            (
+                "Tokio tasks".to_owned(),
+                metrics.num_alive_tasks().to_string(),
+            ),
+            (
+                "Tokio queue depth".to_owned(),
+                metrics.global_queue_depth().to_string(),
+            ),
+        ]);
+    }
+
+    fields
+}
+
+/// Returns the resident set size of the process from `/proc/self/status`
+fn resident_memory() -> Option<String> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .map(|memory| memory.trim().to_owned())
+}
+
+impl ErrorHandler {
+    /// Set whether to attach the uptime, the resident memory and the Tokio
+    /// runtime metrics to [`crate::Severity::Critical`] errors
+    ///
+    /// The uptime is the same as with [`Self::include_uptime`] and left out if
+    /// that's set, the memory is only attached on Linux, defaults to `false`
+    pub fn snapshot_critical(&mut self, snapshot: bool) -> &mut Self {
+        self.config_mut().snapshot_critical = snapshot;
+        self
+    }
+
+    /// Change whether to attach the state of the process to critical errors
+    /// at runtime
+    ///
+    /// Same as [`Self::snapshot_critical`] but through a shared reference
+    pub fn set_snapshot_critical(&self, snapshot: bool) {
+        self.update_config(|config| config.snapshot_critical = snapshot);
+    }
+}
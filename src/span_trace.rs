@@ -0,0 +1,43 @@
+//! Including the span hierarchy the error was handled in
+
+use tracing_error::{SpanTrace, SpanTraceStatus};
+
+use crate::{ErrorHandler, ErrorReport};
+
+impl ErrorReport {
+    /// Capture the span trace of the current span to include in the report,
+    /// such as the command or event handler the error happened in
+    ///
+    /// Nothing is captured if there's no current span or the subscriber
+    /// doesn't have a [`tracing_error::ErrorLayer`], a span trace that was
+    /// already captured is kept
+    pub fn span_trace(mut self) -> Self {
+        if self.span_trace.is_none() {
+            let span_trace = SpanTrace::capture();
+            if span_trace.status() == SpanTraceStatus::CAPTURED {
+                self.span_trace = Some(span_trace.to_string());
+            }
+        }
+
+        self
+    }
+}
+
+impl ErrorHandler {
+    /// Set whether to include the span trace of where errors are handled
+    ///
+    /// This requires a [`tracing_error::ErrorLayer`] in the subscriber,
+    /// defaults to `false`, see [`ErrorReport::span_trace`]
+    pub fn capture_span_traces(&mut self, capture: bool) -> &mut Self {
+        self.config_mut().capture_span_traces = capture;
+        self
+    }
+
+    /// Change whether to include the span trace of where errors are handled at
+    /// runtime
+    ///
+    /// Same as [`Self::capture_span_traces`] but through a shared reference
+    pub fn set_capture_span_traces(&self, capture: bool) {
+        self.update_config(|config| config.capture_span_traces = capture);
+    }
+}
@@ -0,0 +1,377 @@
+//! Counting handled errors and the results of delivering them
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use tokio::time::Instant;
+#[cfg(feature = "discord")]
+use twilight_model::channel::message::{embed::EmbedField, Embed};
+
+use crate::{ErrorHandler, Severity, Sink};
+
+/// How many times delivering to a sink succeeded and failed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SinkStats {
+    /// The number of successful deliveries
+    pub successes: u64,
+    /// The number of failed deliveries
+    pub failures: u64,
+}
+
+/// The statistics of a handler, returned from [`ErrorHandler::stats`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Stats {
+    /// The number of errors handled, including the ones below the minimum
+    /// severity
+    pub total: u64,
+    /// The number of errors handled with [`Severity::Warning`]
+    pub warnings: u64,
+    /// The number of errors handled with [`Severity::Error`]
+    pub errors: u64,
+    /// The number of errors handled with [`Severity::Critical`]
+    pub critical: u64,
+    /// The number of errors handled in the last minute
+    pub last_minute: u64,
+    /// The number of errors handled in the last hour
+    pub last_hour: u64,
+    /// The delivery results of [`Sink::Channel`]
+    pub channel: SinkStats,
+    /// The delivery results of [`Sink::Webhook`]
+    pub webhook: SinkStats,
+    /// The delivery results of [`Sink::File`]
+    pub file: SinkStats,
+}
+
+impl Stats {
+    /// Returns the number of errors handled with the given severity
+    #[must_use]
+    pub const fn severity(&self, severity: Severity) -> u64 {
+        match severity {
+            Severity::Warning => self.warnings,
+            Severity::Error => self.errors,
+            Severity::Critical => self.critical,
+        }
+    }
+
+    /// Returns the delivery results of the given sink
+    #[must_use]
+    pub const fn sink(&self, sink: Sink) -> SinkStats {
+        match sink {
+            Sink::Channel => self.channel,
+            Sink::Webhook => self.webhook,
+            Sink::File => self.file,
+        }
+    }
+
+    /// Returns an embed showing the statistics, such as for a `/status`
+    /// command
+    #[cfg(feature = "discord")]
+    #[must_use]
+    pub fn as_embed(&self) -> Embed {
+        let field = |name: &str, value: String| EmbedField {
+            inline: true,
+            name: name.to_owned(),
+            value,
+        };
+        let sink_field = |sink: Sink| {
+            let stats = self.sink(sink);
+            field(
+                &format!("{sink} deliveries"),
+                format!("{} sent, {} failed", stats.successes, stats.failures),
+            )
+        };
+
+        Embed {
+            author: None,
+            color: None,
+            description: None,
+            fields: vec![
+                field("Total", self.total.to_string()),
+                field("Last minute", self.last_minute.to_string()),
+                field("Last hour", self.last_hour.to_string()),
+                field("Warnings", self.warnings.to_string()),
+                field("Errors", self.errors.to_string()),
+                field("Critical", self.critical.to_string()),
+                sink_field(Sink::Channel),
+                sink_field(Sink::Webhook),
+                sink_field(Sink::File),
+            ],
+            footer: None,
+            image: None,
+            kind: "rich".to_owned(),
+            provider: None,
+            thumbnail: None,
+            timestamp: None,
+            title: Some("Error statistics".to_owned()),
+            url: None,
+            video: None,
+        }
+    }
+}
+
+/// The number of intervals the recent errors are counted in for each window
+const BUCKETS: usize = 60;
+
+/// The number of errors handled in each of the last [`BUCKETS`] intervals
+///
+/// Each bucket packs the interval it counts in its upper 32 bits and the count
+/// in its lower 32 bits, so that it's reset and incremented in one atomic
+/// operation
+#[derive(Debug)]
+struct Buckets {
+    /// The buckets of the intervals, each used again [`BUCKETS`] intervals
+    /// later
+    buckets: [AtomicU64; BUCKETS],
+    /// The length of each interval in seconds
+    interval_secs: u64,
+}
+
+impl Buckets {
+    /// Make buckets of intervals with the given length, every count at zero
+    const fn new(interval_secs: u64) -> Self {
+        Self {
+            buckets: [const { AtomicU64::new(0) }; BUCKETS],
+            interval_secs,
+        }
+    }
+
+    /// Returns the bucket of the interval
+    fn bucket(&self, interval: u64) -> Option<&AtomicU64> {
+        let index = usize::try_from(interval).ok()?.checked_rem(BUCKETS)?;
+        self.buckets.get(index)
+    }
+
+    /// Records that an error was handled the given seconds after the recorder
+    /// was made
+    #[allow(unused_must_use)]
+    fn record(&self, elapsed_secs: u64) {
+        let interval = elapsed_secs
+            .checked_div(self.interval_secs)
+            .unwrap_or_default();
+        let tag = interval & u64::from(u32::MAX);
+        let Some(bucket) = self.bucket(interval) else {
+            return;
+        };
+
+        // fails without changing the bucket if its count is at the maximum
+        bucket.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |packed| {
+            if packed.wrapping_shr(32) != tag {
+                Some(tag.wrapping_shl(32) | 1)
+            } else if packed & u64::from(u32::MAX) == u64::from(u32::MAX) {
+                None
+            } else {
+                Some(packed.wrapping_add(1))
+            }
+        });
+    }
+
+    /// Returns the number of errors handled in the last [`BUCKETS`] intervals
+    fn sum(&self, elapsed_secs: u64) -> u64 {
+        let current = elapsed_secs
+            .checked_div(self.interval_secs)
+            .unwrap_or_default();
+
+        (0..BUCKETS)
+            .filter_map(|ago| current.checked_sub(u64::try_from(ago).ok()?))
+            .filter_map(|interval| {
+                let packed = self.bucket(interval)?.load(Ordering::Relaxed);
+                (packed.wrapping_shr(32) == interval & u64::from(u32::MAX))
+                    .then_some(packed & u64::from(u32::MAX))
+            })
+            .fold(0, u64::saturating_add)
+    }
+}
+
+/// The delivery counters of a sink
+#[derive(Debug)]
+struct SinkCounters {
+    /// The number of successful deliveries
+    successes: AtomicU64,
+    /// The number of failed deliveries
+    failures: AtomicU64,
+}
+
+impl SinkCounters {
+    /// Make counters at zero
+    const fn new() -> Self {
+        Self {
+            successes: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the current counts
+    fn get(&self) -> SinkStats {
+        SinkStats {
+            successes: self.successes.load(Ordering::Relaxed),
+            failures: self.failures.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Keeps the statistics of a handler
+///
+/// Every counter is atomic so that errors handled from many tasks at once
+/// don't wait on each other
+#[derive(Debug)]
+pub(crate) struct StatsRecorder {
+    /// When the recorder was made, which the intervals are counted from
+    start: Instant,
+    /// The number of errors handled
+    total: AtomicU64,
+    /// The number of errors handled with [`Severity::Warning`]
+    warnings: AtomicU64,
+    /// The number of errors handled with [`Severity::Error`]
+    errors: AtomicU64,
+    /// The number of errors handled with [`Severity::Critical`]
+    critical: AtomicU64,
+    /// The errors handled in each of the last 60 seconds
+    seconds: Buckets,
+    /// The errors handled in each of the last 60 minutes
+    minutes: Buckets,
+    /// The delivery results of [`Sink::Channel`]
+    channel: SinkCounters,
+    /// The delivery results of [`Sink::Webhook`]
+    webhook: SinkCounters,
+    /// The delivery results of [`Sink::File`]
+    file: SinkCounters,
+}
+
+impl StatsRecorder {
+    /// Make a recorder with every counter at zero
+    pub(crate) fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            total: AtomicU64::new(0),
+            warnings: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            critical: AtomicU64::new(0),
+            seconds: Buckets::new(1),
+            minutes: Buckets::new(60),
+            channel: SinkCounters::new(),
+            webhook: SinkCounters::new(),
+            file: SinkCounters::new(),
+        }
+    }
+
+    /// Records that an error with the given severity was handled
+    pub(crate) fn record_error(&self, severity: Severity) {
+        self.total.fetch_add(1, Ordering::Relaxed);
+        match severity {
+            Severity::Warning => &self.warnings,
+            Severity::Error => &self.errors,
+            Severity::Critical => &self.critical,
+        }
+        .fetch_add(1, Ordering::Relaxed);
+
+        let elapsed_secs = self.start.elapsed().as_secs();
+        self.seconds.record(elapsed_secs);
+        self.minutes.record(elapsed_secs);
+    }
+
+    /// Records the result of delivering to the sink
+    pub(crate) fn record_sink(&self, sink: Sink, succeeded: bool) {
+        let counters = match sink {
+            Sink::Channel => &self.channel,
+            Sink::Webhook => &self.webhook,
+            Sink::File => &self.file,
+        };
+        if succeeded {
+            &counters.successes
+        } else {
+            &counters.failures
+        }
+        .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the current statistics
+    pub(crate) fn get(&self) -> Stats {
+        let elapsed_secs = self.start.elapsed().as_secs();
+
+        Stats {
+            total: self.total.load(Ordering::Relaxed),
+            warnings: self.warnings.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            critical: self.critical.load(Ordering::Relaxed),
+            last_minute: self.seconds.sum(elapsed_secs),
+            last_hour: self.minutes.sum(elapsed_secs),
+            channel: self.channel.get(),
+            webhook: self.webhook.get(),
+            file: self.file.get(),
+        }
+    }
+}
+
+impl ErrorHandler {
+    /// Returns the statistics of errors handled so far
+    ///
+    /// Use [`Stats::as_embed`] to show them in Discord
+    pub fn stats(&self) -> Stats {
+        self.stats.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_count_the_last_intervals() {
+        let buckets = Buckets::new(1);
+        buckets.record(0);
+        buckets.record(0);
+        buckets.record(30);
+
+        assert_eq!(buckets.sum(30), 3);
+        assert_eq!(buckets.sum(59), 3);
+        assert_eq!(buckets.sum(60), 1);
+        assert_eq!(buckets.sum(90), 0);
+    }
+
+    #[test]
+    fn reused_bucket_is_reset() {
+        let buckets = Buckets::new(1);
+        buckets.record(5);
+        buckets.record(65);
+
+        assert_eq!(buckets.sum(65), 1);
+    }
+
+    #[test]
+    fn buckets_of_minutes() {
+        let buckets = Buckets::new(60);
+        buckets.record(0);
+        buckets.record(59);
+        buckets.record(3_599);
+
+        assert_eq!(buckets.sum(3_599), 3);
+        assert_eq!(buckets.sum(3_600), 1);
+    }
+
+    #[test]
+    fn recorder_counts_by_severity_and_sink() {
+        let recorder = StatsRecorder::new();
+        recorder.record_error(Severity::Warning);
+        recorder.record_error(Severity::Critical);
+        recorder.record_error(Severity::Critical);
+        recorder.record_sink(Sink::File, true);
+        recorder.record_sink(Sink::File, false);
+
+        let stats = recorder.get();
+
+        assert_eq!(stats.total, 3);
+        assert_eq!(stats.severity(Severity::Warning), 1);
+        assert_eq!(stats.severity(Severity::Error), 0);
+        assert_eq!(stats.severity(Severity::Critical), 2);
+        assert_eq!(stats.last_minute, 3);
+        assert_eq!(stats.last_hour, 3);
+        assert_eq!(
+            stats.sink(Sink::File),
+            SinkStats {
+                successes: 1,
+                failures: 1
+            }
+        );
+    }
+}
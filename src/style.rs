@@ -0,0 +1,197 @@
+//! Styling the Discord messages of errors by their severity
+
+use std::borrow::Cow;
+
+use crate::{ErrorHandler, FormattedOutput, Severity};
+
+/// The emoji and embed color of a severity
+#[derive(Clone, Debug)]
+struct Style {
+    /// The emoji prefixed to the content, none if it's empty
+    emoji: Cow<'static, str>,
+    /// The color of the embeds that don't have one
+    color: u32,
+    /// The username of the webhooks, their own if it's `None`
+    webhook_username: Option<String>,
+    /// The URL of the avatar of the webhooks, their own if it's `None`
+    webhook_avatar_url: Option<String>,
+}
+
+/// The styles of the Discord messages of each severity
+#[derive(Clone, Debug)]
+pub(crate) struct SeverityStyles {
+    /// The style of [`Severity::Warning`]
+    warning: Style,
+    /// The style of [`Severity::Error`]
+    error: Style,
+    /// The style of [`Severity::Critical`]
+    critical: Style,
+}
+
+impl SeverityStyles {
+    /// A yellow circle and yellow embeds for warnings, a red circle and red
+    /// embeds for errors and a rotating light and dark red embeds for
+    /// critical errors
+    pub(crate) const DEFAULT: Self = Self {
+        warning: Style {
+            emoji: Cow::Borrowed("\u{1f7e1}"),
+            color: 0x00F1_C40F,
+            webhook_username: None,
+            webhook_avatar_url: None,
+        },
+        error: Style {
+            emoji: Cow::Borrowed("\u{1f534}"),
+            color: 0x00E7_4C3C,
+            webhook_username: None,
+            webhook_avatar_url: None,
+        },
+        critical: Style {
+            emoji: Cow::Borrowed("\u{1f6a8}"),
+            color: 0x0099_2D22,
+            webhook_username: None,
+            webhook_avatar_url: None,
+        },
+    };
+
+    /// Returns the style of the severity
+    const fn get(&self, severity: Severity) -> &Style {
+        match severity {
+            Severity::Warning => &self.warning,
+            Severity::Error => &self.error,
+            Severity::Critical => &self.critical,
+        }
+    }
+
+    /// Returns the style of the severity to change it
+    const fn get_mut(&mut self, severity: Severity) -> &mut Style {
+        match severity {
+            Severity::Warning => &mut self.warning,
+            Severity::Error => &mut self.error,
+            Severity::Critical => &mut self.critical,
+        }
+    }
+
+    /// Prefixes the output's content with the emoji of the severity, after
+    /// its leading blank lines, and colors the embeds that don't have a color
+    pub(crate) fn apply(&self, output: &mut FormattedOutput, severity: Severity) {
+        let style = self.get(severity);
+
+        if !style.emoji.is_empty() {
+            let start = output
+                .content
+                .find(|character| character != '\n')
+                .unwrap_or(output.content.len());
+            output
+                .content
+                .insert_str(start, &format!("{} ", style.emoji));
+        }
+        for embed in &mut output.embeds {
+            embed.color.get_or_insert(style.color);
+        }
+    }
+
+    /// Returns the username and the avatar URL the webhooks use for the
+    /// severity
+    pub(crate) fn webhook_identity(&self, severity: Severity) -> (Option<&str>, Option<&str>) {
+        let style = self.get(severity);
+        (
+            style.webhook_username.as_deref(),
+            style.webhook_avatar_url.as_deref(),
+        )
+    }
+}
+
+impl ErrorHandler {
+    /// Set the emoji the Discord messages of errors with the severity start
+    /// with, an empty emoji removes it
+    ///
+    /// Defaults to 🟡 for warnings, 🔴 for errors and 🚨 for critical errors
+    pub fn severity_emoji(
+        &mut self,
+        severity: Severity,
+        emoji: impl Into<Cow<'static, str>>,
+    ) -> &mut Self {
+        self.config_mut().severity_styles.get_mut(severity).emoji = emoji.into();
+        self
+    }
+
+    /// Change the emoji of the severity at runtime
+    ///
+    /// Same as [`Self::severity_emoji`] but through a shared reference
+    pub fn set_severity_emoji(&self, severity: Severity, emoji: impl Into<Cow<'static, str>>) {
+        let new_emoji = emoji.into();
+        self.update_config(|config| config.severity_styles.get_mut(severity).emoji = new_emoji);
+    }
+
+    /// Set the color of the embeds without a color in the Discord messages of
+    /// errors with the severity, such as the ones of a [`crate::Formatter`]
+    ///
+    /// Defaults to yellow for warnings, red for errors and dark red for
+    /// critical errors
+    pub fn severity_color(&mut self, severity: Severity, color: u32) -> &mut Self {
+        self.config_mut().severity_styles.get_mut(severity).color = color;
+        self
+    }
+
+    /// Change the embed color of the severity at runtime
+    ///
+    /// Same as [`Self::severity_color`] but through a shared reference
+    pub fn set_severity_color(&self, severity: Severity, color: u32) {
+        self.update_config(|config| config.severity_styles.get_mut(severity).color = color);
+    }
+
+    /// Set the username the webhooks use for errors with the severity, such
+    /// as `Bot errors (critical)`, so that it's visible at a glance
+    ///
+    /// This applies to [`Self::webhook`] and the routed and escalation
+    /// webhooks, the username is ignored if Discord wouldn't accept it
+    pub fn severity_webhook_username(
+        &mut self,
+        severity: Severity,
+        username: impl Into<String>,
+    ) -> &mut Self {
+        self.config_mut()
+            .severity_styles
+            .get_mut(severity)
+            .webhook_username = Some(username.into());
+        self
+    }
+
+    /// Change the username the webhooks use for errors with the severity at
+    /// runtime, `None` to use their own
+    ///
+    /// Same as [`Self::severity_webhook_username`] but through a shared
+    /// reference
+    pub fn set_severity_webhook_username(&self, severity: Severity, username: Option<String>) {
+        self.update_config(|config| {
+            config.severity_styles.get_mut(severity).webhook_username = username;
+        });
+    }
+
+    /// Set the URL of the avatar the webhooks use for errors with the
+    /// severity, such as a red siren for critical errors
+    ///
+    /// This applies to the same webhooks as [`Self::severity_webhook_username`]
+    pub fn severity_webhook_avatar(
+        &mut self,
+        severity: Severity,
+        avatar_url: impl Into<String>,
+    ) -> &mut Self {
+        self.config_mut()
+            .severity_styles
+            .get_mut(severity)
+            .webhook_avatar_url = Some(avatar_url.into());
+        self
+    }
+
+    /// Change the URL of the avatar the webhooks use for errors with the
+    /// severity at runtime, `None` to use their own
+    ///
+    /// Same as [`Self::severity_webhook_avatar`] but through a shared
+    /// reference
+    pub fn set_severity_webhook_avatar(&self, severity: Severity, avatar_url: Option<String>) {
+        self.update_config(|config| {
+            config.severity_styles.get_mut(severity).webhook_avatar_url = avatar_url;
+        });
+    }
+}
@@ -0,0 +1,117 @@
+//! Routing errors by the tags of their reports
+
+#[cfg(feature = "discord")]
+use twilight_model::id::{
+    marker::{RoleMarker, UserMarker, WebhookMarker},
+    Id,
+};
+
+use crate::{config::Config, ErrorHandler, Severity};
+
+/// What's done with the errors that have a tag, set with
+/// [`ErrorHandler::tag_route`]
+///
+/// ```ignore
+/// handler.tag_route(
+///     "lavalink",
+///     TagRoute::new()
+///         .webhook(music_webhook_id, music_webhook_token)
+///         .mention_role(music_team_role_id)
+///         .severity(Severity::Warning),
+/// );
+/// ```
+#[derive(Clone, Debug, Default)]
+#[must_use]
+pub struct TagRoute {
+    /// The webhooks the errors are also delivered to
+    #[cfg(feature = "discord")]
+    pub(crate) webhooks: Vec<(Id<WebhookMarker>, String)>,
+    /// The mentions prepended to the Discord messages of the errors
+    #[cfg(feature = "discord")]
+    pub(crate) mentions: Vec<String>,
+    /// The severity the errors are handled with
+    pub(crate) severity: Option<Severity>,
+}
+
+impl TagRoute {
+    /// Make a route that doesn't change how the errors are handled
+    pub const fn new() -> Self {
+        Self {
+            #[cfg(feature = "discord")]
+            webhooks: Vec::new(),
+            #[cfg(feature = "discord")]
+            mentions: Vec::new(),
+            severity: None,
+        }
+    }
+
+    /// Also deliver the errors to the webhook, in addition to the sinks of the
+    /// handler
+    #[cfg(feature = "discord")]
+    pub fn webhook(mut self, webhook_id: Id<WebhookMarker>, token: String) -> Self {
+        self.webhooks.push((webhook_id, token));
+        self
+    }
+
+    /// Mention the role in the Discord messages of the errors
+    #[cfg(feature = "discord")]
+    pub fn mention_role(mut self, role_id: Id<RoleMarker>) -> Self {
+        self.mentions.push(format!("<@&{role_id}>"));
+        self
+    }
+
+    /// Mention the user in the Discord messages of the errors
+    #[cfg(feature = "discord")]
+    pub fn mention_user(mut self, user_id: Id<UserMarker>) -> Self {
+        self.mentions.push(format!("<@{user_id}>"));
+        self
+    }
+
+    /// Handle the errors with the severity instead of the report's, the most
+    /// serious one is used if several tags set it
+    pub const fn severity(mut self, severity: Severity) -> Self {
+        self.severity = Some(severity);
+        self
+    }
+}
+
+impl Config {
+    /// Returns the routes of the tags
+    pub(crate) fn tag_routes<'a>(
+        &'a self,
+        tags: &'a [String],
+    ) -> impl Iterator<Item = &'a TagRoute> + 'a {
+        self.tag_routes
+            .iter()
+            .filter(|(tag, _)| tags.contains(tag))
+            .map(|(_, route)| route)
+    }
+}
+
+impl ErrorHandler {
+    /// Set what's done with the errors that have the tag, see
+    /// [`crate::ErrorReport::tags`]
+    ///
+    /// Replaces the route of the tag if it was already set
+    pub fn tag_route(&mut self, tag: impl Into<String>, route: TagRoute) -> &mut Self {
+        set_route(&mut self.config_mut().tag_routes, tag.into(), Some(route));
+        self
+    }
+
+    /// Change what's done with the errors that have the tag at runtime, `None`
+    /// to handle them like other errors
+    ///
+    /// Same as [`Self::tag_route`] but through a shared reference
+    pub fn set_tag_route(&self, tag: impl Into<String>, route: Option<TagRoute>) {
+        let tag_name = tag.into();
+        self.update_config(|config| set_route(&mut config.tag_routes, tag_name, route));
+    }
+}
+
+/// Replaces the route of the tag, removing it if it's `None`
+fn set_route(routes: &mut Vec<(String, TagRoute)>, tag: String, route: Option<TagRoute>) {
+    routes.retain(|(existing, _)| *existing != tag);
+    if let Some(new_route) = route {
+        routes.push((tag, new_route));
+    }
+}
@@ -0,0 +1,127 @@
+//! Running tasks that report their failures
+
+use core::{
+    fmt::Display,
+    future::{poll_fn, Future},
+    panic::AssertUnwindSafe,
+    pin::pin,
+    task::Poll,
+    time::Duration,
+};
+use std::{panic, sync::Arc};
+
+use tokio::{task::JoinHandle, time};
+use twilight_http::Client;
+
+use crate::{panic::payload_message, ErrorHandler, ErrorReport};
+
+impl ErrorHandler {
+    /// Run the future, handling the error if it returns `Err` or panics
+    ///
+    /// Returns the output of the future if it returns `Ok`, the panic is also
+    /// reported by [`Self::install_panic_hook`] if it's installed
+    pub async fn run<T, E: Display + Send>(
+        &self,
+        http: &Client,
+        future: impl Future<Output = Result<T, E>> + Send,
+    ) -> Option<T> {
+        let mut pinned = pin!(future);
+        let output = poll_fn(|cx| {
+            match panic::catch_unwind(AssertUnwindSafe(|| pinned.as_mut().poll(cx))) {
+                Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+                Ok(Poll::Pending) => Poll::Pending,
+                Err(payload) => Poll::Ready(Err(payload)),
+            }
+        })
+        .await;
+
+        match output {
+            Ok(Ok(value)) => Some(value),
+            Ok(Err(err)) => {
+                let report =
+                    ErrorReport::new(format_args!("Task failed: {err}")).without_location();
+                self.handle_report(http, report).await;
+                None
+            }
+            Err(payload) => {
+                let message = format!("Task panicked: {}", payload_message(&*payload));
+                self.handle_report(http, ErrorReport::new(message).without_location())
+                    .await;
+                None
+            }
+        }
+    }
+
+    /// Spawn the future as a Tokio task, handling the error if it returns
+    /// `Err` or panics
+    ///
+    /// Same as [`Self::run`] but in a spawned task
+    pub fn spawn<T: Send + 'static, E: Display + Send + 'static>(
+        self: Arc<Self>,
+        http: Arc<Client>,
+        future: impl Future<Output = Result<T, E>> + Send + 'static,
+    ) -> JoinHandle<Option<T>> {
+        tokio::spawn(async move { self.run(&http, future).await })
+    }
+
+    /// Spawn a Tokio task that runs the future made by the closure, handling
+    /// the error and running a new future if it returns `Err` or panics
+    ///
+    /// The task waits `initial_backoff` before the first restart, doubling
+    /// the wait after each failure up to `max_backoff`, it ends when a future
+    /// returns `Ok`
+    pub fn spawn_restarting<Fut, E: Display + Send + 'static>(
+        self: Arc<Self>,
+        http: Arc<Client>,
+        initial_backoff: Duration,
+        max_backoff: Duration,
+        mut make_future: impl FnMut() -> Fut + Send + 'static,
+    ) -> JoinHandle<()>
+    where
+        Fut: Future<Output = Result<(), E>> + Send,
+    {
+        tokio::spawn(async move {
+            let mut backoff = initial_backoff;
+
+            while self.run(&http, make_future()).await.is_none() {
+                time::sleep(backoff).await;
+                backoff = backoff.saturating_mul(2).min(max_backoff);
+            }
+        })
+    }
+
+    /// Wait for the task to end, handling the error with the task's name
+    /// attached if it returns `Err` or panics
+    ///
+    /// Returns the output of the task if it returns `Ok`, tasks that are
+    /// cancelled aren't reported
+    ///
+    /// ```ignore
+    /// let task = tokio::spawn(run_reminders());
+    /// tokio::spawn(async move { handler.watch(&http, task, "reminders").await });
+    /// ```
+    pub async fn watch<T, E: Display>(
+        &self,
+        http: &Client,
+        join_handle: JoinHandle<Result<T, E>>,
+        name: &str,
+    ) -> Option<T> {
+        let message = match join_handle.await {
+            Ok(Ok(value)) => return Some(value),
+            Ok(Err(err)) => format!("Task failed: {err}"),
+            Err(err) if err.is_panic() => {
+                format!("Task panicked: {}", payload_message(&*err.into_panic()))
+            }
+            Err(_) => return None,
+        };
+
+        self.handle_report(
+            http,
+            ErrorReport::new(message)
+                .field("Task", name)
+                .without_location(),
+        )
+        .await;
+        None
+    }
+}
@@ -0,0 +1,472 @@
+//! Rendering errors with Handlebars-style templates
+
+use core::{error::Error, fmt};
+use std::borrow::Cow;
+
+#[cfg(feature = "discord")]
+use twilight_model::channel::message::{embed::EmbedField, Embed};
+
+use crate::{ErrorRecord, FormattedOutput, Formatter, TimestampFormat, Timezone};
+
+/// The maximum number of fields of a Discord embed
+#[cfg(feature = "discord")]
+const MAX_EMBED_FIELDS: usize = 25;
+
+/// The error returned when parsing a template fails
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TemplateError {
+    /// A `{{` isn't followed by `}}`
+    UnterminatedTag,
+    /// A block isn't closed, such as an `{{#if}}` without `{{/if}}`
+    UnclosedBlock(&'static str),
+    /// A tag closes a block that isn't open, such as a `{{/each}}` in an
+    /// `{{#if}}`
+    UnexpectedTag(String),
+    /// An `{{#each}}` loops over something other than `fields` or `tags`
+    UnknownList(String),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnterminatedTag => f.write_str("a `{{` isn't followed by `}}`"),
+            Self::UnclosedBlock(block) => write!(f, "the `{{{{#{block}}}}}` block isn't closed"),
+            Self::UnexpectedTag(tag) => write!(f, "the `{{{{{tag}}}}}` tag isn't expected here"),
+            Self::UnknownList(list) => write!(
+                f,
+                "`{list}` can't be looped over, only `fields` and `tags` can"
+            ),
+        }
+    }
+}
+
+impl Error for TemplateError {}
+
+/// What an `{{#each}}` block loops over
+#[derive(Clone, Copy, Debug)]
+enum List {
+    /// The fields of the record
+    Fields,
+    /// The tags of the record
+    Tags,
+}
+
+/// A part of a template
+#[derive(Clone, Debug)]
+enum Node {
+    /// Text rendered as is
+    Text(String),
+    /// A `{{variable}}` replaced with its value
+    Variable(String),
+    /// An `{{#if variable}}` block, rendering the first nodes if the variable
+    /// isn't empty and the second ones after `{{else}}` otherwise
+    If(String, Vec<Self>, Vec<Self>),
+    /// An `{{#each list}}` block, rendering the nodes for each item
+    Each(List, Vec<Self>),
+}
+
+/// The item of the `{{#each}}` block being rendered
+#[derive(Clone, Copy)]
+enum Item<'a> {
+    /// A field, its name is `{{name}}` and its value is `{{value}}`
+    Field(&'a str, &'a str),
+    /// A tag, it's `{{this}}`
+    Tag(&'a str),
+}
+
+/// A parsed template, rendered with the values of an [`ErrorRecord`]
+///
+/// - `{{id}}`, `{{severity}}`, `{{error}}`, `{{kind}}`, `{{correlation_id}}`
+///   and `{{tags}}` are replaced with the record's values, the tags are
+///   separated by commas
+/// - `{{timestamp}}` is replaced with when the error was handled, formatted
+///   with [`crate::ErrorHandler::timestamp_format`]
+/// - `{{fields.Name}}` is replaced with the value of the field named `Name`
+/// - `{{#if variable}}...{{else}}...{{/if}}` renders the first part if the
+///   variable isn't empty and the part after the optional `{{else}}`
+///   otherwise
+/// - `{{#each fields}}...{{/each}}` renders its content for each field with
+///   `{{name}}` and `{{value}}`, `{{#each tags}}...{{/each}}` for each tag
+///   with `{{this}}`
+///
+/// Unknown variables are replaced with nothing
+///
+/// ```ignore
+/// let template = Template::new(
+///     "**{{severity}}** `{{id}}` {{error}}{{#each fields}}\n{{name}}: {{value}}{{/each}}",
+/// )?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct Template {
+    /// The parsed nodes
+    nodes: Vec<Node>,
+}
+
+impl Template {
+    /// Parse the template
+    ///
+    /// # Errors
+    /// Returns [`TemplateError`] if a tag or a block isn't closed or a tag
+    /// isn't valid
+    pub fn new(source: &str) -> Result<Self, TemplateError> {
+        let mut parser = Parser { rest: source };
+        match parser.nodes()? {
+            (nodes, None) => Ok(Self { nodes }),
+            (_, Some(tag)) => Err(TemplateError::UnexpectedTag(tag)),
+        }
+    }
+
+    /// Returns the template rendered with the record's values, with the
+    /// timestamp in RFC 3339 in UTC
+    #[must_use]
+    pub fn render(&self, record: &ErrorRecord) -> String {
+        self.render_with_timestamp(record, &TimestampFormat::Rfc3339, Timezone::Utc)
+    }
+
+    /// Returns the template rendered with the record's values, with the
+    /// timestamp in the format and timezone
+    #[must_use]
+    pub fn render_with_timestamp(
+        &self,
+        record: &ErrorRecord,
+        timestamp_format: &TimestampFormat,
+        timezone: Timezone,
+    ) -> String {
+        self.render_values(&Values::new(record, timestamp_format, timezone))
+    }
+
+    /// Returns the template rendered with the values and the field
+    #[cfg(feature = "discord")]
+    fn render_field(&self, values: &Values<'_>, name: &str, value: &str) -> String {
+        let mut rendered = String::new();
+        render(
+            &self.nodes,
+            values,
+            Some(Item::Field(name, value)),
+            &mut rendered,
+        );
+        rendered
+    }
+
+    /// Returns the template rendered with the values
+    fn render_values(&self, values: &Values<'_>) -> String {
+        let mut rendered = String::new();
+        render(&self.nodes, values, None, &mut rendered);
+        rendered
+    }
+}
+
+/// Parses templates into nodes
+struct Parser<'a> {
+    /// The part of the template that isn't parsed yet
+    rest: &'a str,
+}
+
+impl Parser<'_> {
+    /// Parses nodes until the end of the template or a tag that ends a block,
+    /// returning the nodes and that tag
+    fn nodes(&mut self) -> Result<(Vec<Node>, Option<String>), TemplateError> {
+        let mut nodes = Vec::new();
+
+        loop {
+            let Some((text, after_open)) = self.rest.split_once("{{") else {
+                if !self.rest.is_empty() {
+                    nodes.push(Node::Text(self.rest.to_owned()));
+                }
+                self.rest = "";
+                return Ok((nodes, None));
+            };
+            if !text.is_empty() {
+                nodes.push(Node::Text(text.to_owned()));
+            }
+            let (raw_tag, after_close) = after_open
+                .split_once("}}")
+                .ok_or(TemplateError::UnterminatedTag)?;
+            self.rest = after_close;
+
+            let tag = raw_tag.trim();
+            if let Some(condition) = tag.strip_prefix("#if ") {
+                let (then, otherwise) = self.if_block()?;
+                nodes.push(Node::If(condition.trim().to_owned(), then, otherwise));
+            } else if let Some(list_name) = tag.strip_prefix("#each ") {
+                let list = match list_name.trim() {
+                    "fields" => List::Fields,
+                    "tags" => List::Tags,
+                    other => return Err(TemplateError::UnknownList(other.to_owned())),
+                };
+                let body = self.block("each")?;
+                nodes.push(Node::Each(list, body));
+            } else if tag == "else" || tag.starts_with('/') {
+                return Ok((nodes, Some(tag.to_owned())));
+            } else {
+                nodes.push(Node::Variable(tag.to_owned()));
+            }
+        }
+    }
+
+    /// Parses the nodes of an `{{#if}}` block and of its `{{else}}` part
+    fn if_block(&mut self) -> Result<(Vec<Node>, Vec<Node>), TemplateError> {
+        let (then, end) = self.nodes()?;
+        match end.as_deref() {
+            Some("/if") => Ok((then, Vec::new())),
+            Some("else") => Ok((then, self.block("if")?)),
+            Some(_) | None => Err(Self::unclosed("if", end)),
+        }
+    }
+
+    /// Parses the nodes of a block until its closing tag
+    fn block(&mut self, name: &'static str) -> Result<Vec<Node>, TemplateError> {
+        let (body, end) = self.nodes()?;
+        if end.as_deref().and_then(|tag| tag.strip_prefix('/')) == Some(name) {
+            Ok(body)
+        } else {
+            Err(Self::unclosed(name, end))
+        }
+    }
+
+    /// Returns the error of the block ending with the wrong tag or not at all
+    fn unclosed(name: &'static str, end: Option<String>) -> TemplateError {
+        end.map_or(
+            TemplateError::UnclosedBlock(name),
+            TemplateError::UnexpectedTag,
+        )
+    }
+}
+
+/// The values a template is rendered with
+struct Values<'a> {
+    /// The record of the error
+    record: &'a ErrorRecord,
+    /// When the error was handled, formatted
+    timestamp: String,
+}
+
+impl<'a> Values<'a> {
+    /// Make the values of the record with its timestamp in the format and
+    /// timezone
+    fn new(
+        record: &'a ErrorRecord,
+        timestamp_format: &TimestampFormat,
+        timezone: Timezone,
+    ) -> Self {
+        Self {
+            record,
+            timestamp: timestamp_format.format(record.handled_at, timezone),
+        }
+    }
+}
+
+/// Renders the nodes with the values and the item of the `{{#each}}` block
+/// being rendered
+fn render(nodes: &[Node], values: &Values<'_>, item: Option<Item<'_>>, rendered: &mut String) {
+    let record = values.record;
+    for node in nodes {
+        match node {
+            Node::Text(text) => rendered.push_str(text),
+            Node::Variable(name) => {
+                rendered.push_str(&value(name, values, item).unwrap_or_default());
+            }
+            Node::If(condition, then, otherwise) => {
+                let value = value(condition, values, item);
+                if value.is_some_and(|set| !set.is_empty()) {
+                    render(then, values, item, rendered);
+                } else {
+                    render(otherwise, values, item, rendered);
+                }
+            }
+            Node::Each(List::Fields, body) => {
+                for (name, field_value) in &record.fields {
+                    render(body, values, Some(Item::Field(name, field_value)), rendered);
+                }
+            }
+            Node::Each(List::Tags, body) => {
+                for tag in &record.tags {
+                    render(body, values, Some(Item::Tag(tag)), rendered);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the value of the variable, `None` if it's unknown or not set
+fn value<'a>(name: &str, values: &'a Values<'a>, item: Option<Item<'a>>) -> Option<Cow<'a, str>> {
+    let record = values.record;
+    match (name, item) {
+        ("name", Some(Item::Field(field_name, _))) => return Some(Cow::Borrowed(field_name)),
+        ("value", Some(Item::Field(_, field_value))) => return Some(Cow::Borrowed(field_value)),
+        ("this", Some(Item::Tag(tag))) => return Some(Cow::Borrowed(tag)),
+        _ => {}
+    }
+
+    if let Some(field_name) = name.strip_prefix("fields.") {
+        return record
+            .fields
+            .iter()
+            .find(|(set_name, _)| set_name == field_name)
+            .map(|(_, field_value)| Cow::Borrowed(field_value.as_str()));
+    }
+
+    match name {
+        "id" => Some(Cow::Owned(record.id.to_string())),
+        "severity" => Some(Cow::Owned(record.severity.to_string())),
+        "error" => Some(Cow::Borrowed(&record.error)),
+        "kind" => record.kind.as_deref().map(Cow::Borrowed),
+        "correlation_id" => record.correlation_id.as_deref().map(Cow::Borrowed),
+        "tags" => Some(Cow::Owned(record.tags.join(", "))),
+        "timestamp" => Some(Cow::Borrowed(&values.timestamp)),
+        _ => None,
+    }
+}
+
+/// The templates of the embed of a [`TemplateFormatter`]
+#[cfg(feature = "discord")]
+#[derive(Clone, Debug)]
+struct EmbedTemplate {
+    /// The template of the title
+    title: Option<Template>,
+    /// The template of the description
+    description: Option<Template>,
+    /// The templates of the name and value of the field added for each field
+    /// of the record
+    fields: Option<(Template, Template)>,
+}
+
+/// Renders errors with templates, so that custom layouts don't need a
+/// [`Formatter`] of their own
+///
+/// ```ignore
+/// handler.formatter(
+///     Sink::Channel,
+///     TemplateFormatter::new(Template::new("**{{severity}}** `{{id}}`")?)
+///         .embed_title(Template::new("{{error}}")?)
+///         .embed_fields(Template::new("{{name}}")?, Template::new("{{value}}")?),
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct TemplateFormatter {
+    /// The template of the content
+    content: Template,
+    /// The templates of the embed, only sent if any of them is set
+    #[cfg(feature = "discord")]
+    embed: EmbedTemplate,
+}
+
+impl TemplateFormatter {
+    /// Make a formatter that renders the content with the template
+    #[must_use]
+    pub const fn new(content: Template) -> Self {
+        Self {
+            content,
+            #[cfg(feature = "discord")]
+            embed: EmbedTemplate {
+                title: None,
+                description: None,
+                fields: None,
+            },
+        }
+    }
+
+    /// Add an embed to Discord messages with the title rendered with the
+    /// template
+    #[cfg(feature = "discord")]
+    #[must_use]
+    pub fn embed_title(mut self, title: Template) -> Self {
+        self.embed.title = Some(title);
+        self
+    }
+
+    /// Add an embed to Discord messages with the description rendered with
+    /// the template
+    #[cfg(feature = "discord")]
+    #[must_use]
+    pub fn embed_description(mut self, description: Template) -> Self {
+        self.embed.description = Some(description);
+        self
+    }
+
+    /// Add an embed to Discord messages with a field for each field of the
+    /// record, its name and value rendered with the templates, which can use
+    /// `{{name}}` and `{{value}}`
+    ///
+    /// Fields whose name or value render empty are skipped, since Discord
+    /// doesn't accept them
+    #[cfg(feature = "discord")]
+    #[must_use]
+    pub fn embed_fields(mut self, name: Template, value: Template) -> Self {
+        self.embed.fields = Some((name, value));
+        self
+    }
+
+    /// Returns the embed rendered with the values, `None` if none of its
+    /// templates are set
+    #[cfg(feature = "discord")]
+    fn render_embed(&self, values: &Values<'_>) -> Option<Embed> {
+        let record = values.record;
+        let EmbedTemplate {
+            title,
+            description,
+            fields,
+        } = &self.embed;
+        if title.is_none() && description.is_none() && fields.is_none() {
+            return None;
+        }
+
+        let embed_fields = fields
+            .iter()
+            .flat_map(|(name, value)| {
+                record
+                    .fields
+                    .iter()
+                    .map(|(field_name, field_value)| EmbedField {
+                        inline: false,
+                        name: name.render_field(values, field_name, field_value),
+                        value: value.render_field(values, field_name, field_value),
+                    })
+            })
+            .filter(|field| !field.name.is_empty() && !field.value.is_empty())
+            .take(MAX_EMBED_FIELDS)
+            .collect();
+
+        Some(Embed {
+            author: None,
+            color: None,
+            description: description
+                .as_ref()
+                .map(|template| template.render_values(values)),
+            fields: embed_fields,
+            footer: None,
+            image: None,
+            kind: "rich".to_owned(),
+            provider: None,
+            thumbnail: None,
+            timestamp: None,
+            title: title
+                .as_ref()
+                .map(|template| template.render_values(values)),
+            url: None,
+            video: None,
+        })
+    }
+}
+
+impl Formatter for TemplateFormatter {
+    fn format(&self, record: &ErrorRecord) -> FormattedOutput {
+        self.format_with_timestamp(record, &TimestampFormat::Rfc3339, Timezone::Utc)
+    }
+
+    fn format_with_timestamp(
+        &self,
+        record: &ErrorRecord,
+        timestamp_format: &TimestampFormat,
+        timezone: Timezone,
+    ) -> FormattedOutput {
+        let values = Values::new(record, timestamp_format, timezone);
+        let output = FormattedOutput::new(self.content.render_values(&values));
+        #[cfg(feature = "discord")]
+        if let Some(embed) = self.render_embed(&values) {
+            return output.embed(embed);
+        }
+        output
+    }
+}
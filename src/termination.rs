@@ -0,0 +1,62 @@
+//! Writing the last fatal error where container orchestrators look for it
+
+use std::{fs, path::PathBuf};
+
+use crate::{config::Config, ErrorHandler, ErrorRecord, Severity};
+
+/// The path Kubernetes reads the termination message of containers from by
+/// default
+pub const DEFAULT_TERMINATION_LOG: &str = "/dev/termination-log";
+
+/// The most bytes Kubernetes shows of the termination message
+const MAX_TERMINATION_LOG_LEN: usize = 4096;
+
+impl Config {
+    /// Overwrites the termination log with the record if it's a critical
+    /// error
+    pub(crate) fn write_termination_log(&self, record: &ErrorRecord) {
+        let Some(path) = &self.termination_log else {
+            return;
+        };
+        if record.severity != Severity::Critical {
+            return;
+        }
+
+        let message = record.message();
+        let trimmed = message.trim_start();
+        let end = trimmed
+            .char_indices()
+            .map(|(index, character)| index.saturating_add(character.len_utf8()))
+            .take_while(|end| *end <= MAX_TERMINATION_LOG_LEN)
+            .last()
+            .unwrap_or(0);
+        if let Err(err) = fs::write(path, trimmed.get(..end).unwrap_or(trimmed)) {
+            self.print_notice(&format!(
+                "Failed to write the error {} to the termination log: {err}",
+                record.id
+            ));
+        }
+    }
+}
+
+impl ErrorHandler {
+    /// Also write critical errors, such as the panics reported with
+    /// [`Self::install_panic_hook`], to the file at the path, overwriting it,
+    /// so that the last one is shown as why the container stopped
+    ///
+    /// Use [`DEFAULT_TERMINATION_LOG`] for the default path of Kubernetes, so
+    /// that `kubectl describe pod` shows it, only its first 4096 bytes are
+    /// written
+    pub fn termination_log(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        self.config_mut().termination_log = Some(path.into());
+        self
+    }
+
+    /// Change the file critical errors are written to at runtime, `None` to
+    /// not write them
+    ///
+    /// Same as [`Self::termination_log`] but through a shared reference
+    pub fn set_termination_log(&self, path: Option<PathBuf>) {
+        self.update_config(|config| config.termination_log = path);
+    }
+}
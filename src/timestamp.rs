@@ -0,0 +1,109 @@
+//! Formatting when errors were handled
+
+use core::fmt::{Display, Write as _};
+use core::time::Duration;
+use std::time::SystemTime;
+
+use chrono::{DateTime, Local, SecondsFormat, TimeZone, Utc};
+
+use crate::ErrorHandler;
+
+/// How timestamps are formatted, see [`ErrorHandler::timestamp_format`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TimestampFormat {
+    /// RFC 3339, such as `2024-01-02T03:04:05.678Z`
+    #[default]
+    Rfc3339,
+    /// A `strftime` pattern, such as `%Y-%m-%d %H:%M:%S`, see
+    /// [`chrono::format::strftime`]
+    ///
+    /// Invalid patterns fall back to [`Self::Rfc3339`]
+    Custom(String),
+}
+
+/// The timezone of timestamps, see [`ErrorHandler::timestamp_format`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+#[non_exhaustive]
+pub enum Timezone {
+    /// Coordinated Universal Time
+    #[default]
+    Utc,
+    /// The local timezone of the host
+    Local,
+}
+
+impl TimestampFormat {
+    /// Returns the time formatted in the timezone, such as for custom
+    /// [`crate::Formatter`]s
+    #[must_use]
+    pub fn format(&self, time: SystemTime, timezone: Timezone) -> String {
+        let utc = DateTime::<Utc>::from(time);
+
+        match timezone {
+            Timezone::Utc => self.format_datetime(&utc),
+            Timezone::Local => self.format_datetime(&utc.with_timezone(&Local)),
+        }
+    }
+
+    /// Returns the time formatted with the pattern
+    fn format_datetime<Tz: TimeZone>(&self, time: &DateTime<Tz>) -> String
+    where
+        Tz::Offset: Display,
+    {
+        if let Self::Custom(pattern) = self {
+            let mut formatted = String::new();
+            if write!(formatted, "{}", time.format(pattern)).is_ok() {
+                return formatted;
+            }
+        }
+
+        time.to_rfc3339_opts(SecondsFormat::Millis, true)
+    }
+}
+
+/// Returns the duration in hours, minutes and seconds, such as `3h 12m` or
+/// `45s`, leaving out the seconds if it's at least an hour
+pub(crate) fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let hours = secs.checked_div(3600).unwrap_or(0);
+    let minutes = secs
+        .checked_rem(3600)
+        .and_then(|rest| rest.checked_div(60))
+        .unwrap_or(0);
+    let seconds = secs.checked_rem(60).unwrap_or(0);
+
+    if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else if minutes > 0 {
+        format!("{minutes}m {seconds}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+impl ErrorHandler {
+    /// Set the format and timezone of the timestamps of errors appended to
+    /// the file, defaults to RFC 3339 in UTC
+    pub fn timestamp_format(&mut self, format: TimestampFormat, timezone: Timezone) -> &mut Self {
+        let config = self.config_mut();
+        config.timestamp_format = format;
+        config.timezone = timezone;
+        self
+    }
+
+    /// Change the format and timezone of timestamps at runtime
+    ///
+    /// Same as [`Self::timestamp_format`] but through a shared reference
+    pub fn set_timestamp_format(&self, format: TimestampFormat, timezone: Timezone) {
+        self.update_config(|config| {
+            config.timestamp_format = format;
+            config.timezone = timezone;
+        });
+    }
+}
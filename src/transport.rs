@@ -0,0 +1,226 @@
+//! Sending the messages of the Discord sinks without HTTP, such as in tests
+//!
+//! The time-based policies, such as the circuit breaker, rate limits and the
+//! escalation windows, use Tokio's clock, which this feature lets tests pause
+//! and advance with `tokio::time::pause` and `tokio::time::advance`
+
+use core::{fmt::Debug, future::Future, pin::Pin};
+use std::{
+    io,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use twilight_model::{
+    channel::message::Embed,
+    http::attachment::Attachment,
+    id::{
+        marker::{ChannelMarker, UserMarker, WebhookMarker},
+        Id,
+    },
+};
+
+use crate::{
+    config::Config,
+    delivery::{self, Delivery},
+    formatter::Message,
+    payload::{self, Payload},
+    ErrorHandler, ErrorId, Sink,
+};
+
+/// The future returned from [`Transport::send`]
+pub type TransportFuture<'a> = Pin<Box<dyn Future<Output = Result<(), String>> + Send + 'a>>;
+
+/// Where a Discord message is sent
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Destination {
+    /// The channel set with [`ErrorHandler::channel`]
+    Channel(Id<ChannelMarker>),
+    /// The DMs of the user set with [`ErrorHandler::owner`]
+    Owner(Id<UserMarker>),
+    /// The webhook set with [`ErrorHandler::webhook`] or
+    /// [`ErrorHandler::escalate_repeated`]
+    Webhook(Id<WebhookMarker>),
+}
+
+/// A Discord message sent through a [`Transport`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SentMessage {
+    /// Where the message is sent
+    pub destination: Destination,
+    /// The content of the message, already shortened and validated
+    pub content: String,
+    /// The embeds of the message
+    pub embeds: Vec<Embed>,
+    /// The attachments of the message, such as the backtrace and the end of
+    /// the log file
+    pub attachments: Vec<Attachment>,
+    /// The ID of the error, to recognize duplicates of it, see
+    /// [`crate::DeliverySemantics`], `None` for notices
+    pub idempotency_key: Option<ErrorId>,
+    /// The username of the webhook, see
+    /// [`ErrorHandler::severity_webhook_username`]
+    pub username: Option<String>,
+    /// The URL of the avatar of the webhook, see
+    /// [`ErrorHandler::severity_webhook_avatar`]
+    pub avatar_url: Option<String>,
+}
+
+impl SentMessage {
+    /// Makes the message of the error with the ID sent to the destination
+    /// with the payload
+    pub(crate) fn new(
+        destination: Destination,
+        payload: &Payload<'_>,
+        idempotency_key: Option<ErrorId>,
+    ) -> Self {
+        Self {
+            destination,
+            content: payload.content.clone().into_owned(),
+            embeds: payload.embeds.to_vec(),
+            attachments: payload.attachments.clone(),
+            idempotency_key,
+            username: payload.username.map(str::to_owned),
+            avatar_url: payload.avatar_url.map(str::to_owned),
+        }
+    }
+}
+
+/// Sends the messages of the Discord sinks instead of `twilight_http`, set
+/// with [`ErrorHandler::transport`]
+///
+/// Returning `Err` is handled like a failed request, so it can be used to
+/// test the failures of the sinks
+pub trait Transport: Debug + Send + Sync {
+    /// Sends the message
+    fn send(&self, message: SentMessage) -> TransportFuture<'_>;
+}
+
+/// A [`Transport`] that keeps the messages in memory
+///
+/// ```ignore
+/// let capture = Arc::new(CaptureSink::new());
+/// handler.channel(channel_id).transport(capture.clone());
+///
+/// handler.handle(&http, "oh no").await;
+///
+/// let sent = capture.sent();
+/// assert_eq!(sent[0].destination, Destination::Channel(channel_id));
+/// assert!(sent[0].content.contains("oh no"));
+/// ```
+#[derive(Debug, Default)]
+pub struct CaptureSink {
+    /// The messages sent so far, oldest first
+    sent: Mutex<Vec<SentMessage>>,
+}
+
+impl CaptureSink {
+    /// Make a sink without any messages
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            sent: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns the messages sent so far, oldest first
+    pub fn sent(&self) -> Vec<SentMessage> {
+        self.sent
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Returns the messages sent to the destination so far, oldest first
+    pub fn sent_to(&self, destination: Destination) -> Vec<SentMessage> {
+        self.sent()
+            .into_iter()
+            .filter(|message| message.destination == destination)
+            .collect()
+    }
+
+    /// Removes the messages sent so far
+    pub fn clear(&self) {
+        self.sent
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+}
+
+impl Transport for CaptureSink {
+    fn send(&self, message: SentMessage) -> TransportFuture<'_> {
+        self.sent
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(message);
+        Box::pin(async { Ok(()) })
+    }
+}
+
+impl ErrorHandler {
+    /// Sends the message to the sink through the transport instead of
+    /// `twilight_http`, formatted the same way
+    pub(crate) async fn send_to_transport(
+        &self,
+        transport: &dyn Transport,
+        config: &Config,
+        message: Message<'_>,
+        notes: &str,
+        sink: Sink,
+    ) -> Delivery {
+        let destination = match (sink, config.channel, config.owner, &config.webhook) {
+            (Sink::Channel, Some(channel_id), _, _) => Destination::Channel(channel_id),
+            (Sink::Channel, None, Some(owner_id), _) => Destination::Owner(owner_id),
+            (Sink::Webhook, _, _, Some((webhook_id, _))) => Destination::Webhook(*webhook_id),
+            _ => return Delivery::default(),
+        };
+
+        let output = message.output_with_notes(config, sink, notes);
+        let mut payload = Payload::new(
+            config,
+            &output.content,
+            &output.embeds,
+            payload::attachments(config, message),
+        );
+        if matches!(destination, Destination::Webhook(_)) {
+            payload = payload.webhook_identity(config, message);
+        }
+        let result = delivery::traced(
+            sink,
+            delivery::retrying(
+                config.sink_retry_policy(sink).as_deref(),
+                || async {
+                    transport
+                        .send(SentMessage::new(destination, &payload, message.id()))
+                        .await
+                        .map_err(io::Error::other)
+                },
+                |_| true,
+            ),
+        )
+        .await;
+
+        let transition = self.record_result(sink, result.is_ok());
+        Delivery::new(&result, "send the message", transition)
+    }
+
+    /// Set the transport to send the messages of the Discord sinks through
+    /// instead of `twilight_http`, such as a [`CaptureSink`] in tests
+    ///
+    /// The client passed to the handler isn't used for the sinks then, but
+    /// [`Self::channel`], [`Self::owner`] or [`Self::webhook`] still need to
+    /// be set, pins and deletions of the messages are skipped
+    pub fn transport(&mut self, transport: Arc<dyn Transport>) -> &mut Self {
+        self.config_mut().transport = Some(transport);
+        self
+    }
+
+    /// Change the transport at runtime, `None` to use `twilight_http` again
+    ///
+    /// Same as [`Self::transport`] but through a shared reference
+    pub fn set_transport(&self, transport: Option<Arc<dyn Transport>>) {
+        self.update_config(|config| config.transport = transport);
+    }
+}
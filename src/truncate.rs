@@ -0,0 +1,213 @@
+//! Shortening error messages that are too long for Discord
+
+use std::borrow::Cow;
+
+use unicode_segmentation::UnicodeSegmentation as _;
+
+use crate::ErrorHandler;
+
+/// The maximum number of characters in the content of a Discord message
+pub(crate) const MAX_CONTENT_LEN: usize = 2000;
+
+/// The text marking where the error message was cut
+const MARKER: &str = "\n[...]\n";
+
+/// The fence of code blocks
+const FENCE: &str = "```";
+
+/// The text closing a code block cut at its end
+const CLOSING_FENCE: &str = "\n```";
+
+/// The text opening a code block cut at its start
+const OPENING_FENCE: &str = "```\n";
+
+/// How error messages that are too long for Discord are shortened, see
+/// [`ErrorHandler::truncation`]
+///
+/// They're only shortened if they're too long for the description of an embed
+/// too, they're sent in one otherwise
+///
+/// The messages are cut between grapheme clusters, so that combined
+/// characters such as flags and emoji with skin tones stay whole, code blocks
+/// that are cut are closed or opened again so that the rest of the message
+/// isn't in them
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[non_exhaustive]
+pub enum Truncation {
+    /// Replace the message with [`crate::DEFAULT_ERROR_MESSAGE`], attaching
+    /// the full message as [`crate::ERROR_FILENAME`]
+    Fallback,
+    /// Keep the start of the message
+    Head,
+    /// Keep the end of the message
+    Tail,
+    /// Keep the start and the end of the message, cutting the middle
+    #[default]
+    HeadAndTail,
+}
+
+impl Truncation {
+    /// Returns the text shortened to the maximum number of characters, `None`
+    /// if it should be replaced with the fallback message
+    pub(crate) fn truncate(self, text: &str, max_len: usize) -> Option<Cow<'_, str>> {
+        if text.chars().count() <= max_len {
+            return Some(Cow::Borrowed(text));
+        }
+
+        let budget = max_len
+            .saturating_sub(MARKER.len())
+            .saturating_sub(CLOSING_FENCE.len())
+            .saturating_sub(OPENING_FENCE.len());
+        let (head_len, tail_len) = match self {
+            Self::Fallback => return None,
+            Self::Head => (budget, 0),
+            Self::Tail => (0, budget),
+            Self::HeadAndTail => (
+                budget.div_ceil(2),
+                budget.saturating_sub(budget.div_ceil(2)),
+            ),
+        };
+
+        let tail_start = boundary(text, text.chars().count().saturating_sub(tail_len), true);
+        let (before_tail, tail) = text.split_at_checked(tail_start)?;
+        let (head, _) = before_tail.split_at_checked(boundary(text, head_len, false))?;
+
+        let mut truncated = String::with_capacity(max_len);
+        truncated.push_str(head);
+        if in_code_block(head) {
+            truncated.push_str(CLOSING_FENCE);
+        }
+        truncated.push_str(MARKER);
+        if in_code_block(before_tail) {
+            truncated.push_str(OPENING_FENCE);
+        }
+        truncated.push_str(tail);
+
+        Some(Cow::Owned(truncated))
+    }
+}
+
+/// Returns whether the text ends inside a code block
+fn in_code_block(text: &str) -> bool {
+    !text.matches(FENCE).count().is_multiple_of(2)
+}
+
+/// Returns the byte index of the grapheme cluster boundary closest to the
+/// character at the index, the one after it if `after` is `true`, otherwise
+/// the one before it
+fn boundary(text: &str, index: usize, after: bool) -> usize {
+    let mut chars = 0_usize;
+    let mut before = 0_usize;
+    for (byte_index, grapheme) in text.grapheme_indices(true) {
+        if chars == index || (chars > index && after) {
+            return byte_index;
+        }
+        if chars > index {
+            return before;
+        }
+        before = byte_index;
+        chars = chars.saturating_add(grapheme.chars().count());
+    }
+
+    if chars <= index || after {
+        text.len()
+    } else {
+        before
+    }
+}
+
+impl ErrorHandler {
+    /// Set how error messages that are too long for Discord are shortened,
+    /// defaults to [`Truncation::HeadAndTail`]
+    pub fn truncation(&mut self, truncation: Truncation) -> &mut Self {
+        self.config_mut().truncation = truncation;
+        self
+    }
+
+    /// Change how error messages that are too long are shortened at runtime
+    ///
+    /// Same as [`Self::truncation`] but through a shared reference
+    pub fn set_truncation(&self, truncation: Truncation) {
+        self.update_config(|config| config.truncation = truncation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_text_is_borrowed() {
+        assert!(matches!(
+            Truncation::HeadAndTail.truncate("error", 10),
+            Some(Cow::Borrowed("error"))
+        ));
+    }
+
+    #[test]
+    fn fallback_is_none() {
+        assert_eq!(Truncation::Fallback.truncate(&"a".repeat(20), 10), None);
+    }
+
+    #[test]
+    fn head_keeps_the_start() {
+        let text = format!("start{}", "a".repeat(100));
+
+        let truncated = Truncation::Head.truncate(&text, 40).unwrap();
+
+        assert!(truncated.starts_with("start"));
+        assert!(truncated.ends_with(MARKER));
+        assert!(truncated.chars().count() <= 40);
+    }
+
+    #[test]
+    fn tail_keeps_the_end() {
+        let text = format!("{}end", "a".repeat(100));
+
+        let truncated = Truncation::Tail.truncate(&text, 40).unwrap();
+
+        assert!(truncated.starts_with(MARKER));
+        assert!(truncated.ends_with("end"));
+        assert!(truncated.chars().count() <= 40);
+    }
+
+    #[test]
+    fn head_and_tail_keeps_both() {
+        let text = format!("start{}end", "a".repeat(100));
+
+        let truncated = Truncation::HeadAndTail.truncate(&text, 40).unwrap();
+
+        assert!(truncated.starts_with("start"));
+        assert!(truncated.contains(MARKER));
+        assert!(truncated.ends_with("end"));
+        assert!(truncated.chars().count() <= 40);
+    }
+
+    #[test]
+    fn cut_code_blocks_are_closed_and_opened() {
+        let text = format!("```\n{}\n```", "a".repeat(100));
+
+        let truncated = Truncation::HeadAndTail.truncate(&text, 60).unwrap();
+
+        assert_eq!(truncated.matches(FENCE).count(), 4);
+        assert!(truncated.contains(&format!("{CLOSING_FENCE}{MARKER}{OPENING_FENCE}")));
+    }
+
+    #[test]
+    fn grapheme_clusters_stay_whole() {
+        let family = "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}";
+        let text = family.repeat(20);
+
+        let truncated = Truncation::Head.truncate(&text, 32).unwrap();
+        let head = truncated.trim_end_matches(MARKER);
+
+        assert!(!head.is_empty());
+        assert!(head.chars().count().is_multiple_of(family.chars().count()));
+        assert_eq!(head.replace(family, ""), "");
+    }
+}
@@ -0,0 +1,134 @@
+//! Choosing the severity of Twilight errors by their kind
+
+use twilight_http::{error::ErrorType, Error as HttpError};
+
+use crate::{config::Config, ErrorHandler, ErrorReport, Severity};
+
+/// The kinds of Twilight errors that have their own severity, so that
+/// problems with Discord are told apart from bugs in the bot
+///
+/// Reports of these errors are handled with the severity set with
+/// [`ErrorHandler::twilight_severity`], or [`Self::default_severity`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TwilightErrorKind {
+    /// A shard disconnected with a close code it can't connect again after,
+    /// such as an invalid token or disallowed intents
+    FatalClose,
+    /// A shard disconnected with a close code it can connect again after
+    Close,
+    /// The session of a shard was invalidated
+    InvalidatedSession,
+    /// A shard reconnected [`ErrorHandler::reconnect_limit`] times
+    ReconnectStorm,
+    /// Receiving a message of a shard failed in a way it can't recover from,
+    /// see [`crate::ShardErrorReporter::handle_receive_error`]
+    FatalReceive,
+    /// Receiving a message of a shard failed, such as if an event couldn't be
+    /// deserialized
+    ReceiveFailed,
+    /// Discord responded to a request with a 5xx status or was unavailable
+    ServerError,
+    /// Discord responded to a request with a 4xx status other than 429
+    ClientError,
+    /// Discord responded to a request with 429 Too Many Requests
+    Ratelimited,
+    /// A request couldn't be sent or its response couldn't be read, such as
+    /// when it timed out
+    RequestFailed,
+}
+
+impl TwilightErrorKind {
+    /// Returns the severity errors of the kind are handled with by default
+    ///
+    /// Fatal close codes and receive errors are [`Severity::Critical`],
+    /// client errors and reconnect storms are [`Severity::Error`], and the
+    /// others are [`Severity::Warning`] since they're usually temporary
+    /// problems with Discord
+    #[must_use]
+    pub const fn default_severity(self) -> Severity {
+        match self {
+            Self::FatalClose | Self::FatalReceive => Severity::Critical,
+            Self::ClientError | Self::ReconnectStorm => Severity::Error,
+            Self::Close
+            | Self::InvalidatedSession
+            | Self::ReceiveFailed
+            | Self::ServerError
+            | Self::Ratelimited
+            | Self::RequestFailed => Severity::Warning,
+        }
+    }
+
+    /// Returns the kind of the `twilight_http` error, `None` if it's not one
+    /// of the kinds, such as if building the request failed
+    #[must_use]
+    #[allow(clippy::wildcard_enum_match_arm)]
+    pub const fn from_http_error(error: &HttpError) -> Option<Self> {
+        match error.kind() {
+            ErrorType::Response { status, .. } if status.get() == 429 => Some(Self::Ratelimited),
+            ErrorType::Response { status, .. } if status.is_server_error() => {
+                Some(Self::ServerError)
+            }
+            ErrorType::Response { status, .. } if status.is_client_error() => {
+                Some(Self::ClientError)
+            }
+            ErrorType::ServiceUnavailable { .. } => Some(Self::ServerError),
+            ErrorType::Unauthorized => Some(Self::ClientError),
+            ErrorType::ChunkingResponse
+            | ErrorType::RequestCanceled
+            | ErrorType::RequestError
+            | ErrorType::RequestTimedOut => Some(Self::RequestFailed),
+            _ => None,
+        }
+    }
+}
+
+impl Config {
+    /// Returns the severity errors of the kind are handled with
+    pub(crate) fn twilight_severity(&self, kind: TwilightErrorKind) -> Severity {
+        self.twilight_severities
+            .iter()
+            .find_map(|(overridden, severity)| (*overridden == kind).then_some(*severity))
+            .unwrap_or_else(|| kind.default_severity())
+    }
+}
+
+impl ErrorReport {
+    /// Set the kind of the Twilight error, handling it with the severity of
+    /// the kind
+    ///
+    /// This is set by [`Self::from_error`] and
+    /// [`ErrorHandler::handle_gateway_event`], calling [`Self::severity`]
+    /// afterwards overrides the severity of the kind
+    pub const fn twilight_kind(mut self, kind: TwilightErrorKind) -> Self {
+        self.severity = kind.default_severity();
+        self.twilight_kind = Some(kind);
+        self
+    }
+}
+
+impl ErrorHandler {
+    /// Set the severity Twilight errors of the kind are handled with instead
+    /// of [`TwilightErrorKind::default_severity`]
+    pub fn twilight_severity(&mut self, kind: TwilightErrorKind, severity: Severity) -> &mut Self {
+        let severities = &mut self.config_mut().twilight_severities;
+        severities.retain(|(overridden, _)| *overridden != kind);
+        severities.push((kind, severity));
+        self
+    }
+
+    /// Change the severity Twilight errors of the kind are handled with at
+    /// runtime, `None` to use its default severity again
+    ///
+    /// Same as [`Self::twilight_severity`] but through a shared reference
+    pub fn set_twilight_severity(&self, kind: TwilightErrorKind, severity: Option<Severity>) {
+        self.update_config(|config| {
+            config
+                .twilight_severities
+                .retain(|(overridden, _)| *overridden != kind);
+            if let Some(overriding) = severity {
+                config.twilight_severities.push((kind, overriding));
+            }
+        });
+    }
+}
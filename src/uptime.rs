@@ -0,0 +1,90 @@
+//! Attaching how long the handler has been running and how many times the
+//! application restarted today
+
+use core::time::Duration;
+use std::{fs, io, path::Path};
+
+use chrono::{Local, NaiveDate};
+
+use crate::{timestamp::format_duration, ErrorHandler};
+
+/// The name of the field the uptime and the restart count are attached as
+pub const UPTIME_FIELD: &str = "Uptime";
+
+/// Returns the restart count of today after this restart, the file holds the
+/// date and the count such as `2024-05-01 7`, counting starts over on a new
+/// day or if the file is missing or invalid
+fn count_restart(path: &Path) -> io::Result<u32> {
+    let today = Local::now().date_naive();
+    let previous = match fs::read_to_string(path) {
+        Ok(contents) => contents
+            .trim()
+            .split_once(' ')
+            .and_then(|(date, count)| Some((date.parse::<NaiveDate>().ok()?, count.parse().ok()?)))
+            .filter(|(date, _)| *date == today)
+            .map_or(0, |(_, count): (NaiveDate, u32)| count),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => 0,
+        Err(err) => return Err(err),
+    };
+
+    let restart = previous.saturating_add(1);
+    fs::write(path, format!("{today} {restart}\n"))?;
+
+    Ok(restart)
+}
+
+impl ErrorHandler {
+    /// Set whether to attach how long the handler has been running as
+    /// [`UPTIME_FIELD`], such as `3h 12m, restart #7 today`
+    ///
+    /// The uptime is measured from when the handler was made, the restart is
+    /// only included if [`Self::restart_counter`] is set, defaults to `false`
+    pub fn include_uptime(&mut self, include: bool) -> &mut Self {
+        self.config_mut().include_uptime = include;
+        self
+    }
+
+    /// Change whether to attach how long the handler has been running at
+    /// runtime
+    ///
+    /// Same as [`Self::include_uptime`] but through a shared reference
+    pub fn set_include_uptime(&self, include: bool) {
+        self.update_config(|config| config.include_uptime = include);
+    }
+
+    /// Count this start of the application in the file at the path, keeping
+    /// the number of restarts today across restarts
+    ///
+    /// Call this once when the application starts, the count is attached to
+    /// reports with [`Self::include_uptime`], a crash loop shows up as a
+    /// quickly increasing count
+    ///
+    /// # Errors
+    /// Returns the error if reading or writing the file fails
+    pub fn restart_counter(&mut self, path: impl AsRef<Path>) -> io::Result<&mut Self> {
+        self.restart = Some(count_restart(path.as_ref())?);
+        Ok(self)
+    }
+
+    /// Returns how long the handler has been running
+    pub fn uptime(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+    /// Returns the number of times the application restarted today,
+    /// including this start, if [`Self::restart_counter`] is set
+    pub const fn restarts_today(&self) -> Option<u32> {
+        self.restart
+    }
+
+    /// Returns the uptime field, such as `3h 12m, restart #7 today`
+    pub(crate) fn uptime_field(&self) -> (String, String) {
+        let uptime = format_duration(self.uptime());
+        let value = match self.restart {
+            Some(restart) => format!("{uptime}, restart #{restart} today"),
+            None => uptime,
+        };
+
+        (UPTIME_FIELD.to_owned(), value)
+    }
+}
@@ -0,0 +1,190 @@
+//! Degrading to stderr and the file when delivering to Discord keeps failing
+
+use core::time::Duration;
+use std::sync::{Mutex, PoisonError};
+
+use tokio::time::Instant;
+use twilight_http::Client;
+
+use crate::{
+    config::Config, formatter::Message, timestamp::format_duration, DiagnosticEvent, ErrorHandler,
+};
+
+/// The notice of a change in whether errors are delivered to Discord
+#[derive(Clone, Copy, Debug)]
+enum Notice {
+    /// Delivering to Discord failed too many times in a row
+    Degraded {
+        /// The number of consecutive failures that caused it
+        failures: u32,
+        /// How long errors aren't delivered to Discord
+        cooldown: Duration,
+    },
+    /// Delivering to Discord succeeded after the cooldown
+    Recovered,
+}
+
+/// The state of the watchdog
+#[derive(Debug)]
+struct State {
+    /// The number of consecutive failures to degrade after and how long to
+    /// degrade for, `None` to never degrade
+    settings: Option<(u32, Duration)>,
+    /// The number of times delivering to Discord failed in a row
+    consecutive_failures: u32,
+    /// When delivering was degraded or last retried, `None` if it isn't
+    degraded_since: Option<Instant>,
+    /// The notice that wasn't delivered yet
+    notice: Option<Notice>,
+}
+
+/// Stops delivering to Discord for a while after it keeps failing across
+/// every Discord sink
+#[derive(Debug)]
+pub(crate) struct Watchdog {
+    /// The state of the watchdog
+    state: Mutex<State>,
+}
+
+impl Watchdog {
+    /// Make a watchdog that never degrades
+    pub(crate) const fn new() -> Self {
+        Self {
+            state: Mutex::new(State {
+                settings: None,
+                consecutive_failures: 0,
+                degraded_since: None,
+                notice: None,
+            }),
+        }
+    }
+
+    /// Set the number of consecutive failures to degrade after and how long
+    /// to degrade for, `None` to never degrade
+    fn configure(&self, settings: Option<(u32, Duration)>) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.settings = settings;
+        if settings.is_none() {
+            state.consecutive_failures = 0;
+            state.degraded_since = None;
+        }
+    }
+
+    /// Returns whether errors shouldn't be delivered to Discord
+    ///
+    /// Delivering is allowed once every cooldown to retry Discord
+    pub(crate) fn degraded(&self) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some((_, cooldown)) = state.settings else {
+            return false;
+        };
+
+        match state.degraded_since {
+            Some(since) if since.elapsed() >= cooldown => {
+                state.degraded_since = Some(Instant::now());
+                false
+            }
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    /// Records the result of delivering to a Discord sink
+    pub(crate) fn record(&self, succeeded: bool) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some((threshold, cooldown)) = state.settings else {
+            return;
+        };
+
+        if succeeded {
+            state.consecutive_failures = 0;
+            if state.degraded_since.take().is_some() {
+                state.notice = Some(Notice::Recovered);
+            }
+            return;
+        }
+
+        state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+        if state.degraded_since.is_none() && state.consecutive_failures < threshold {
+            return;
+        }
+
+        if state.degraded_since.replace(Instant::now()).is_none() {
+            state.notice = Some(Notice::Degraded {
+                failures: state.consecutive_failures,
+                cooldown,
+            });
+        }
+    }
+
+    /// Removes and returns the notice that wasn't delivered yet
+    fn take_notice(&self) -> Option<Notice> {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .notice
+            .take()
+    }
+}
+
+impl ErrorHandler {
+    /// Returns whether delivering to Discord is degraded, see
+    /// [`Self::watchdog`]
+    pub fn is_degraded(&self) -> bool {
+        self.watchdog
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .degraded_since
+            .is_some()
+    }
+
+    /// Delivers the notice of the watchdog if there's one, printing and
+    /// appending it to the file if delivering was degraded
+    pub(crate) async fn report_watchdog_notice(&self, http: &Client, config: &Config) {
+        match self.watchdog.take_notice() {
+            Some(Notice::Degraded { failures, cooldown }) => {
+                self.diagnose(&DiagnosticEvent::Degraded { failures, cooldown });
+                let notice = format!(
+                    "\n\nError reporting degraded: delivering to Discord failed {failures} times \
+                     in a row, errors are only printed and appended to the file for the next \
+                     {}, then Discord is retried",
+                    format_duration(cooldown)
+                );
+                let delivery = self.maybe_append_error(config, Message::Notice(&notice), "");
+                config.print_notice(&format!(
+                    "{}{}",
+                    notice.trim_start(),
+                    delivery.note.unwrap_or_default()
+                ));
+            }
+            Some(Notice::Recovered) => {
+                self.diagnose(&DiagnosticEvent::Recovered);
+                let notice = "\n\nError reporting recovered, errors are delivered to Discord \
+                              again";
+                self.report(http, config, Message::Notice(notice), &[])
+                    .await;
+            }
+            None => {}
+        }
+    }
+
+    /// Stop delivering to Discord for `cooldown` after delivering to every
+    /// Discord sink fails `failures` times in a row
+    ///
+    /// Meanwhile errors are only printed to [`std::io::stderr`] and appended
+    /// to the file, a single notice is printed and appended to the file when
+    /// it's degraded and delivered when Discord works again, which is retried
+    /// once every cooldown
+    pub fn watchdog(&mut self, failures: u32, cooldown: Duration) -> &mut Self {
+        self.watchdog.configure(Some((failures, cooldown)));
+        self
+    }
+
+    /// Change the watchdog settings at runtime, `None` to never degrade
+    ///
+    /// Same as [`Self::watchdog`] but through a shared reference
+    pub fn set_watchdog(&self, settings: Option<(u32, Duration)>) {
+        self.watchdog.configure(settings);
+    }
+}
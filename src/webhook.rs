@@ -0,0 +1,182 @@
+//! Checking that the webhook exists before executing it
+
+use core::fmt;
+use std::sync::{Mutex, PoisonError};
+
+use twilight_http::{error::ErrorType, Client, Error as HttpError};
+use twilight_model::id::{
+    marker::{ChannelMarker, GuildMarker, WebhookMarker},
+    Id,
+};
+
+use crate::{
+    config::Config,
+    delivery, execute_webhook,
+    formatter::Message,
+    payload::{self, Payload},
+    ErrorHandler, FormattedOutput,
+};
+#[cfg(feature = "test-util")]
+use crate::{Destination, SentMessage};
+
+/// The statuses Discord responds with if the webhook or its token is invalid
+const INVALID_STATUSES: [u16; 3] = [401, 403, 404];
+
+/// Where the webhook posts to, returned from [`ErrorHandler::validate`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct WebhookDetails {
+    /// The channel the webhook posts to
+    pub channel_id: Id<ChannelMarker>,
+    /// The guild of the channel
+    pub guild_id: Option<Id<GuildMarker>>,
+}
+
+impl fmt::Display for WebhookDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the channel {}", self.channel_id)?;
+        if let Some(guild_id) = self.guild_id {
+            write!(f, " of the guild {guild_id}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A checked webhook and token, and its details or why it's invalid
+type CheckedWebhook = (Id<WebhookMarker>, String, Result<WebhookDetails, String>);
+
+/// The result of checking the webhook, cached until the webhook changes
+#[derive(Debug, Default)]
+pub(crate) struct WebhookCheck {
+    /// The last checked webhook
+    checked: Mutex<Option<CheckedWebhook>>,
+}
+
+impl WebhookCheck {
+    /// Returns the details of the webhook, checking it if it isn't cached,
+    /// `None` if checking it failed for another reason than it being invalid
+    pub(crate) async fn get(
+        &self,
+        http: &Client,
+        webhook_id: Id<WebhookMarker>,
+        token: &str,
+    ) -> Option<Result<WebhookDetails, String>> {
+        let cached = self
+            .checked
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        if let Some((cached_id, cached_token, result)) = cached {
+            if cached_id == webhook_id && cached_token == token {
+                return Some(result);
+            }
+        }
+
+        self.check(http, webhook_id, token).await
+    }
+
+    /// Checks the webhook and caches the result, `None` if checking it failed
+    /// for another reason than it being invalid
+    async fn check(
+        &self,
+        http: &Client,
+        webhook_id: Id<WebhookMarker>,
+        token: &str,
+    ) -> Option<Result<WebhookDetails, String>> {
+        let result = match http.webhook(webhook_id).token(token).await {
+            Ok(response) => match response.model().await {
+                Ok(webhook) => Ok(WebhookDetails {
+                    channel_id: webhook.channel_id,
+                    guild_id: webhook.guild_id,
+                }),
+                Err(_) => return None,
+            },
+            Err(err) if is_invalid(&err) => Err(err.to_string()),
+            Err(_) => return None,
+        };
+
+        *self.checked.lock().unwrap_or_else(PoisonError::into_inner) =
+            Some((webhook_id, token.to_owned(), result.clone()));
+        Some(result)
+    }
+}
+
+/// Returns whether the error is because the webhook or its token is invalid
+fn is_invalid(error: &HttpError) -> bool {
+    matches!(
+        error.kind(),
+        ErrorType::Response { status, .. } if INVALID_STATUSES.contains(&status.get())
+    )
+}
+
+impl ErrorHandler {
+    /// Check that the webhook exists and its token is valid, returning where
+    /// it posts to, `Ok(None)` if it isn't set
+    ///
+    /// The result is cached, an invalid webhook isn't executed until it's
+    /// changed or this is called again, this is also done when the webhook is
+    /// first used
+    ///
+    /// # Errors
+    /// Returns why the webhook is invalid or checking it failed
+    pub async fn validate(&self, http: &Client) -> Result<Option<WebhookDetails>, String> {
+        let Some((webhook_id, token)) = self.config().webhook.clone() else {
+            return Ok(None);
+        };
+
+        self.webhook_check
+            .check(http, webhook_id, &token)
+            .await
+            .unwrap_or_else(|| Err("Checking the webhook failed".to_owned()))
+            .map(Some)
+    }
+}
+
+/// Executes a webhook other than [`ErrorHandler::webhook`], such as the
+/// escalation webhook, with the content, attaching the backtrace of the message
+/// and the end of the log file
+///
+/// Returns the note of why it failed, the webhook is described with its name
+pub(crate) async fn execute_additional(
+    http: &Client,
+    config: &Config,
+    (webhook_id, token): &(Id<WebhookMarker>, String),
+    name: &str,
+    message: Message<'_>,
+    output: &FormattedOutput,
+    full_content: &str,
+) -> Option<String> {
+    if config.dry_run {
+        return Some(format!(
+            "\n\nDry run, would execute the {name} {webhook_id}"
+        ));
+    }
+
+    let payload = Payload::new(
+        config,
+        full_content,
+        &output.embeds,
+        payload::attachments(config, message),
+    )
+    .webhook_identity(config, message);
+
+    #[cfg(feature = "test-util")]
+    let result = match &config.transport {
+        Some(transport) => {
+            let sent = SentMessage::new(Destination::Webhook(*webhook_id), &payload, message.id());
+            delivery::traced(name, transport.send(sent)).await
+        }
+        None => delivery::traced(name, execute_webhook(http, *webhook_id, token, &payload))
+            .await
+            .map_err(|err| err.to_string()),
+    };
+    #[cfg(not(feature = "test-util"))]
+    let result = delivery::traced(name, execute_webhook(http, *webhook_id, token, &payload)).await;
+
+    result.err().map(|err| {
+        config.redact(
+            &format!("\n\nFailed to execute the {name}: {err}"),
+            http.token(),
+        )
+    })
+}
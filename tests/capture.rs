@@ -0,0 +1,183 @@
+//! Delivering errors through a capture sink instead of Discord
+
+mod common;
+
+use core::time::Duration;
+use std::{fs, sync::Arc};
+
+use common::{handler, http, FlakyTransport};
+use regex::Regex;
+use twilight_error::{
+    CaptureSink, DeliveryMode, Destination, Severity, Truncation, DEFAULT_ERROR_MESSAGE,
+    ERROR_FILENAME, REDACTED,
+};
+use twilight_model::id::Id;
+
+/// A token shaped like a bot token, different from the token of the client
+const BOT_TOKEN: &str = "MTIzNDU2Nzg5MDEyMzQ1Njc4OQ.G1a2b3.abcdefghijklmnopqrstuvwxyz12345";
+
+#[tokio::test]
+async fn error_is_sent_to_the_channel() {
+    let capture = Arc::new(CaptureSink::new());
+    let handler = handler(capture.clone());
+
+    let id = handler.handle(&http(), "oh no").await;
+
+    let sent = capture.sent_to(Destination::Channel(Id::new(1)));
+    assert_eq!(sent.len(), 1);
+    assert!(sent[0].content.contains("oh no"));
+    assert!(sent[0].content.contains(&id.to_string()));
+    assert_eq!(sent[0].idempotency_key, Some(id));
+}
+
+#[tokio::test]
+async fn circuit_breaker_disables_and_enables_the_sink() {
+    let transport = Arc::new(FlakyTransport::default());
+    let mut handler = handler(transport.clone());
+    handler.circuit_breaker(2, Duration::ZERO);
+    let http = http();
+
+    transport.fail(true);
+    handler.handle(&http, "first").await;
+    handler.handle(&http, "second").await;
+    // the notice about disabling the channel is probed right away and fails too
+    assert_eq!(handler.stats().channel.failures, 3);
+
+    transport.fail(false);
+    handler.handle(&http, "third").await;
+
+    let sent = transport.capture.sent();
+    assert!(sent[0].content.contains("third"));
+    assert!(sent[1]
+        .content
+        .contains("Enabled the channel again after a successful retry"));
+    assert_eq!(handler.stats().channel.successes, 2);
+}
+
+#[tokio::test]
+async fn secrets_are_redacted() {
+    let capture = Arc::new(CaptureSink::new());
+    let mut handler = handler(capture.clone());
+    handler.redact_secrets(true);
+
+    handler
+        .handle(
+            &http(),
+            format!("request with {BOT_TOKEN} failed, the client has client-token"),
+        )
+        .await;
+
+    let content = &capture.sent()[0].content;
+    assert!(!content.contains(BOT_TOKEN));
+    assert!(!content.contains("client-token"));
+    assert_eq!(content.matches(REDACTED).count(), 2);
+}
+
+#[tokio::test]
+async fn rules_and_personal_information_are_redacted() {
+    let capture = Arc::new(CaptureSink::new());
+    let mut handler = handler(capture.clone());
+    handler
+        .redact(Regex::new(r"user \d+").unwrap())
+        .redact_pii();
+
+    handler
+        .handle(
+            &http(),
+            "request for user 42 at admin@example.com from 10.0.0.1 failed",
+        )
+        .await;
+
+    let content = &capture.sent()[0].content;
+    assert!(!content.contains("user 42"));
+    assert!(content.contains(REDACTED));
+    assert!(content.contains("[email]"));
+    assert!(content.contains("[ip]"));
+}
+
+#[tokio::test]
+async fn stats_count_errors_below_the_minimum_severity() {
+    let capture = Arc::new(CaptureSink::new());
+    let mut handler = handler(capture.clone());
+    handler.min_severity(Severity::Error);
+
+    handler
+        .handle_with_severity(&http(), Severity::Warning, "minor")
+        .await;
+    handler.handle(&http(), "major").await;
+
+    let sent = capture.sent();
+    assert_eq!(sent.len(), 1);
+    assert!(sent[0].content.contains("major"));
+    let stats = handler.stats();
+    assert_eq!(stats.total, 2);
+    assert_eq!(stats.warnings, 1);
+    assert_eq!(stats.errors, 1);
+    assert_eq!(stats.last_minute, 2);
+    assert_eq!(stats.channel.successes, 1);
+}
+
+#[tokio::test]
+async fn too_long_error_is_truncated() {
+    let capture = Arc::new(CaptureSink::new());
+    let handler = handler(capture.clone());
+
+    handler
+        .handle(&http(), format!("start{}end", "a".repeat(10_000)))
+        .await;
+
+    let sent = &capture.sent()[0];
+    assert!(sent.content.chars().count() <= 2_000);
+    assert!(sent.content.contains("start"));
+    assert!(sent.content.contains("[...]"));
+    assert!(sent.content.contains("end"));
+    assert!(sent.attachments.is_empty());
+}
+
+#[tokio::test]
+async fn fallback_attaches_the_error() {
+    let capture = Arc::new(CaptureSink::new());
+    let mut handler = handler(capture.clone());
+    handler.truncation(Truncation::Fallback);
+
+    handler.handle(&http(), "a".repeat(10_000)).await;
+
+    let sent = &capture.sent()[0];
+    assert_eq!(sent.content, DEFAULT_ERROR_MESSAGE);
+    assert_eq!(sent.attachments[0].filename, ERROR_FILENAME);
+}
+
+#[tokio::test]
+async fn long_error_is_sent_in_an_embed() {
+    let capture = Arc::new(CaptureSink::new());
+    let handler = handler(capture.clone());
+    let error = "a".repeat(3_000);
+
+    handler.handle(&http(), &error).await;
+
+    let sent = &capture.sent()[0];
+    assert!(sent.content.is_empty());
+    assert!(sent.embeds[0]
+        .description
+        .as_deref()
+        .is_some_and(|description| description.contains(&error)));
+}
+
+#[tokio::test]
+async fn dry_run_falls_back_to_the_file() {
+    let capture = Arc::new(CaptureSink::new());
+    let path = std::env::temp_dir().join(format!("twilight-error-dry-run-{}", std::process::id()));
+    let mut handler = handler(capture.clone());
+    handler
+        .webhook(Id::new(2), "token".to_owned())
+        .file(&path)
+        .delivery_mode(DeliveryMode::Fallback)
+        .dry_run(true);
+
+    handler.handle(&http(), "oh no").await;
+
+    let file = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(capture.sent().is_empty());
+    assert!(file.contains("oh no"));
+}
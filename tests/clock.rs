@@ -0,0 +1,208 @@
+//! Policies that depend on time, tested with Tokio's paused clock
+
+mod common;
+
+use core::time::Duration;
+use std::sync::Arc;
+
+use common::{handler, http, FlakyTransport};
+use tokio::time;
+use twilight_error::{CaptureSink, Destination, Severity};
+use twilight_model::id::Id;
+
+#[tokio::test(start_paused = true)]
+async fn repeated_errors_are_escalated_in_the_window() {
+    let capture = Arc::new(CaptureSink::new());
+    let mut handler = handler(capture.clone());
+    handler.escalate_repeated(
+        2,
+        Duration::from_mins(1),
+        Id::new(2),
+        "token",
+        Some(Id::new(3)),
+    );
+    let http = http();
+    let escalated = || capture.sent_to(Destination::Webhook(Id::new(2)));
+
+    handler.handle(&http, "disk full").await;
+    handler.handle(&http, "disk full").await;
+    assert!(escalated().is_empty());
+
+    handler.handle(&http, "disk full").await;
+    assert_eq!(escalated().len(), 1);
+    assert!(escalated()[0]
+        .content
+        .starts_with("<@&3> Happened 3 times in the last 60s"));
+
+    time::advance(Duration::from_mins(1)).await;
+    handler.handle(&http, "disk full").await;
+    assert_eq!(escalated().len(), 1);
+    assert_eq!(capture.sent_to(Destination::Channel(Id::new(1))).len(), 4);
+
+    handler.set_escalate_repeated(None);
+    for _ in 0_u8..3 {
+        handler.handle(&http, "disk full").await;
+    }
+    assert_eq!(escalated().len(), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn crash_loop_is_muted_for_the_window() {
+    let capture = Arc::new(CaptureSink::new());
+    let mut handler = handler(capture.clone());
+    handler.crash_loop_detection(2, Duration::from_mins(1), Some(Id::new(3)));
+    let http = http();
+
+    handler.handle(&http, "failed to connect").await;
+    handler.handle(&http, "failed to connect").await;
+    handler.handle(&http, "failed to connect").await;
+
+    let sent = capture.sent();
+    assert_eq!(sent.len(), 2);
+    assert!(sent[1].content.starts_with("<@&3>"));
+    assert!(sent[1].content.contains("Crash loop"));
+
+    time::advance(Duration::from_mins(1)).await;
+    handler.handle(&http, "failed to connect").await;
+    assert_eq!(capture.sent().len(), 3);
+}
+
+#[tokio::test(start_paused = true)]
+async fn watchdog_retries_discord_after_the_cooldown() {
+    let transport = Arc::new(FlakyTransport::default());
+    let mut handler = handler(transport.clone());
+    handler.watchdog(2, Duration::from_mins(5));
+    let http = http();
+
+    transport.fail(true);
+    handler.handle(&http, "first").await;
+    handler.handle(&http, "second").await;
+    assert!(handler.is_degraded());
+
+    transport.fail(false);
+    handler.handle(&http, "during the cooldown").await;
+    assert!(transport.capture.sent().is_empty());
+    assert!(handler.is_degraded());
+
+    time::advance(Duration::from_mins(5)).await;
+    handler.handle(&http, "after the cooldown").await;
+    assert!(!handler.is_degraded());
+
+    let sent = transport.capture.sent();
+    assert!(sent[0].content.contains("after the cooldown"));
+    assert!(sent[1]
+        .content
+        .contains("Error reporting recovered, errors are delivered to Discord again"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn circuit_breaker_probes_after_the_interval() {
+    let transport = Arc::new(FlakyTransport::default());
+    let mut handler = handler(transport.clone());
+    handler.circuit_breaker(1, Duration::from_secs(30));
+    let http = http();
+
+    transport.fail(true);
+    handler.handle(&http, "first").await;
+    assert_eq!(handler.stats().channel.failures, 1);
+
+    transport.fail(false);
+    handler.handle(&http, "before the probe").await;
+    assert!(transport.capture.sent().is_empty());
+
+    time::advance(Duration::from_secs(30)).await;
+    handler.handle(&http, "probe").await;
+
+    let sent = transport.capture.sent();
+    assert!(sent[0].content.contains("probe"));
+    assert!(sent[1]
+        .content
+        .contains("Enabled the channel again after a successful retry"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn escalation_ends_when_the_rate_drops() {
+    let capture = Arc::new(CaptureSink::new());
+    let mut handler = handler(capture.clone());
+    handler.escalation(2, Some(Id::new(3)));
+    let http = http();
+
+    for _ in 0_u8..4 {
+        handler.handle(&http, "rate limited").await;
+    }
+
+    let sent = capture.sent();
+    assert_eq!(sent.len(), 3);
+    assert!(sent[2]
+        .content
+        .contains("<@&3> More than 2 errors were handled in the last minute"));
+
+    time::advance(Duration::from_mins(1)).await;
+    handler.handle(&http, "rate limited").await;
+
+    let sent = capture.sent();
+    assert!(sent[3]
+        .content
+        .contains("The error rate dropped, errors are delivered to every sink again"));
+    assert!(sent[4].content.contains("rate limited"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn stats_rotate_out_of_the_last_minute() {
+    let capture = Arc::new(CaptureSink::new());
+    let handler = handler(capture);
+    let http = http();
+
+    handler.handle(&http, "first").await;
+    time::advance(Duration::from_secs(30)).await;
+    handler.handle(&http, "second").await;
+    assert_eq!(handler.stats().last_minute, 2);
+
+    time::advance(Duration::from_secs(31)).await;
+    let stats = handler.stats();
+    assert_eq!(stats.last_minute, 1);
+    assert_eq!(stats.last_hour, 2);
+
+    time::advance(Duration::from_hours(1)).await;
+    let stats = handler.stats();
+    assert_eq!(stats.last_hour, 0);
+    assert_eq!(stats.total, 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn heartbeat_is_only_delivered_without_errors() {
+    let capture = Arc::new(CaptureSink::new());
+    let handler = Arc::new(handler(capture.clone()));
+    let http = Arc::new(http());
+    let heartbeat = Arc::clone(&handler).spawn_heartbeat(Arc::clone(&http), Duration::from_mins(1));
+
+    time::sleep(Duration::from_secs(61)).await;
+    assert_eq!(capture.sent().len(), 1);
+    assert!(capture.sent()[0]
+        .content
+        .contains("No errors in the last 60s"));
+
+    handler.handle(&http, "oh no").await;
+    time::sleep(Duration::from_mins(1)).await;
+    assert_eq!(capture.sent().len(), 2);
+
+    time::sleep(Duration::from_mins(1)).await;
+    assert_eq!(capture.sent().len(), 3);
+    heartbeat.abort();
+}
+
+#[tokio::test(start_paused = true)]
+async fn snapshot_uptime_is_measured_from_the_handler() {
+    let capture = Arc::new(CaptureSink::new());
+    time::advance(Duration::from_hours(1)).await;
+    let mut handler = handler(capture.clone());
+    handler.snapshot_critical(true);
+
+    time::advance(Duration::from_secs(90)).await;
+    handler
+        .handle_with_severity(&http(), Severity::Critical, "oh no")
+        .await;
+
+    assert_eq!(handler.uptime(), Duration::from_secs(90));
+    assert!(capture.sent()[0].content.contains("Uptime: 1m 30s"));
+}
@@ -0,0 +1,50 @@
+//! Fixtures shared by the integration tests
+
+// each test crate only uses some of them
+#![allow(dead_code)]
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use twilight_error::{CaptureSink, ErrorHandler, SentMessage, Transport, TransportFuture};
+use twilight_http::Client;
+use twilight_model::id::Id;
+
+/// Returns a handler delivering to a channel through the transport
+pub fn handler(transport: Arc<impl Transport + 'static>) -> ErrorHandler {
+    let mut handler = ErrorHandler::new();
+    handler.transport(transport).channel(Id::new(1));
+    handler
+}
+
+/// Returns the client the errors are handled with, never used for requests
+pub fn http() -> Client {
+    Client::new("client-token".to_owned())
+}
+
+/// A transport that fails while it's told to
+#[derive(Debug, Default)]
+pub struct FlakyTransport {
+    /// Whether sending fails
+    pub failing: AtomicBool,
+    /// The messages sent while it wasn't failing
+    pub capture: CaptureSink,
+}
+
+impl FlakyTransport {
+    /// Make sending fail or succeed
+    pub fn fail(&self, failing: bool) {
+        self.failing.store(failing, Ordering::Relaxed);
+    }
+}
+
+impl Transport for FlakyTransport {
+    fn send(&self, message: SentMessage) -> TransportFuture<'_> {
+        if self.failing.load(Ordering::Relaxed) {
+            return Box::pin(async { Err("the transport is down".to_owned()) });
+        }
+        self.capture.send(message)
+    }
+}
@@ -0,0 +1,71 @@
+//! Saving the recently handled errors and loading them after a restart
+
+mod common;
+
+use std::{env, fs, path::PathBuf, process, sync::Arc};
+
+use common::{handler, http};
+use twilight_error::CaptureSink;
+
+/// Returns the path of a history file that doesn't exist yet
+fn history_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!(
+        "twilight-error-history-{name}-{}.json",
+        process::id()
+    ))
+}
+
+#[tokio::test]
+async fn saved_errors_are_loaded_before_the_new_ones() {
+    let path = history_path("loaded");
+    let before = handler(Arc::new(CaptureSink::new()));
+    let http = http();
+    before.handle(&http, "first").await;
+    before.handle(&http, "second").await;
+    before.save_history(&path).unwrap();
+
+    let mut after = handler(Arc::new(CaptureSink::new()));
+    after.history(2);
+    after.handle(&http, "third").await;
+    after.load_history(&path).unwrap();
+
+    let errors: Vec<_> = after
+        .recent_errors()
+        .into_iter()
+        .map(|record| record.error)
+        .collect();
+    assert_eq!(errors, ["third", "second"]);
+    fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn saving_replaces_the_file() {
+    let path = history_path("replaced");
+    fs::write(&path, "not json").unwrap();
+    let handler = handler(Arc::new(CaptureSink::new()));
+    handler.handle(&http(), "oh no").await;
+
+    handler.save_history(&path).unwrap();
+    assert!(fs::read_to_string(&path).unwrap().contains("oh no"));
+
+    let mut temp_path = path.clone().into_os_string();
+    temp_path.push(".tmp");
+    assert!(!PathBuf::from(temp_path).exists());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn missing_file_loads_nothing() {
+    let handler = handler(Arc::new(CaptureSink::new()));
+    handler.load_history(history_path("missing")).unwrap();
+    assert!(handler.recent_errors().is_empty());
+}
+
+#[test]
+fn invalid_file_is_an_error() {
+    let path = history_path("invalid");
+    fs::write(&path, "not json").unwrap();
+    let handler = handler(Arc::new(CaptureSink::new()));
+    assert!(handler.load_history(&path).is_err());
+    fs::remove_file(path).unwrap();
+}
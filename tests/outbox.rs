@@ -0,0 +1,82 @@
+//! Keeping errors in the outbox until they're delivered to Discord
+
+mod common;
+
+use core::time::Duration;
+use std::{env, fs, path::PathBuf, process, sync::Arc};
+
+use common::{handler, http, FlakyTransport};
+use tokio::{sync::Notify, time};
+use twilight_error::{CaptureSink, SentMessage, Transport, TransportFuture};
+
+/// Returns the path of an outbox that doesn't exist yet
+fn outbox_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("twilight-error-outbox-{name}-{}", process::id()))
+}
+
+/// A transport that waits until it's released before sending
+#[derive(Debug, Default)]
+struct GatedTransport {
+    /// Notified when sending starts
+    started: Notify,
+    /// Notified to let the message be sent
+    release: Notify,
+    /// The messages sent
+    capture: CaptureSink,
+}
+
+impl Transport for GatedTransport {
+    fn send(&self, message: SentMessage) -> TransportFuture<'_> {
+        Box::pin(async move {
+            self.started.notify_one();
+            self.release.notified().await;
+            self.capture.send(message).await
+        })
+    }
+}
+
+#[tokio::test]
+async fn undelivered_errors_are_delivered_later() {
+    let transport = Arc::new(FlakyTransport::default());
+    let path = outbox_path("later");
+    let mut handler = handler(transport.clone());
+    handler.outbox(&path);
+    let http = http();
+
+    transport.fail(true);
+    handler.handle(&http, "oh no").await;
+    assert!(fs::read_to_string(&path).unwrap().contains("oh no"));
+
+    transport.fail(false);
+    assert_eq!(handler.deliver_outbox(&http).await.unwrap(), 1);
+    assert!(!path.exists());
+    assert!(transport.capture.sent()[0].content.contains("oh no"));
+    assert_eq!(handler.deliver_outbox(&http).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn errors_being_delivered_are_skipped() {
+    let transport = Arc::new(GatedTransport::default());
+    let path = outbox_path("skipped");
+    let mut handler = handler(transport.clone());
+    handler.outbox(&path);
+    let http = http();
+
+    let ((), delivered) = tokio::join!(
+        async {
+            handler.handle(&http, "oh no").await;
+        },
+        async {
+            transport.started.notified().await;
+            let delivered = time::timeout(Duration::from_secs(5), handler.deliver_outbox(&http))
+                .await
+                .map(Result::unwrap);
+            transport.release.notify_one();
+            delivered
+        }
+    );
+
+    assert_eq!(delivered, Ok(0));
+    assert_eq!(transport.capture.sent().len(), 1);
+    assert!(!path.exists());
+}
@@ -0,0 +1,42 @@
+//! Reporting panics through the panic hook
+
+mod common;
+
+use core::{future, time::Duration};
+use std::{
+    sync::{mpsc, Arc},
+    thread,
+};
+
+use common::{handler, http};
+use twilight_error::{SentMessage, Severity, Transport, TransportFuture};
+
+/// A transport that never finishes sending
+#[derive(Debug)]
+struct StalledTransport;
+
+impl Transport for StalledTransport {
+    fn send(&self, _message: SentMessage) -> TransportFuture<'_> {
+        Box::pin(future::pending())
+    }
+}
+
+#[test]
+fn panic_hook_gives_up_after_the_fatal_deadline() {
+    let mut handler = handler(Arc::new(StalledTransport));
+    handler.fatal_deadline(Duration::from_millis(100));
+    let handler = Arc::new(handler);
+    Arc::clone(&handler).install_panic_hook(Arc::new(http()));
+
+    let (done, finished) = mpsc::channel();
+    thread::spawn(move || {
+        let panicked = thread::spawn(|| panic!("oh no")).join();
+        done.send(panicked.is_err()).unwrap();
+    });
+
+    assert!(finished.recv_timeout(Duration::from_secs(5)).unwrap());
+    let record = &handler.recent_errors()[0];
+    assert_eq!(record.severity, Severity::Critical);
+    assert!(record.error.contains("panicked"));
+    assert!(record.error.contains("oh no"));
+}
@@ -0,0 +1,68 @@
+//! Reloading the configuration file while the bot is running
+
+mod common;
+
+use core::time::Duration;
+use std::{
+    env,
+    fs::{self, File},
+    path::Path,
+    process,
+    sync::Arc,
+    time::SystemTime,
+};
+
+use common::{handler, http};
+use tokio::time;
+use twilight_error::{CaptureSink, ConfigFormat, Destination};
+use twilight_model::id::Id;
+
+/// Writes the configuration, marking the file as modified at the given
+/// number of seconds from now, since the test runs faster than the
+/// resolution of the modification time
+fn write_config(path: &Path, content: &str, modified_in: u64) {
+    fs::write(path, content).unwrap();
+    File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(modified_in))
+        .unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn changes_are_applied_and_invalid_files_are_reported() {
+    let capture = Arc::new(CaptureSink::new());
+    let path = env::temp_dir().join(format!("twilight-error-reload-{}.toml", process::id()));
+    write_config(&path, "channel_id = \"1\"", 0);
+    let handler = Arc::new(handler(capture.clone()));
+    let watcher = Arc::clone(&handler).watch_config(
+        Arc::new(http()),
+        &path,
+        ConfigFormat::Toml,
+        Duration::from_secs(1),
+    );
+
+    time::sleep(Duration::from_millis(1_500)).await;
+    assert!(capture.sent().is_empty());
+
+    write_config(&path, "channel_id = \"2\"", 10);
+    time::sleep(Duration::from_secs(1)).await;
+    let reloaded = capture.sent_to(Destination::Channel(Id::new(2)));
+    assert_eq!(reloaded.len(), 1);
+    assert!(reloaded[0]
+        .content
+        .contains("Reloaded the configuration from"));
+
+    write_config(&path, "channel_id = ", 20);
+    time::sleep(Duration::from_secs(1)).await;
+    let failed = capture.sent_to(Destination::Channel(Id::new(2)));
+    assert_eq!(failed.len(), 2);
+    assert!(failed[1]
+        .content
+        .contains("Failed to reload the configuration from"));
+    assert!(capture.sent_to(Destination::Channel(Id::new(1))).is_empty());
+
+    watcher.abort();
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,68 @@
+//! Queueing errors to be handled by the reporter task
+
+mod common;
+
+use core::time::Duration;
+use std::sync::{Arc, Mutex};
+
+use common::{handler, http};
+use twilight_error::{CaptureSink, DiagnosticEvent, ErrorReport, QueueError};
+
+#[tokio::test]
+async fn queued_reports_are_handled_before_the_task_ends() {
+    let capture = Arc::new(CaptureSink::new());
+    let handler = Arc::new(handler(capture.clone()));
+    let (reporter, task) = Arc::clone(&handler).spawn_reporter(Arc::new(http()), 2);
+
+    reporter.report(ErrorReport::new("first")).await.unwrap();
+    reporter.try_report(ErrorReport::new("second")).unwrap();
+    assert_eq!(reporter.queued(), 2);
+    drop(reporter);
+    task.await.unwrap();
+
+    let sent = capture.sent();
+    assert_eq!(sent.len(), 2);
+    assert!(sent[0].content.contains("first"));
+    assert!(sent[1].content.contains("second"));
+    assert!(handler.flush(Duration::from_secs(1)).await);
+}
+
+#[tokio::test]
+async fn full_queue_returns_the_report() {
+    let capture = Arc::new(CaptureSink::new());
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let mut handler = handler(capture.clone());
+    let hook_events = Arc::clone(&events);
+    handler.diagnostics_hook(move |event| hook_events.lock().unwrap().push(event.to_string()));
+    let (reporter, task) = Arc::new(handler).spawn_reporter(Arc::new(http()), 1);
+
+    reporter.try_report(ErrorReport::new("queued")).unwrap();
+    let err = reporter
+        .try_report(ErrorReport::new("dropped"))
+        .unwrap_err();
+    assert!(matches!(err, QueueError::QueueFull(_)));
+    assert!(format!("{:?}", err.into_report()).contains("dropped"));
+    assert_eq!(
+        events.lock().unwrap().as_slice(),
+        [DiagnosticEvent::QueueFull.to_string()]
+    );
+
+    drop(reporter);
+    task.await.unwrap();
+    assert_eq!(capture.sent().len(), 1);
+}
+
+#[tokio::test]
+async fn stopped_task_closes_the_queue() {
+    let handler = Arc::new(handler(Arc::new(CaptureSink::new())));
+    let (reporter, task) = Arc::clone(&handler).spawn_reporter(Arc::new(http()), 1);
+    task.abort();
+    assert!(task.await.unwrap_err().is_cancelled());
+
+    let err = reporter
+        .report(ErrorReport::new("oh no"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, QueueError::Closed(_)));
+    assert!(handler.flush(Duration::from_secs(1)).await);
+}
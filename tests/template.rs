@@ -0,0 +1,86 @@
+//! Rendering errors sent to Discord with templates
+
+mod common;
+
+use std::sync::Arc;
+
+use chrono::Utc;
+use common::{handler, http};
+use twilight_error::{
+    CaptureSink, ErrorReport, Severity, Sink, Template, TemplateError, TemplateFormatter,
+    TimestampFormat, Timezone,
+};
+
+#[tokio::test]
+async fn template_formatter_renders_the_record() {
+    let capture = Arc::new(CaptureSink::new());
+    let mut handler = handler(capture.clone());
+    handler.formatter(
+        Sink::Channel,
+        TemplateFormatter::new(
+            Template::new(
+                "{{severity}}: {{error}}{{#if kind}} ({{kind}}){{/if}}{{#each tags}} \
+                 #{{this}}{{/each}}",
+            )
+            .unwrap(),
+        )
+        .embed_title(Template::new("{{fields.Command}}").unwrap()),
+    );
+
+    handler
+        .handle_report(
+            &http(),
+            ErrorReport::new("timed out")
+                .severity(Severity::Warning)
+                .tags(["db", "slow"])
+                .field("Command", "/play"),
+        )
+        .await;
+
+    let sent = &capture.sent()[0];
+    assert_eq!(sent.content, "\u{1f7e1} warning: timed out #db #slow");
+    assert_eq!(sent.embeds[0].title.as_deref(), Some("/play"));
+}
+
+#[tokio::test]
+async fn timestamp_is_formatted_like_the_handler() {
+    let capture = Arc::new(CaptureSink::new());
+    let mut handler = handler(capture.clone());
+    handler
+        .timestamp_format(
+            TimestampFormat::Custom("%Y-%m-%d".to_owned()),
+            Timezone::Utc,
+        )
+        .formatter(
+            Sink::Channel,
+            TemplateFormatter::new(Template::new("{{timestamp}}").unwrap())
+                .embed_title(Template::new("at {{timestamp}}").unwrap()),
+        );
+
+    handler.handle(&http(), "oh no").await;
+
+    let today = Utc::now().format("%Y-%m-%d").to_string();
+    let sent = &capture.sent()[0];
+    assert!(sent.content.ends_with(&today));
+    assert_eq!(sent.embeds[0].title, Some(format!("at {today}")));
+}
+
+#[test]
+fn invalid_templates_are_rejected() {
+    assert!(Template::new("{{error").is_err());
+    assert!(Template::new("{{#if kind}}unclosed").is_err());
+    assert!(Template::new("{{#each users}}{{/each}}").is_err());
+    assert!(Template::new("{{/if}}").is_err());
+}
+
+#[test]
+fn template_errors_are_described() {
+    assert_eq!(
+        Template::new("{{#if kind}}unclosed").unwrap_err(),
+        TemplateError::UnclosedBlock("if")
+    );
+    assert_eq!(
+        Template::new("{{#each users}}{{/each}}").unwrap_err(),
+        TemplateError::UnknownList("users".to_owned())
+    );
+}